@@ -0,0 +1,265 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate, NaiveTime};
+
+use crate::config::Config;
+use crate::content::{friendly_filename, newest_first};
+use crate::feed::{canonical_url, category_url, media_url};
+use crate::json;
+use crate::markdown::escape_html;
+use crate::template::context_value;
+use crate::value::{Table, Value};
+use crate::ContentUnit;
+
+pub const ACTOR_FILE: &str = "actor.json";
+pub const OUTBOX_FILE: &str = "outbox.json";
+const WEBFINGER_FILE: &str = ".well-known/webfinger";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+fn timestamp(date: NaiveDate) -> String {
+    date.and_time(NaiveTime::default())
+        .and_local_timezone(Local)
+        .unwrap()
+        .to_rfc3339()
+}
+
+pub fn username(cfg: &Config) -> String {
+    cfg.activitypub
+        .username
+        .clone()
+        .unwrap_or_else(|| friendly_filename(&cfg.site.title))
+}
+
+fn host(cfg: &Config) -> &str {
+    let base_url = &cfg.site.base_url;
+    let rest = base_url
+        .split_once("://")
+        .map_or(base_url.as_str(), |(_, rest)| rest);
+    &rest[..rest.find('/').unwrap_or(rest.len())]
+}
+
+pub fn actor_url(cfg: &Config) -> String {
+    category_url(cfg, ACTOR_FILE)
+}
+
+fn actor(cfg: &Config, public_key: Option<String>) -> Table {
+    let actor_url = actor_url(cfg);
+    let mut actor = Table::new();
+    actor.insert(
+        String::from("@context"),
+        Value::Array(vec![
+            Value::String(String::from("https://www.w3.org/ns/activitystreams")),
+            Value::String(String::from("https://w3id.org/security/v1")),
+        ]),
+    );
+    actor.insert(String::from("id"), Value::String(actor_url.clone()));
+    actor.insert(String::from("type"), Value::String(String::from("Person")));
+    actor.insert(
+        String::from("preferredUsername"),
+        Value::String(username(cfg)),
+    );
+    actor.insert(String::from("name"), Value::String(cfg.site.title.clone()));
+    if !cfg.site.description.is_empty() {
+        actor.insert(
+            String::from("summary"),
+            Value::String(format!("<p>{}</p>", escape_html(&cfg.site.description))),
+        );
+    }
+    actor.insert(String::from("url"), Value::String(category_url(cfg, "")));
+    actor.insert(
+        String::from("inbox"),
+        Value::String(
+            cfg.activitypub
+                .inbox
+                .clone()
+                .unwrap_or_else(|| category_url(cfg, "inbox")),
+        ),
+    );
+    actor.insert(
+        String::from("outbox"),
+        Value::String(category_url(cfg, OUTBOX_FILE)),
+    );
+    if let Some(image) = &cfg.site.image {
+        actor.insert(
+            String::from("icon"),
+            context_value([
+                ("type", Value::String(String::from("Image"))),
+                ("url", Value::String(media_url(cfg, image))),
+            ]),
+        );
+    }
+    if let Some(public_key) = public_key {
+        actor.insert(
+            String::from("publicKey"),
+            context_value([
+                ("id", Value::String(format!("{}#main-key", actor_url))),
+                ("owner", Value::String(actor_url)),
+                ("publicKeyPem", Value::String(public_key)),
+            ]),
+        );
+    }
+    actor
+}
+
+// Each post as the Create activity of an Article, which Mastodon shows as its title and link.
+fn create_activity(cfg: &Config, unit: &ContentUnit) -> Value {
+    let meta = &unit.meta;
+    let url = canonical_url(cfg, meta);
+    let actor_url = actor_url(cfg);
+    let published = timestamp(meta.date);
+    let summary = meta.summary.as_deref().unwrap_or(&unit.excerpt);
+    let mut content = format!("<p>{}</p>", escape_html(summary.trim()));
+    content.push_str(&format!(
+        "<p><a href=\"{}\">{}</a></p>",
+        escape_html(&url),
+        escape_html(&url)
+    ));
+
+    let mut article = Table::new();
+    article.insert(String::from("id"), Value::String(url.clone()));
+    article.insert(String::from("type"), Value::String(String::from("Article")));
+    article.insert(String::from("name"), Value::String(meta.name.clone()));
+    article.insert(String::from("content"), Value::String(content.clone()));
+    article.insert(String::from("url"), Value::String(url.clone()));
+    article.insert(String::from("published"), Value::String(published.clone()));
+    if let Some(updated) = meta.updated {
+        article.insert(String::from("updated"), Value::String(timestamp(updated)));
+    }
+    article.insert(
+        String::from("attributedTo"),
+        Value::String(actor_url.clone()),
+    );
+    article.insert(
+        String::from("to"),
+        Value::Array(vec![Value::String(String::from(PUBLIC))]),
+    );
+    if let Some(language) = &meta.language {
+        article.insert(
+            String::from("contentMap"),
+            Value::Table(Table::from([(language.clone(), Value::String(content))])),
+        );
+    }
+    if !meta.tags.is_empty() {
+        article.insert(
+            String::from("tag"),
+            Value::Array(
+                meta.tags
+                    .iter()
+                    .map(|tag| {
+                        context_value([
+                            ("type", Value::String(String::from("Hashtag"))),
+                            ("name", Value::String(format!("#{}", tag.replace(' ', "")))),
+                        ])
+                    })
+                    .collect(),
+            ),
+        );
+    }
+
+    context_value([
+        ("id", Value::String(format!("{}#create", url))),
+        ("type", Value::String(String::from("Create"))),
+        ("actor", Value::String(actor_url)),
+        ("published", Value::String(published)),
+        (
+            "to",
+            Value::Array(vec![Value::String(String::from(PUBLIC))]),
+        ),
+        ("object", Value::Table(article)),
+    ])
+}
+
+// Newest first, as many as the feeds carry.
+fn outbox(cfg: &Config, content: &[ContentUnit]) -> Table {
+    let mut units: Vec<&ContentUnit> = content.iter().collect();
+    units.sort_by(|a, b| newest_first(&a.meta, &b.meta));
+    if let Some(limit) = cfg.feed.limit {
+        units.truncate(limit);
+    }
+    let mut outbox = Table::new();
+    outbox.insert(
+        String::from("@context"),
+        Value::String(String::from("https://www.w3.org/ns/activitystreams")),
+    );
+    outbox.insert(
+        String::from("id"),
+        Value::String(category_url(cfg, OUTBOX_FILE)),
+    );
+    outbox.insert(
+        String::from("type"),
+        Value::String(String::from("OrderedCollection")),
+    );
+    outbox.insert(
+        String::from("totalItems"),
+        Value::Integer(units.len() as i64),
+    );
+    outbox.insert(
+        String::from("orderedItems"),
+        Value::Array(
+            units
+                .into_iter()
+                .map(|unit| create_activity(cfg, unit))
+                .collect(),
+        ),
+    );
+    outbox
+}
+
+fn webfinger(cfg: &Config) -> Table {
+    Table::from([
+        (
+            String::from("subject"),
+            Value::String(format!("acct:{}@{}", username(cfg), host(cfg))),
+        ),
+        (
+            String::from("links"),
+            Value::Array(vec![
+                context_value([
+                    ("rel", Value::String(String::from("self"))),
+                    (
+                        "type",
+                        Value::String(String::from("application/activity+json")),
+                    ),
+                    ("href", Value::String(actor_url(cfg))),
+                ]),
+                context_value([
+                    (
+                        "rel",
+                        Value::String(String::from("http://webfinger.net/rel/profile-page")),
+                    ),
+                    ("type", Value::String(String::from("text/html"))),
+                    ("href", Value::String(category_url(cfg, ""))),
+                ]),
+            ]),
+        ),
+    ])
+}
+
+fn write(path: PathBuf, table: Table) -> io::Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, json::to_string(&Value::Table(table)))?;
+    Ok(path)
+}
+
+// The actor and outbox go next to the feeds, and .well-known/webfinger at the output root points to the actor.
+pub fn write_activitypub(cfg: &Config, content: &[ContentUnit]) -> io::Result<Vec<PathBuf>> {
+    let public_key = match &cfg.activitypub.public_key {
+        Some(path) => Some(fs::read_to_string(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("[activitypub] public_key {}: {}", path.display(), err),
+            )
+        })?),
+        None => None,
+    };
+    let dir = cfg.output_root_path.join(&cfg.output_content_root_path);
+    Ok(vec![
+        write(dir.join(ACTOR_FILE), actor(cfg, public_key))?,
+        write(dir.join(OUTBOX_FILE), outbox(cfg, content))?,
+        write(cfg.output_root_path.join(WEBFINGER_FILE), webfinger(cfg))?,
+    ])
+}
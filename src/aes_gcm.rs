@@ -0,0 +1,208 @@
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+fn xtime(byte: u8) -> u8 {
+    (byte << 1) ^ if byte & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+// AES-256, encryption only, which is all counter mode needs. The state is column-major.
+struct Aes256 {
+    round_keys: [[u8; 16]; 15],
+}
+
+impl Aes256 {
+    fn new(key: &[u8; 32]) -> Aes256 {
+        let mut words = [[0u8; 4]; 60];
+        for (word, key) in words.iter_mut().zip(key.chunks_exact(4)) {
+            word.copy_from_slice(key);
+        }
+        let mut rcon = 1;
+        for i in 8..60 {
+            let mut word = words[i - 1];
+            if i % 8 == 0 {
+                word = [word[1], word[2], word[3], word[0]].map(|byte| SBOX[byte as usize]);
+                word[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if i % 8 == 4 {
+                word = word.map(|byte| SBOX[byte as usize]);
+            }
+            for (j, byte) in word.iter().enumerate() {
+                words[i][j] = words[i - 8][j] ^ byte;
+            }
+        }
+
+        let mut round_keys = [[0u8; 16]; 15];
+        for (round_key, words) in round_keys.iter_mut().zip(words.chunks_exact(4)) {
+            for (bytes, word) in round_key.chunks_exact_mut(4).zip(words) {
+                bytes.copy_from_slice(word);
+            }
+        }
+        Aes256 { round_keys }
+    }
+
+    fn encrypt_block(&self, block: [u8; 16]) -> [u8; 16] {
+        let add_round_key = |state: &mut [u8; 16], round: usize| {
+            for (byte, key) in state.iter_mut().zip(self.round_keys[round]) {
+                *byte ^= key;
+            }
+        };
+        let sub_shift = |state: &[u8; 16]| {
+            let mut shifted = [0u8; 16];
+            for (i, byte) in shifted.iter_mut().enumerate() {
+                let (column, row) = (i / 4, i % 4);
+                *byte = SBOX[state[4 * ((column + row) % 4) + row] as usize];
+            }
+            shifted
+        };
+
+        let mut state = block;
+        add_round_key(&mut state, 0);
+        for round in 1..15 {
+            state = sub_shift(&state);
+            if round < 14 {
+                for column in state.chunks_exact_mut(4) {
+                    let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+                    let all = a0 ^ a1 ^ a2 ^ a3;
+                    column[0] ^= all ^ xtime(a0 ^ a1);
+                    column[1] ^= all ^ xtime(a1 ^ a2);
+                    column[2] ^= all ^ xtime(a2 ^ a3);
+                    column[3] ^= all ^ xtime(a3 ^ a0);
+                }
+            }
+            add_round_key(&mut state, round);
+        }
+        state
+    }
+}
+
+// Multiplication in GHASH's field, with its reflected bit order.
+fn gf_mul(x: u128, y: u128) -> u128 {
+    let mut product = 0;
+    let mut v = y;
+    for i in (0..128).rev() {
+        if (x >> i) & 1 == 1 {
+            product ^= v;
+        }
+        v = if v & 1 == 1 {
+            (v >> 1) ^ (0xe1 << 120)
+        } else {
+            v >> 1
+        };
+    }
+    product
+}
+
+// AES-256-GCM with a 96 bit nonce and no associated data; the 16 byte tag follows the ciphertext.
+pub fn encrypt(key: &[u8; 32], nonce: &[u8; 12], plaintext: &[u8]) -> Vec<u8> {
+    let aes = Aes256::new(key);
+    let hash_key = u128::from_be_bytes(aes.encrypt_block([0; 16]));
+    let mut counter = [0u8; 16];
+    counter[..12].copy_from_slice(nonce);
+    counter[15] = 1;
+    let tag_mask = u128::from_be_bytes(aes.encrypt_block(counter));
+
+    let mut out = Vec::with_capacity(plaintext.len() + 16);
+    for (i, chunk) in plaintext.chunks(16).enumerate() {
+        counter[12..].copy_from_slice(&(i as u32 + 2).to_be_bytes());
+        let keystream = aes.encrypt_block(counter);
+        out.extend(chunk.iter().zip(keystream).map(|(byte, key)| byte ^ key));
+    }
+
+    let mut ghash = 0;
+    for chunk in out.chunks(16) {
+        let mut block = [0u8; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+        ghash = gf_mul(ghash ^ u128::from_be_bytes(block), hash_key);
+    }
+    // The length block: no associated data, then the ciphertext length in bits.
+    ghash = gf_mul(ghash ^ (plaintext.len() as u128 * 8), hash_key);
+    out.extend((ghash ^ tag_mask).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // What the browser does with encrypt's output: counter mode is its own inverse,
+    // and the tag must match the one sealing the recovered plaintext.
+    fn decrypt(key: &[u8; 32], nonce: &[u8; 12], sealed: &[u8]) -> Option<Vec<u8>> {
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - 16);
+        let plaintext = encrypt(key, nonce, ciphertext)[..ciphertext.len()].to_vec();
+        (encrypt(key, nonce, &plaintext)[ciphertext.len()..] == *tag).then_some(plaintext)
+    }
+
+    // Test cases 14 and 15 of the GCM specification, the AES-256 ones without associated data.
+    #[test]
+    fn encrypt_matches_gcm_test_case_14() {
+        let sealed = encrypt(&[0; 32], &[0; 12], &[0; 16]);
+        assert_eq!(
+            hex(&sealed),
+            "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919"
+        );
+    }
+
+    #[test]
+    fn encrypt_matches_gcm_test_case_15() {
+        let key = unhex("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308");
+        let nonce = unhex("cafebabefacedbaddecaf888");
+        let plaintext = unhex(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+        );
+        let sealed = encrypt(
+            &key.try_into().unwrap(),
+            &nonce.try_into().unwrap(),
+            &plaintext,
+        );
+        assert_eq!(
+            hex(&sealed),
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+             8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad\
+             b094dac5d93471bdec1a502270e3cc6c"
+        );
+    }
+
+    #[test]
+    fn decrypt_recovers_plaintext_and_rejects_tampering() {
+        let key = [7; 32];
+        let nonce = [9; 12];
+        let plaintext = b"A protected post, longer than one block and not a multiple of it.";
+        let mut sealed = encrypt(&key, &nonce, plaintext);
+        assert_eq!(sealed.len(), plaintext.len() + 16);
+        assert_eq!(
+            decrypt(&key, &nonce, &sealed).as_deref(),
+            Some(&plaintext[..])
+        );
+
+        sealed[3] ^= 1;
+        assert_eq!(decrypt(&key, &nonce, &sealed), None);
+    }
+}
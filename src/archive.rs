@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::content::{adoc_prologue, base_path, index_entry, ContentMeta};
+use crate::dates::month_name;
+
+pub type Archive = BTreeMap<u32, BTreeMap<u32, Vec<ContentMeta>>>;
+
+fn year_dir(cfg: &Config, year: u32) -> String {
+    cfg.category_path(&year.to_string())
+}
+
+fn month_dir(cfg: &Config, year: u32, month: u32) -> String {
+    format!("{}/{:02}", year_dir(cfg, year), month)
+}
+
+pub fn year_index_path(cfg: &Config, year: u32) -> String {
+    format!("{}/index.adoc", year_dir(cfg, year))
+}
+
+pub fn month_index_path(cfg: &Config, year: u32, month: u32) -> String {
+    format!("{}/index.adoc", month_dir(cfg, year, month))
+}
+
+fn year_page(cfg: &Config, year: u32, months: &BTreeMap<u32, Vec<ContentMeta>>) -> String {
+    let base_path = base_path(&year_dir(cfg, year));
+
+    let mut page = format!(
+        "{}== \u{1F5D3}\u{FE0F} {}\n\n",
+        adoc_prologue(cfg, &base_path),
+        year
+    );
+
+    for (month, entries) in months.iter().rev() {
+        page.push_str(&format!(
+            "=== xref:{:02}/index.adoc[{}]\n\n",
+            month,
+            month_name(&cfg.site.locale, *month)
+        ));
+        for meta in entries {
+            page.push_str(&format!(
+                "==== {}\n\n",
+                index_entry(cfg, meta, &format!("{}/", base_path))
+            ));
+        }
+    }
+
+    page
+}
+
+fn month_page(cfg: &Config, year: u32, month: u32, entries: &[ContentMeta]) -> String {
+    let base_path = base_path(&month_dir(cfg, year, month));
+
+    let mut page = format!(
+        "{}== \u{1F5D3}\u{FE0F} {} {}\n\n",
+        adoc_prologue(cfg, &base_path),
+        month_name(&cfg.site.locale, month),
+        year
+    );
+    page.push_str(&format!("xref:../index.adoc[All of {}]\n\n", year));
+
+    for meta in entries {
+        page.push_str(&format!(
+            "==== {}\n\n",
+            index_entry(cfg, meta, &format!("{}/", base_path))
+        ));
+    }
+
+    page
+}
+
+pub fn write_archive_pages(cfg: &Config, archive: &Archive) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+
+    for (year, months) in archive {
+        for (month, entries) in months {
+            let path = cfg
+                .output_root_path
+                .join(month_index_path(cfg, *year, *month));
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, month_page(cfg, *year, *month, entries))?;
+            written.push(path);
+        }
+
+        let path = cfg.output_root_path.join(year_index_path(cfg, *year));
+        fs::write(&path, year_page(cfg, *year, months))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
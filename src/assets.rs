@@ -0,0 +1,238 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::content::{ContentFormat, ContentMeta};
+use crate::images;
+use crate::tools::is_fresh;
+use crate::url;
+
+const ASCIIDOC_MACROS: [&str; 4] = ["image:", "video:", "audio:", "link:"];
+const HTML_ATTRIBUTES: [&str; 2] = ["src=\"", "href=\""];
+
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// Where copy_assets puts a file from the input, as a path from the output root.
+pub fn output_url(cfg: &Config, asset: &Path) -> String {
+    let asset = normalize(asset);
+    let input_root = normalize(&cfg.input_content_root_path);
+    let relative = asset.strip_prefix(&input_root).unwrap_or(&asset);
+    cfg.category_path(&url::from_path(relative))
+}
+
+pub fn is_local(target: &str) -> bool {
+    !target.is_empty()
+        && !target.contains("://")
+        && !target.starts_with(['/', '#', '{', '?'])
+        && !target.starts_with("mailto:")
+        && !target.starts_with("data:")
+}
+
+struct Rewriter<'a> {
+    cfg: &'a Config,
+    source_dir: &'a Path,
+    input_root: PathBuf,
+    prefix: String,
+    assets: BTreeSet<PathBuf>,
+}
+
+impl Rewriter<'_> {
+    fn rewrite(&mut self, marker: &str, target: &str) -> Option<String> {
+        if !is_local(target) {
+            return None;
+        }
+        let (file, suffix) = match target.find(['#', '?']) {
+            Some(at) => target.split_at(at),
+            None => (target, ""),
+        };
+
+        let asset = normalize(&self.source_dir.join(file));
+        if !asset.is_file() || ContentFormat::is_content_file(&asset) {
+            return None;
+        }
+        let relative = asset.strip_prefix(&self.input_root).ok()?;
+
+        let rewritten = format!(
+            "{}{}",
+            self.prefix,
+            self.cfg.category_path(&url::from_path(relative))
+        );
+        self.assets.insert(asset);
+
+        if !self.cfg.images.enabled || !images::is_raster(&rewritten) {
+            return Some(format!("{}{}", rewritten, suffix));
+        }
+        let optimized = images::optimized(self.cfg, &rewritten);
+        match marker {
+            "image:" => Some(format!("{}{}", optimized, suffix)),
+            "src=\"" => {
+                let srcset = images::srcset(self.cfg, &rewritten);
+                if srcset.is_empty() {
+                    Some(format!("{}{}", optimized, suffix))
+                } else {
+                    // The closing quote of src now closes the added srcset.
+                    Some(format!("{}{}\" srcset=\"{}", optimized, suffix, srcset))
+                }
+            }
+            _ => Some(format!("{}{}", rewritten, suffix)),
+        }
+    }
+}
+
+fn rewrite_targets<F>(text: &str, markers: &[&str], end: F, rewriter: &mut Rewriter) -> String
+where
+    F: Fn(char) -> bool,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((at, marker)) = markers
+        .iter()
+        .filter_map(|marker| rest.find(marker).map(|at| (at, *marker)))
+        .min()
+    {
+        let word_start = rest[..at]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        let mut start = at + marker.len();
+        if marker.ends_with(':') && rest[start..].starts_with(':') {
+            start += 1;
+        }
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let len = rest.find(&end).unwrap_or(rest.len());
+        let target = &rest[..len];
+        if !word_start || target.chars().any(char::is_whitespace) {
+            continue;
+        }
+        match rewriter.rewrite(marker, target) {
+            Some(rewritten) => out.push_str(&rewritten),
+            None => out.push_str(target),
+        }
+        rest = &rest[len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+pub fn rewrite_references(
+    cfg: &Config,
+    meta: &ContentMeta,
+    source: &Path,
+    text: &str,
+    html: bool,
+) -> (String, BTreeSet<PathBuf>) {
+    let mut rewriter = Rewriter {
+        cfg,
+        source_dir: source.parent().unwrap_or(Path::new("")),
+        input_root: normalize(&cfg.input_content_root_path),
+        prefix: meta.path.split('/').map(|_| "../").collect(),
+        assets: BTreeSet::new(),
+    };
+
+    let rewritten = if html {
+        rewrite_targets(text, &HTML_ATTRIBUTES, |c| c == '"', &mut rewriter)
+    } else {
+        rewrite_targets(text, &ASCIIDOC_MACROS, |c| c == '[', &mut rewriter)
+    };
+    (rewritten, rewriter.assets)
+}
+
+// A copy is fresh when it is also the same size as the file.
+fn is_copied(source: &Path, target: &Path) -> bool {
+    let len = |path: &Path| fs::metadata(path).map(|metadata| metadata.len());
+    matches!((len(source), len(target)), (Ok(source), Ok(target)) if source == target)
+        && is_fresh(source, target)
+}
+
+fn copy(source: &Path, target: &Path) -> io::Result<bool> {
+    if is_copied(source, target) {
+        return Ok(false);
+    }
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(source, target)?;
+    Ok(true)
+}
+
+pub fn copy_assets(cfg: &Config, assets: &BTreeSet<PathBuf>) -> io::Result<Vec<PathBuf>> {
+    let input_root = normalize(&cfg.input_content_root_path);
+    let output_root = cfg.output_root_path.join(&cfg.output_content_root_path);
+
+    let mut copied = 0;
+    let mut written = Vec::with_capacity(assets.len());
+    for asset in assets {
+        // Rendered diagrams are written straight to the output.
+        if asset.starts_with(&cfg.output_root_path) {
+            written.push(asset.clone());
+            continue;
+        }
+        let relative = match asset.strip_prefix(&input_root) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let target = output_root.join(relative);
+        if copy(asset, &target)? {
+            copied += 1;
+        }
+        written.push(target);
+    }
+
+    if copied > 0 {
+        eprintln!("copied {} assets.", copied);
+    }
+    Ok(written)
+}
+
+fn copy_dir(source: &Path, target: &Path, written: &mut Vec<PathBuf>) -> io::Result<usize> {
+    let mut copied = 0;
+    for dir_entry in fs::read_dir(source)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        let target = target.join(dir_entry.file_name());
+        if path.is_dir() {
+            copied += copy_dir(&path, &target, written)?;
+            continue;
+        }
+        if copy(&path, &target)? {
+            copied += 1;
+        }
+        written.push(target);
+    }
+    Ok(copied)
+}
+
+pub fn copy_static(cfg: &Config) -> io::Result<Vec<PathBuf>> {
+    let static_root = match &cfg.static_root_path {
+        Some(static_root) => static_root,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut written = Vec::new();
+    let copied = copy_dir(static_root, &cfg.output_root_path, &mut written)?;
+    if copied > 0 {
+        eprintln!("copied {} static files.", copied);
+    }
+    Ok(written)
+}
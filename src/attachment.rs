@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::assets;
+use crate::config::Config;
+use crate::content::{base_path, meta_context, ContentMeta, Enclosure, DRAFT_PREFIX};
+use crate::template::Templates;
+use crate::url;
+use crate::value::Value;
+
+const MIME_TYPES: [(&str, &str); 7] = [
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+];
+
+#[derive(Clone, Debug)]
+pub struct Attachment {
+    pub mime_type: &'static str,
+    pub length: u64,
+    // The file's URL from the output root, set by the build.
+    pub url: String,
+}
+
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| *known == ext)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+// A PDF or image named like a post, 2024-05-01_Slides.pdf; others stay assets.
+pub fn is_attachment_post(path: &Path) -> bool {
+    let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) => stem.strip_prefix(DRAFT_PREFIX).unwrap_or(stem),
+        None => return false,
+    };
+    mime_type(path).is_some()
+        && stem.get(..11).is_some_and(|prefix| {
+            prefix.ends_with('_') && NaiveDate::parse_from_str(&prefix[..10], "%Y-%m-%d").is_ok()
+        })
+}
+
+// "1.2 MB", as the download link gives the size.
+fn size(length: u64) -> String {
+    match length {
+        0..1024 => format!("{} bytes", length),
+        1024..1_048_576 => format!("{:.1} KB", length as f64 / 1024.0),
+        _ => format!("{:.1} MB", length as f64 / 1_048_576.0),
+    }
+}
+
+// Fills in the file's URL and, unless the front matter gives one, makes the file the feed enclosure.
+pub fn resolve(cfg: &Config, meta: &mut ContentMeta, source: &Path) {
+    let attachment = match &mut meta.attachment {
+        Some(attachment) => attachment,
+        None => return,
+    };
+    attachment.url = url::encode_path(&assets::output_url(cfg, source));
+    if meta.enclosure.is_none() {
+        meta.enclosure = Some(Enclosure {
+            url: attachment.url.clone(),
+            length: attachment.length,
+            mime_type: attachment.mime_type.to_string(),
+            duration: None,
+            episode: None,
+            season: None,
+            explicit: None,
+        });
+    }
+}
+
+// The wrapper page's body, from the attachment.adoc template.
+pub fn attachment_body(meta: &ContentMeta, source: &Path, templates: &Templates) -> String {
+    let attachment = match &meta.attachment {
+        Some(attachment) => attachment,
+        None => return String::new(),
+    };
+    let href = match base_path(&meta.path) {
+        base if base.is_empty() => attachment.url.clone(),
+        base => format!("{}/{}", base, attachment.url),
+    };
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let kind = source
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_uppercase())
+        .unwrap_or_default();
+
+    let mut context = meta_context(meta);
+    context.insert(String::from("href"), Value::String(href));
+    context.insert(String::from("file_name"), Value::String(file_name));
+    context.insert(String::from("type"), Value::String(kind));
+    context.insert(
+        String::from("mime_type"),
+        Value::String(attachment.mime_type.to_string()),
+    );
+    context.insert(String::from("size"), Value::String(size(attachment.length)));
+    context.insert(
+        String::from("image"),
+        Value::Boolean(attachment.mime_type.starts_with("image/")),
+    );
+    // Templates lose their last newline, which the body needs before the footer.
+    templates.attachment.render(&context) + "\n"
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::content::{
+    adoc_prologue, base_path, friendly_filename, index_entry, newest_first, ContentMeta,
+    ContentUnit,
+};
+use crate::error::WebWeaverError;
+use crate::feed;
+use crate::toml;
+use crate::url;
+use crate::value::Value;
+
+const AUTHORS_DIR: &str = "authors";
+
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    // AsciiDoc, shown under the author's name.
+    pub bio: Option<String>,
+    // An image, relative to the output root or absolute.
+    pub avatar: Option<String>,
+    pub url: Option<String>,
+}
+
+// Profiles by author slug.
+pub type Profiles = BTreeMap<String, Profile>;
+
+fn profile_err(path: &Path, message: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message.to_string()),
+    )
+}
+
+// authors.toml: a table per author, keyed by the name posts give, with bio, avatar and url.
+pub fn load_profiles(path: &Path) -> io::Result<Profiles> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let table = toml::parse(&source).map_err(|err| profile_err(path, err))?;
+
+    let mut profiles = Profiles::new();
+    for (author, value) in &table {
+        if !matches!(value, Value::Table(_)) {
+            return Err(profile_err(path, format!("{} must be a table.", author)));
+        }
+        let field = |key: &str| match value.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(profile_err(
+                path,
+                format!("{}.{} must be a string.", author, key),
+            )),
+        };
+        let profile = Profile {
+            bio: field("bio")?,
+            avatar: field("avatar")?,
+            url: field("url")?,
+        };
+        let slug = author_slug(author);
+        if profiles.insert(slug, profile).is_some() {
+            return Err(profile_err(
+                path,
+                format!("more than one profile for {}.", author),
+            ));
+        }
+    }
+    Ok(profiles)
+}
+
+pub struct Author<'a> {
+    pub name: String,
+    pub entries: Vec<&'a ContentUnit>,
+}
+
+pub fn author_slug(author: &str) -> String {
+    friendly_filename(author)
+}
+
+fn author_dir(author: &str) -> String {
+    format!("{}/{}", AUTHORS_DIR, author_slug(author))
+}
+
+pub fn author_index_path(cfg: &Config, author: &str) -> String {
+    cfg.category_path(&format!("{}/index.adoc", author_dir(author)))
+}
+
+pub fn is_multi_author<'a, I>(metas: I) -> bool
+where
+    I: IntoIterator<Item = &'a ContentMeta>,
+{
+    let mut first: Option<String> = None;
+    for slug in metas
+        .into_iter()
+        .filter_map(|meta| meta.author.as_deref())
+        .map(author_slug)
+        .filter(|slug| !slug.is_empty())
+    {
+        match &first {
+            None => first = Some(slug),
+            Some(first) if *first != slug => return true,
+            Some(_) => {}
+        }
+    }
+    false
+}
+
+// Authors get pages when posts have more than one, or when authors.toml gives profiles.
+pub fn has_author_pages<'a, I>(cfg: &Config, metas: I) -> bool
+where
+    I: IntoIterator<Item = &'a ContentMeta>,
+{
+    !cfg.author_profiles.is_empty() || is_multi_author(metas)
+}
+
+pub fn author_map(content: &[ContentUnit]) -> BTreeMap<String, Author<'_>> {
+    let mut authors: BTreeMap<String, Author> = BTreeMap::new();
+
+    for unit in content {
+        let name = match &unit.meta.author {
+            Some(name) => name,
+            None => continue,
+        };
+        let slug = author_slug(name);
+        if slug.is_empty() {
+            continue;
+        }
+        authors
+            .entry(slug)
+            .or_insert_with(|| Author {
+                name: name.clone(),
+                entries: Vec::new(),
+            })
+            .entries
+            .push(unit);
+    }
+
+    for author in authors.values_mut() {
+        author
+            .entries
+            .sort_by(|a, b| newest_first(&a.meta, &b.meta));
+    }
+
+    authors
+}
+
+fn author_page(cfg: &Config, author: &Author) -> String {
+    let base_path = base_path(&cfg.category_path(&author_dir(&author.name)));
+
+    let mut page = format!(
+        "{}== \u{270D}\u{FE0F} {}\n\n",
+        adoc_prologue(cfg, &base_path),
+        author.name
+    );
+
+    if let Some(profile) = cfg.author_profiles.get(&author_slug(&author.name)) {
+        if let Some(avatar) = &profile.avatar {
+            let target = if url::is_absolute(avatar) {
+                avatar.clone()
+            } else {
+                format!("{}/{}", base_path, avatar.trim_start_matches('/'))
+            };
+            page.push_str(&format!(
+                "image::{}[{},128,role=avatar]\n\n",
+                target, author.name
+            ));
+        }
+        if let Some(bio) = &profile.bio {
+            page.push_str(bio.trim());
+            page.push_str("\n\n");
+        }
+        if let Some(homepage) = &profile.url {
+            page.push_str(&format!("\u{1F517} {}[]\n\n", homepage));
+        }
+    }
+
+    page.push_str(&feed::listing_feed_links(cfg));
+
+    for unit in &author.entries {
+        page.push_str(&format!(
+            "==== {}\n\n",
+            index_entry(cfg, &unit.meta, &format!("{}/", base_path))
+        ));
+    }
+
+    page
+}
+
+pub fn write_author_pages(
+    cfg: &Config,
+    content: &[ContentUnit],
+) -> Result<Vec<PathBuf>, WebWeaverError> {
+    if !has_author_pages(cfg, content.iter().map(|unit| &unit.meta)) {
+        return Ok(Vec::new());
+    }
+
+    let mut written = Vec::new();
+    for author in author_map(content).values() {
+        let dir = author_dir(&author.name);
+        let title = format!("{} \u{2014} {}", cfg.site.title, author.name);
+        let page_url = url::join(&cfg.site.base_url, &author_index_path(cfg, &author.name));
+
+        let path = cfg
+            .output_root_path
+            .join(author_index_path(cfg, &author.name));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, author_page(cfg, author))?;
+        written.push(path);
+
+        written.extend(feed::write_listing_feeds(
+            cfg,
+            &author.entries,
+            &dir,
+            &title,
+            &page_url,
+        )?);
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::config::{Config, LinkCheck};
+use crate::json;
+use crate::url;
+use crate::value::{Table, Value};
+
+pub const CACHE_FILE: &str = ".webweaver-cache.json";
+const CACHE_VERSION: i64 = 3;
+
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    pub output: String,
+    pub output_hash: String,
+    pub category: String,
+    pub render: Render,
+}
+
+// What rendering a source gave besides its page, reused while neither the source
+// nor anything else the render read has changed.
+#[derive(Clone, Debug, Default)]
+pub struct Render {
+    pub source_hash: String,
+    pub source_mtime: i64,
+    // The post's metadata and series navigation, the config and the templates.
+    pub render_hash: String,
+    pub excerpt: String,
+    pub description: String,
+    pub contents: String,
+    pub assets: Vec<String>,
+}
+
+// Each output is owned by the category whose build wrote it, by its output path.
+// A build of one category only prunes that category's outputs and keeps the others' entries.
+pub struct BuildCache {
+    path: PathBuf,
+    root: PathBuf,
+    scope: Option<String>,
+    category: String,
+    previous: BTreeMap<String, CacheEntry>,
+    current: BTreeMap<String, CacheEntry>,
+    previous_generated: BTreeMap<String, String>,
+    current_generated: BTreeMap<String, String>,
+}
+
+// FNV-1a, fed a piece at a time; the pieces hash as they would joined.
+pub struct Hasher(u64);
+
+impl Default for Hasher {
+    fn default() -> Hasher {
+        Hasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hasher = Hasher::default();
+    hasher.update(bytes);
+    hasher.finish()
+}
+
+// Nanoseconds since the epoch.
+pub fn file_mtime(path: &Path) -> io::Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    let since = modified
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?;
+    Ok(since.as_nanos() as i64)
+}
+
+// The config as far as it can change a page; keys that only steer the build are left out,
+// so a different --jobs or a terminal for progress bars does not render everything again.
+pub fn config_fingerprint(cfg: &Config) -> String {
+    let mut cfg = cfg.clone();
+    cfg.output_root_path = PathBuf::new();
+    cfg.build.incremental = false;
+    cfg.build.clean = false;
+    cfg.build.atomic = false;
+    cfg.build.force = false;
+    cfg.build.progress = false;
+    cfg.build.profile = false;
+    cfg.build.prune = false;
+    cfg.build.jobs = 0;
+    cfg.build.watch = false;
+    cfg.build.watch_interval_ms = 0;
+    cfg.build.keep_going = false;
+    cfg.build.manifest = false;
+    cfg.build.minify = false;
+    cfg.build.checksums = false;
+    cfg.build.check_links = LinkCheck::Off;
+    content_hash(format!("{:?}", cfg).as_bytes())
+}
+
+// The content_hash of a file, read in blocks.
+pub fn file_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::default();
+    let mut block = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut block)? {
+            0 => return Ok(hasher.finish()),
+            read => hasher.update(&block[..read]),
+        }
+    }
+}
+
+fn entry_from_value(value: &Value) -> Option<CacheEntry> {
+    let string = |key: &str| value.get(key)?.as_str().map(String::from);
+    Some(CacheEntry {
+        output: string("output")?,
+        output_hash: string("output_hash")?,
+        category: string("category")?,
+        render: Render {
+            source_hash: string("source_hash")?,
+            source_mtime: value.get("source_mtime")?.as_integer()?,
+            render_hash: string("render_hash")?,
+            excerpt: string("excerpt")?,
+            description: string("description")?,
+            contents: string("contents")?,
+            assets: value
+                .get("assets")?
+                .as_array()?
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+        },
+    })
+}
+
+fn entry_to_value(entry: &CacheEntry) -> Value {
+    let mut table = Table::new();
+    table.insert(String::from("output"), Value::String(entry.output.clone()));
+    table.insert(
+        String::from("output_hash"),
+        Value::String(entry.output_hash.clone()),
+    );
+    table.insert(
+        String::from("category"),
+        Value::String(entry.category.clone()),
+    );
+    let render = &entry.render;
+    for (key, value) in [
+        ("source_hash", &render.source_hash),
+        ("render_hash", &render.render_hash),
+        ("excerpt", &render.excerpt),
+        ("description", &render.description),
+        ("contents", &render.contents),
+    ] {
+        table.insert(String::from(key), Value::String(value.clone()));
+    }
+    table.insert(
+        String::from("source_mtime"),
+        Value::Integer(render.source_mtime),
+    );
+    table.insert(
+        String::from("assets"),
+        Value::Array(render.assets.iter().cloned().map(Value::String).collect()),
+    );
+    Value::Table(table)
+}
+
+impl BuildCache {
+    // scope is the output path of the one category being built, or None when building them all.
+    pub fn load(output_root_path: &Path, scope: Option<&Path>) -> BuildCache {
+        let path = output_root_path.join(CACHE_FILE);
+        let mut previous = BTreeMap::new();
+        let mut previous_generated = BTreeMap::new();
+
+        if let Ok(contents) = fs::read_to_string(&path) {
+            match json::parse(&contents) {
+                Ok(value)
+                    if value.get("version").and_then(Value::as_integer) == Some(CACHE_VERSION) =>
+                {
+                    if let Some(Value::Table(files)) = value.get("files") {
+                        for (source, entry) in files {
+                            if let Some(entry) = entry_from_value(entry) {
+                                previous.insert(source.clone(), entry);
+                            }
+                        }
+                    }
+                    if let Some(Value::Table(generated)) = value.get("generated") {
+                        for (output, category) in generated {
+                            if let Some(category) = category.as_str() {
+                                previous_generated.insert(output.clone(), category.to_string());
+                            }
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!(
+                    "warning: ignoring unreadable build cache {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        BuildCache {
+            path,
+            root: output_root_path.to_path_buf(),
+            scope: scope.map(url::from_path),
+            category: scope.map(url::from_path).unwrap_or_default(),
+            previous,
+            current: BTreeMap::new(),
+            previous_generated,
+            current_generated: BTreeMap::new(),
+        }
+    }
+
+    // Outputs recorded from here on belong to the category at this output path.
+    pub fn enter(&mut self, category: &Path) {
+        self.category = url::from_path(category);
+    }
+
+    fn in_scope(&self, category: &str) -> bool {
+        self.scope.as_deref().is_none_or(|scope| scope == category)
+    }
+
+    fn key(source: &Path) -> String {
+        source.to_string_lossy().into_owned()
+    }
+
+    pub fn is_fresh(
+        &self,
+        source: &Path,
+        output: &str,
+        output_hash: &str,
+        output_path: &Path,
+    ) -> bool {
+        match self.previous.get(&Self::key(source)) {
+            Some(entry) => {
+                entry.output == output && entry.output_hash == output_hash && output_path.exists()
+            }
+            None => false,
+        }
+    }
+
+    // The last render of an unchanged source whose output is still there: same render_hash,
+    // and the same mtime or, for a file touched but not edited, the same contents.
+    pub fn unchanged(
+        &self,
+        source: &Path,
+        source_mtime: i64,
+        render_hash: &str,
+        output_path: &Path,
+    ) -> Option<&CacheEntry> {
+        let entry = self.previous.get(&Self::key(source))?;
+        let render = &entry.render;
+        let same_source = render.source_mtime == source_mtime
+            || file_hash(source).is_ok_and(|hash| hash == render.source_hash);
+        (render.render_hash == render_hash && same_source && output_path.exists()).then_some(entry)
+    }
+
+    pub fn record(&mut self, source: &Path, output: &str, output_hash: String, render: Render) {
+        self.current.insert(
+            Self::key(source),
+            CacheEntry {
+                output: output.to_string(),
+                output_hash,
+                category: self.category.clone(),
+                render,
+            },
+        );
+    }
+
+    pub fn keep(&mut self, source: &Path) {
+        let key = Self::key(source);
+        if let Some(entry) = self.previous.get(&key) {
+            self.current.insert(key, entry.clone());
+        }
+    }
+
+    pub fn generated(&mut self, output_path: &Path) {
+        let output = output_path.strip_prefix(&self.root).unwrap_or(output_path);
+        self.current_generated
+            .insert(url::from_path(output), self.category.clone());
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, CacheEntry> {
+        &self.current
+    }
+
+    pub fn generated_outputs(&self) -> impl Iterator<Item = &String> {
+        self.current_generated.keys()
+    }
+
+    pub fn outputs(&self) -> Vec<String> {
+        self.current
+            .values()
+            .map(|entry| entry.output.clone())
+            .chain(self.current_generated.keys().cloned())
+            .collect()
+    }
+
+    // The outputs of the categories this build leaves alone.
+    pub fn other_outputs(&self) -> BTreeSet<String> {
+        self.previous
+            .values()
+            .filter(|entry| !self.in_scope(&entry.category))
+            .map(|entry| entry.output.clone())
+            .chain(
+                self.previous_generated
+                    .iter()
+                    .filter(|(_, category)| !self.in_scope(category))
+                    .map(|(output, _)| output.clone()),
+            )
+            .collect()
+    }
+
+    pub fn stale_outputs(&self) -> Vec<String> {
+        let mut current: BTreeSet<String> = self.outputs().into_iter().collect();
+        current.extend(self.other_outputs());
+
+        let previous: BTreeSet<&str> = self
+            .previous
+            .values()
+            .filter(|entry| self.in_scope(&entry.category))
+            .map(|entry| entry.output.as_str())
+            .chain(
+                self.previous_generated
+                    .iter()
+                    .filter(|(_, category)| self.in_scope(category))
+                    .map(|(output, _)| output.as_str()),
+            )
+            .collect();
+
+        previous
+            .into_iter()
+            .filter(|output| !current.contains(*output))
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut files = Table::new();
+        for (source, entry) in &self.previous {
+            if !self.in_scope(&entry.category) {
+                files.insert(source.clone(), entry_to_value(entry));
+            }
+        }
+        for (source, entry) in &self.current {
+            files.insert(source.clone(), entry_to_value(entry));
+        }
+
+        let mut generated = Table::new();
+        for (output, category) in self
+            .previous_generated
+            .iter()
+            .filter(|(_, category)| !self.in_scope(category))
+            .chain(&self.current_generated)
+        {
+            generated.insert(output.clone(), Value::String(category.clone()));
+        }
+
+        let mut root = Table::new();
+        root.insert(String::from("version"), Value::Integer(CACHE_VERSION));
+        root.insert(String::from("files"), Value::Table(files));
+        root.insert(String::from("generated"), Value::Table(generated));
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, json::to_string(&Value::Table(root)))
+    }
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::manifest::MANIFEST_FILE;
+use crate::parallel;
+use crate::sha256;
+
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+// One `<sha256>  <path>` line per output, which `sha256sum -c SHA256SUMS` checks from the output root.
+pub fn write_checksums(cfg: &Config, outputs: &[String]) -> io::Result<PathBuf> {
+    let mut outputs: Vec<&String> = outputs
+        .iter()
+        .filter(|output| *output != CHECKSUMS_FILE && *output != MANIFEST_FILE)
+        .collect();
+    outputs.sort();
+    outputs.dedup();
+
+    let hashes = parallel::map(cfg.build.jobs, &outputs, |output| {
+        match fs::read(cfg.output_root_path.join(output)) {
+            Ok(bytes) => Ok(Some(sha256::hex_digest(&bytes))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    });
+
+    let mut sums = String::new();
+    for (output, hash) in outputs.iter().zip(hashes) {
+        if let Some(hash) = hash? {
+            sums.push_str(&format!("{}  {}\n", hash, output));
+        }
+    }
+
+    let path = cfg.output_root_path.join(CHECKSUMS_FILE);
+    fs::write(&path, sums)?;
+    Ok(path)
+}
@@ -0,0 +1,773 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::content::{ContentFormat, ContentMeta};
+use crate::emoji::BodyScan;
+use crate::json;
+use crate::markdown::escape_html;
+use crate::value::Value;
+
+// Stand in for citations while the Markdown or Org converter runs, as math::protect's placeholders do.
+const CITE_START: char = '\u{e004}';
+const CITE_END: char = '\u{e005}';
+
+#[derive(Clone, Debug, Default)]
+pub struct Entry {
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+    pub title: Option<String>,
+    // The journal, proceedings or book the work appeared in.
+    pub container: Option<String>,
+    pub volume: Option<String>,
+    pub issue: Option<String>,
+    pub pages: Option<String>,
+    pub publisher: Option<String>,
+    pub url: Option<String>,
+}
+
+// Entries by citation key.
+pub type Bibliography = BTreeMap<String, Entry>;
+
+fn bibliography_err(path: &Path, message: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message.to_string()),
+    )
+}
+
+// Reads the [citations] bibliography: CSL-JSON for a .json file, BibTeX otherwise.
+pub fn load(path: &Path) -> io::Result<Bibliography> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    {
+        csl_json(&source).map_err(|err| bibliography_err(path, err))
+    } else {
+        bibtex(&source).map_err(|err| bibliography_err(path, err))
+    }
+}
+
+// LaTeX accents, as the combining marks that follow their letter.
+const ACCENTS: [(char, char); 7] = [
+    ('\'', '\u{301}'),
+    ('`', '\u{300}'),
+    ('"', '\u{308}'),
+    ('^', '\u{302}'),
+    ('~', '\u{303}'),
+    ('=', '\u{304}'),
+    ('.', '\u{307}'),
+];
+
+// Accents named by a letter, like \H{o}.
+const LETTER_ACCENTS: [(&str, char); 6] = [
+    ("H", '\u{30b}'),
+    ("c", '\u{327}'),
+    ("v", '\u{30c}'),
+    ("u", '\u{306}'),
+    ("k", '\u{328}'),
+    ("r", '\u{30a}'),
+];
+
+// Letters LaTeX writes as commands.
+const LETTERS: [(&str, &str); 10] = [
+    ("ss", "ß"),
+    ("o", "ø"),
+    ("O", "Ø"),
+    ("ae", "æ"),
+    ("AE", "Æ"),
+    ("aa", "å"),
+    ("AA", "Å"),
+    ("l", "ł"),
+    ("L", "Ł"),
+    ("i", "ı"),
+];
+
+// A BibTeX value as text: braces dropped, accents and escapes resolved,
+// other commands like \emph reduced to their argument.
+fn latex_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '~' => out.push(' '),
+            '\\' => match chars.peek().copied() {
+                Some(accent) if ACCENTS.iter().any(|(c, _)| *c == accent) => {
+                    chars.next();
+                    let letter = chars.find(|c| *c != '{' && *c != '\\' && *c != ' ');
+                    if let Some(letter) = letter {
+                        out.push(letter);
+                        out.extend(
+                            ACCENTS
+                                .iter()
+                                .filter(|(c, _)| *c == accent)
+                                .map(|(_, mark)| mark),
+                        );
+                    }
+                }
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let mut command = String::new();
+                    while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                        command.push(*c);
+                        chars.next();
+                    }
+                    if let Some((_, letter)) = LETTERS.iter().find(|(name, _)| *name == command) {
+                        out.push_str(letter);
+                    } else if let Some((_, mark)) =
+                        LETTER_ACCENTS.iter().find(|(name, _)| *name == command)
+                    {
+                        if let Some(letter) = chars.find(|c| *c != '{' && *c != ' ') {
+                            out.push(letter);
+                            out.push(*mark);
+                        }
+                    }
+                }
+                Some(c) => {
+                    out.push(c);
+                    chars.next();
+                }
+                None => {}
+            },
+            c => out.push(c),
+        }
+    }
+    out.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .replace("---", "—")
+        .replace("--", "–")
+}
+
+// The text between an opening brace or quote at i and its close, which i is moved past.
+fn delimited(chars: &[char], i: &mut usize, close: char) -> Result<String, String> {
+    let mut depth = 0;
+    let start = *i + 1;
+    for (at, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if *c == close && depth == 0 => {
+                *i = at + 1;
+                return Ok(chars[start..at].iter().collect());
+            }
+            _ => {}
+        }
+    }
+    Err(String::from("unbalanced braces or quotes."))
+}
+
+fn skip_space(chars: &[char], i: &mut usize) {
+    while chars.get(*i).is_some_and(|c| c.is_whitespace()) {
+        *i += 1;
+    }
+}
+
+fn word(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while chars
+        .get(*i)
+        .is_some_and(|c| !c.is_whitespace() && !",={}()#\"".contains(*c))
+    {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect()
+}
+
+// A field value: braced, quoted, a number or an @string name, joined by #.
+fn bibtex_value(
+    chars: &[char],
+    i: &mut usize,
+    strings: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let mut value = String::new();
+    loop {
+        skip_space(chars, i);
+        match chars.get(*i) {
+            Some('{') => value.push_str(&delimited(chars, i, '}')?),
+            Some('"') => value.push_str(&delimited(chars, i, '"')?),
+            Some(_) => {
+                let word = word(chars, i);
+                if word.is_empty() {
+                    return Err(String::from("a field has no value."));
+                }
+                match strings.get(&word.to_lowercase()) {
+                    Some(text) => value.push_str(text),
+                    None => value.push_str(&word),
+                }
+            }
+            None => return Err(String::from("the file ends inside an entry.")),
+        }
+        skip_space(chars, i);
+        if chars.get(*i) != Some(&'#') {
+            return Ok(value);
+        }
+        *i += 1;
+    }
+}
+
+// Splits an author field at its top-level "and"s.
+fn bibtex_names(value: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut name = String::new();
+    for token in value.split(' ') {
+        if depth == 0 && token == "and" {
+            names.push(std::mem::take(&mut name));
+            continue;
+        }
+        depth += token.matches('{').count() as i32 - token.matches('}').count() as i32;
+        if !name.is_empty() {
+            name.push(' ');
+        }
+        name.push_str(token);
+    }
+    names.push(name);
+    names
+        .iter()
+        .map(|name| latex_text(name))
+        .filter(|name| !name.is_empty())
+        // "Last, First" reads as "First Last".
+        .map(|name| match name.split_once(',') {
+            Some((last, first)) => format!("{} {}", first.trim(), last.trim()),
+            None => name,
+        })
+        .collect()
+}
+
+fn doi_url(doi: &str) -> String {
+    if doi.starts_with("http") {
+        return doi.to_string();
+    }
+    format!("https://doi.org/{}", doi)
+}
+
+fn bibtex_entry(fields: &BTreeMap<String, String>) -> Entry {
+    let field = |names: &[&str]| {
+        names
+            .iter()
+            .filter_map(|name| fields.get(*name))
+            .map(|value| latex_text(value))
+            .find(|value| !value.is_empty())
+    };
+    let authors = ["author", "editor"]
+        .iter()
+        .filter_map(|name| fields.get(*name))
+        .map(|value| bibtex_names(&value.replace('\n', " ")))
+        .find(|names| !names.is_empty())
+        .unwrap_or_default();
+    Entry {
+        authors,
+        year: field(&["year"])
+            .or_else(|| field(&["date"]).map(|date| date.chars().take(4).collect())),
+        title: field(&["title"]),
+        container: field(&["journal", "journaltitle", "booktitle"]),
+        volume: field(&["volume"]),
+        issue: field(&["number", "issue"]),
+        pages: field(&["pages"]),
+        publisher: field(&["publisher", "institution", "school", "organization"]),
+        url: field(&["url"]).or_else(|| field(&["doi"]).map(|doi| doi_url(&doi))),
+    }
+}
+
+fn bibtex(source: &str) -> Result<Bibliography, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut entries = Bibliography::new();
+    let mut strings: BTreeMap<String, String> = BTreeMap::new();
+    let mut i = 0;
+    while let Some(at) = chars[i..].iter().position(|c| *c == '@') {
+        i += at + 1;
+        let kind = word(&chars, &mut i).to_lowercase();
+        skip_space(&chars, &mut i);
+        let close = match chars.get(i) {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => continue,
+        };
+        if kind == "comment" || kind == "preamble" {
+            delimited(&chars, &mut i, close)?;
+            continue;
+        }
+        i += 1;
+        skip_space(&chars, &mut i);
+        let key = match kind.as_str() {
+            "string" => None,
+            _ => {
+                let key = word(&chars, &mut i);
+                if key.is_empty() {
+                    return Err(format!("an @{} entry has no key.", kind));
+                }
+                Some(key)
+            }
+        };
+
+        let mut fields = BTreeMap::new();
+        loop {
+            while chars.get(i).is_some_and(|c| c.is_whitespace() || *c == ',') {
+                i += 1;
+            }
+            match chars.get(i) {
+                Some(c) if *c == close => {
+                    i += 1;
+                    break;
+                }
+                None => return Err(String::from("the file ends inside an entry.")),
+                Some(_) => {}
+            }
+            let name = word(&chars, &mut i).to_lowercase();
+            skip_space(&chars, &mut i);
+            if chars.get(i) != Some(&'=') {
+                return Err(format!(
+                    "expected = after {} in {}.",
+                    name,
+                    key.as_deref().unwrap_or("@string")
+                ));
+            }
+            i += 1;
+            let value = bibtex_value(&chars, &mut i, &strings)?;
+            fields.insert(name, value);
+        }
+        match key {
+            Some(key) => {
+                entries.insert(key, bibtex_entry(&fields));
+            }
+            None => strings.extend(fields),
+        }
+    }
+    Ok(entries)
+}
+
+// Strings and numbers, which CSL-JSON uses for volumes and pages alike.
+fn csl_text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        Value::Integer(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn csl_name(name: &Value) -> Option<String> {
+    if let Some(literal) = csl_text(name.get("literal")) {
+        return Some(literal);
+    }
+    let parts: Vec<String> = ["given", "non-dropping-particle", "family"]
+        .iter()
+        .filter_map(|part| csl_text(name.get(part)))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+fn csl_year(issued: Option<&Value>) -> Option<String> {
+    let issued = issued?;
+    let year = issued
+        .get("date-parts")
+        .and_then(Value::as_array)
+        .and_then(|parts| parts.first())
+        .and_then(Value::as_array)
+        .and_then(|parts| csl_text(parts.first()));
+    year.or_else(|| {
+        csl_text(issued.get("raw"))
+            .or_else(|| csl_text(issued.get("literal")))
+            .map(|date| date.chars().take(4).collect())
+    })
+}
+
+fn csl_json(source: &str) -> io::Result<Bibliography> {
+    let items = json::parse(source)?;
+    let items = items
+        .as_array()
+        .ok_or_else(|| io::Error::other("expected a list of CSL-JSON items."))?;
+    let mut entries = Bibliography::new();
+    for item in items {
+        let key = match csl_text(item.get("id")) {
+            Some(key) => key,
+            None => continue,
+        };
+        let authors = ["author", "editor"]
+            .iter()
+            .filter_map(|role| item.get(role).and_then(Value::as_array))
+            .map(|names| names.iter().filter_map(csl_name).collect::<Vec<String>>())
+            .find(|names| !names.is_empty())
+            .unwrap_or_default();
+        let entry = Entry {
+            authors,
+            year: csl_year(item.get("issued")),
+            title: csl_text(item.get("title")),
+            container: csl_text(item.get("container-title")),
+            volume: csl_text(item.get("volume")),
+            issue: csl_text(item.get("issue")),
+            pages: csl_text(item.get("page")).map(|pages| pages.replace('-', "–")),
+            publisher: csl_text(item.get("publisher")),
+            url: csl_text(item.get("URL"))
+                .or_else(|| csl_text(item.get("DOI")).map(|doi| doi_url(&doi))),
+        };
+        entries.insert(key, entry);
+    }
+    Ok(entries)
+}
+
+// One reference in a citation: [see @knuth84, p. 3] has the prefix "see" and the locator "p. 3".
+struct Cite {
+    prefix: String,
+    key: String,
+    locator: String,
+    // The entry's number in the post's bibliography; None for a key the bibliography lacks.
+    number: Option<usize>,
+}
+
+pub struct Citations {
+    groups: Vec<Vec<Cite>>,
+    // The cited entries, numbered in the order they are first cited.
+    entries: Vec<(String, Entry)>,
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-:.#$%&+?<>~/".contains(c)
+}
+
+// The references in the text of a citation, split at semicolons. Keys take an @ but in AsciiDoc.
+fn cites(format: ContentFormat, text: &str) -> Option<Vec<Cite>> {
+    text.split(';')
+        .map(|part| {
+            let at = match format {
+                ContentFormat::AsciiDoc if !part.contains('@') => {
+                    part.len() - part.trim_start().len()
+                }
+                // An @ inside a word, as in an email address, is not a key.
+                _ => {
+                    let (at, _) = part.char_indices().find(|(at, c)| {
+                        *c == '@' && part[..*at].chars().last().is_none_or(char::is_whitespace)
+                    })?;
+                    at + 1
+                }
+            };
+            let key_len = part[at..]
+                .find(|c: char| !is_key_char(c))
+                .unwrap_or(part.len() - at);
+            // Keys end before trailing punctuation, as in Pandoc.
+            let key =
+                part[at..at + key_len].trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_');
+            if key.is_empty() {
+                return None;
+            }
+            let prefix = part[..at].trim_end_matches('@').trim();
+            let locator = part[at + key.len()..].trim().trim_start_matches(',').trim();
+            Some(Cite {
+                prefix: prefix.to_string(),
+                key: key.to_string(),
+                locator: locator.to_string(),
+                number: None,
+            })
+        })
+        .collect()
+}
+
+// The citation at the start of text and its length: [@key] in Markdown, [cite:@key] in Org,
+// cite:[key] in AsciiDoc.
+fn citation(format: ContentFormat, text: &str) -> Option<(usize, Vec<Cite>)> {
+    let (inner, start) = match format {
+        ContentFormat::Markdown => (text.strip_prefix('[')?, 1),
+        ContentFormat::Org => {
+            let inner = text.strip_prefix("[cite")?;
+            let colon = inner.find(':')?;
+            // A style like [cite/t:@key] is read as a plain citation.
+            if inner[..colon].contains(|c: char| c.is_whitespace() || c == ']') {
+                return None;
+            }
+            (&inner[colon + 1..], 6 + colon)
+        }
+        ContentFormat::AsciiDoc => (text.strip_prefix("cite:[")?, 6),
+    };
+    let end = inner.find(']')?;
+    // [@key](url) and [@key][ref] are links.
+    if format == ContentFormat::Markdown
+        && (!inner[..end].contains('@') || inner[end + 1..].starts_with(['(', '[']))
+    {
+        return None;
+    }
+    Some((start + end + 1, cites(format, &inner[..end])?))
+}
+
+// Replaces each citation outside code with a placeholder, or in AsciiDoc with cross references,
+// numbering the cited entries of the [citations] bibliography. Unknown keys are reported
+// and left as they are written.
+pub fn extract(cfg: &Config, meta: &ContentMeta, text: &str) -> (String, Citations) {
+    let mut citations = Citations {
+        groups: Vec::new(),
+        entries: Vec::new(),
+    };
+    let marker = match meta.format {
+        ContentFormat::Markdown => "[",
+        ContentFormat::Org => "[cite",
+        ContentFormat::AsciiDoc => "cite:[",
+    };
+    if cfg.citations.bibliography.is_none() || !text.contains(marker) {
+        return (text.to_string(), citations);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut scan = BodyScan::default();
+    for line in text.split_inclusive('\n') {
+        if scan.is_code(meta.format, line) {
+            out.push_str(line);
+            continue;
+        }
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                let run = rest.chars().take_while(|c| *c == '`').count();
+                let end = rest[run..]
+                    .find(&rest[..run])
+                    .map_or(run, |end| run + end + run);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            let (len, mut group) = match citation(meta.format, rest) {
+                Some(citation) => citation,
+                None => {
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                    continue;
+                }
+            };
+            for cite in &mut group {
+                let cited = citations
+                    .entries
+                    .iter()
+                    .position(|(key, _)| *key == cite.key);
+                cite.number = match (cited, cfg.citations.entries.get(&cite.key)) {
+                    (Some(at), _) => Some(at + 1),
+                    (None, Some(entry)) => {
+                        citations.entries.push((cite.key.clone(), entry.clone()));
+                        Some(citations.entries.len())
+                    }
+                    (None, None) => {
+                        eprintln!(
+                            "warning: no entry for citation {} in {}: {}",
+                            cite.key,
+                            meta.output_path(),
+                            cfg.citations
+                                .bibliography
+                                .as_deref()
+                                .unwrap_or(Path::new(""))
+                                .display()
+                        );
+                        None
+                    }
+                };
+            }
+            if group.iter().all(|cite| cite.number.is_none()) {
+                out.push_str(&rest[..len]);
+            } else if meta.format == ContentFormat::AsciiDoc {
+                out.push_str(&citation_text(meta.format, &group));
+            } else {
+                out.push(CITE_START);
+                out.push_str(&citations.groups.len().to_string());
+                out.push(CITE_END);
+                citations.groups.push(group);
+            }
+            rest = &rest[len..];
+        }
+    }
+    (out, citations)
+}
+
+// The anchor of an entry in the bibliography, in characters AsciiDoc ids allow.
+fn anchor(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("ref-{}", key)
+}
+
+// A citation as numbers linking to the bibliography: [see 1, p. 3; 2].
+fn citation_text(format: ContentFormat, group: &[Cite]) -> String {
+    let html = format != ContentFormat::AsciiDoc;
+    let parts: Vec<String> = group
+        .iter()
+        .map(|cite| {
+            let reference = match cite.number {
+                Some(number) if html => {
+                    format!("<a href=\"#{}\">{}</a>", anchor(&cite.key), number)
+                }
+                Some(number) => format!("<<{},{}>>", anchor(&cite.key), number),
+                None if html => format!("@{}", escape_html(&cite.key)),
+                None => format!("@{}", cite.key),
+            };
+            let (prefix, locator) = if html {
+                (escape_html(&cite.prefix), escape_html(&cite.locator))
+            } else {
+                (cite.prefix.clone(), cite.locator.clone())
+            };
+            let mut part = reference;
+            if !prefix.is_empty() {
+                part = format!("{} {}", prefix, part);
+            }
+            if !locator.is_empty() {
+                part = format!("{}, {}", part, locator);
+            }
+            part
+        })
+        .collect();
+    if html {
+        format!("<span class=\"citation\">[{}]</span>", parts.join("; "))
+    } else {
+        format!("[{}]", parts.join("; "))
+    }
+}
+
+// Puts the citations back into the converted HTML.
+pub fn restore(html: &str, citations: &Citations) -> String {
+    if citations.groups.is_empty() {
+        return html.to_string();
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(CITE_START) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + CITE_START.len_utf8()..];
+        let group = after.split_once(CITE_END).and_then(|(index, tail)| {
+            let group = citations.groups.get(index.parse::<usize>().ok()?)?;
+            Some((group, tail))
+        });
+        match group {
+            Some((group, tail)) => {
+                out.push_str(&citation_text(ContentFormat::Markdown, group));
+                rest = tail;
+            }
+            None => {
+                out.push(CITE_START);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [names @ .., last] => format!("{} and {}", names.join(", "), last),
+    }
+}
+
+// An entry in author-year order: Authors (Year). Title. Container, volume(issue), pages. Publisher. URL
+fn entry_text(format: ContentFormat, entry: &Entry) -> String {
+    let html = format != ContentFormat::AsciiDoc;
+    let text = |text: &str| {
+        if html {
+            escape_html(text)
+        } else {
+            text.to_string()
+        }
+    };
+    let emphasis = |text: &str| {
+        if html {
+            format!("<em>{}</em>", escape_html(text))
+        } else {
+            format!("__{}__", text)
+        }
+    };
+
+    let mut parts = Vec::new();
+    match (entry.authors.is_empty(), &entry.year) {
+        (false, Some(year)) => parts.push(format!(
+            "{} ({})",
+            text(&join_names(&entry.authors)),
+            text(year)
+        )),
+        (false, None) => parts.push(text(&join_names(&entry.authors))),
+        (true, Some(year)) => parts.push(format!("({})", text(year))),
+        (true, None) => {}
+    }
+    match (&entry.title, &entry.container) {
+        (Some(title), Some(_)) => parts.push(text(title)),
+        (Some(title), None) => parts.push(emphasis(title)),
+        (None, _) => {}
+    }
+    if let Some(container) = &entry.container {
+        let mut source = emphasis(container);
+        if let Some(volume) = &entry.volume {
+            source.push_str(&format!(", {}", text(volume)));
+        }
+        if let Some(issue) = &entry.issue {
+            source.push_str(&format!("({})", text(issue)));
+        }
+        if let Some(pages) = &entry.pages {
+            source.push_str(&format!(", {}", text(pages)));
+        }
+        parts.push(source);
+    }
+    if let Some(publisher) = &entry.publisher {
+        parts.push(text(publisher));
+    }
+    let mut entry_text: String = parts
+        .iter()
+        .map(|part| {
+            if part.ends_with(['.', '?', '!']) {
+                format!("{} ", part)
+            } else {
+                format!("{}. ", part)
+            }
+        })
+        .collect();
+    match &entry.url {
+        Some(url) if html => {
+            entry_text.push_str(&format!("<a href=\"{0}\">{0}</a>", escape_html(url)))
+        }
+        Some(url) => entry_text.push_str(url),
+        None => {}
+    }
+    entry_text.trim_end().to_string()
+}
+
+// The post's bibliography in the markup Asciidoctor gives a [bibliography] section.
+pub fn bibliography_html(cfg: &Config, citations: &Citations) -> String {
+    if citations.entries.is_empty() {
+        return String::new();
+    }
+    let mut html = format!(
+        "<div class=\"sect1\">\n<h2 id=\"_references\">{}</h2>\n<div class=\"sectionbody\">\n<div class=\"ulist bibliography\">\n<ul class=\"bibliography\">\n",
+        escape_html(&cfg.citations.title)
+    );
+    for (i, (key, entry)) in citations.entries.iter().enumerate() {
+        html.push_str(&format!(
+            "<li>\n<p><a id=\"{}\"></a>[{}] {}</p>\n</li>\n",
+            anchor(key),
+            i + 1,
+            entry_text(ContentFormat::Markdown, entry)
+        ));
+    }
+    html.push_str("</ul>\n</div>\n</div>\n</div>\n");
+    html
+}
+
+// The post's bibliography as an AsciiDoc section to end its body with.
+pub fn bibliography_adoc(cfg: &Config, citations: &Citations) -> String {
+    if citations.entries.is_empty() {
+        return String::new();
+    }
+    let mut adoc = format!("\n[bibliography]\n== {}\n\n", cfg.citations.title);
+    for (i, (key, entry)) in citations.entries.iter().enumerate() {
+        adoc.push_str(&format!(
+            "* [[[{},{}]]] {}\n",
+            anchor(key),
+            i + 1,
+            entry_text(ContentFormat::AsciiDoc, entry)
+        ));
+    }
+    adoc
+}
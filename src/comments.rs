@@ -0,0 +1,63 @@
+use crate::config::{CommentsProvider, Config};
+use crate::content::{meta_context, ContentMeta};
+use crate::feed::canonical_url;
+use crate::i18n::default_language;
+use crate::template::{optional, Templates};
+use crate::value::Value;
+
+// Threads are keyed by the post's output path without its extension, which stays put
+// when the page is rendered to HTML or the site moves to another domain.
+pub fn comment_identifier(meta: &ContentMeta) -> String {
+    let path = meta.output_path();
+    match path.rsplit_once('.') {
+        Some((stem, _)) => stem.to_string(),
+        None => path,
+    }
+}
+
+// The [comments] embed for the post; empty without a provider or with comments: false.
+pub fn comments_block(cfg: &Config, meta: &ContentMeta, templates: &Templates) -> String {
+    let comments = &cfg.comments;
+    let provider = match comments.provider {
+        Some(provider) if meta.comments != Some(false) => provider,
+        _ => return String::new(),
+    };
+
+    let mut context = meta_context(meta);
+    for (name, enabled) in [
+        ("giscus", provider == CommentsProvider::Giscus),
+        ("utterances", provider == CommentsProvider::Utterances),
+        ("isso", provider == CommentsProvider::Isso),
+    ] {
+        context.insert(String::from(name), Value::Boolean(enabled));
+    }
+    context.insert(
+        String::from("identifier"),
+        Value::String(comment_identifier(meta)),
+    );
+    context.insert(
+        String::from("page_url"),
+        Value::String(canonical_url(cfg, meta)),
+    );
+    context.insert(
+        String::from("language"),
+        Value::String(
+            meta.language
+                .clone()
+                .unwrap_or_else(|| default_language(cfg)),
+        ),
+    );
+    for (name, value) in [
+        ("repo", &comments.repo),
+        ("repo_id", &comments.repo_id),
+        ("category", &comments.category),
+        ("category_id", &comments.category_id),
+        ("label", &comments.label),
+        ("theme", &comments.theme),
+        ("url", &comments.url),
+    ] {
+        context.insert(String::from(name), optional(value.as_deref()));
+    }
+
+    templates.comments.render(&context)
+}
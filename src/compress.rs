@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::parallel;
+use crate::progress::Progress;
+use crate::tools::{is_fresh, spawn_err};
+
+// The compressor writes to stdout; None when the result is no smaller than the file.
+fn compressed(
+    command: &str,
+    args: &[&str],
+    key: &str,
+    source: &Path,
+) -> io::Result<Option<Vec<u8>>> {
+    let output = Command::new(command)
+        .args(args)
+        .arg(source)
+        .output()
+        .map_err(|err| {
+            spawn_err(
+                command,
+                "it",
+                &format!("[compress] {} to its path", key),
+                err,
+            )
+        })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed on {} with {}: {}",
+            command,
+            source.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let size = fs::metadata(source)?.len();
+    Ok(Some(output.stdout).filter(|bytes| (bytes.len() as u64) < size))
+}
+
+struct Format<'a> {
+    command: &'a str,
+    args: &'static [&'static str],
+    key: &'static str,
+    ext: &'static str,
+}
+
+fn formats(cfg: &Config) -> Vec<Format<'_>> {
+    let mut formats = Vec::new();
+    if cfg.compress.gzip {
+        formats.push(Format {
+            command: &cfg.compress.gzip_command,
+            args: &["-9", "-n", "-c"],
+            key: "gzip_command",
+            ext: "gz",
+        });
+    }
+    if cfg.compress.brotli {
+        formats.push(Format {
+            command: &cfg.compress.brotli_command,
+            args: &["-q", "11", "-c"],
+            key: "brotli_command",
+            ext: "br",
+        });
+    }
+    formats
+}
+
+// The compressed files next to source, and how many of them were written rather than fresh.
+fn compress_file(formats: &[Format], source: &Path) -> io::Result<(Vec<PathBuf>, usize)> {
+    let mut written = Vec::new();
+    let mut compressed_files = 0;
+    for format in formats {
+        let target = PathBuf::from(format!("{}.{}", source.display(), format.ext));
+        if is_fresh(source, &target) {
+            written.push(target);
+            continue;
+        }
+        match compressed(format.command, format.args, format.key, source)? {
+            Some(bytes) => {
+                fs::write(&target, bytes)?;
+                compressed_files += 1;
+                written.push(target);
+            }
+            None => {
+                if target.exists() {
+                    fs::remove_file(&target)?;
+                }
+            }
+        }
+    }
+    Ok((written, compressed_files))
+}
+
+fn is_compressible(cfg: &Config, output: &str) -> bool {
+    match output.rsplit_once('.') {
+        Some((_, ext)) => cfg
+            .compress
+            .extensions
+            .iter()
+            .any(|compressible| compressible.eq_ignore_ascii_case(ext)),
+        None => false,
+    }
+}
+
+// Writes <file>.gz and <file>.br next to each text output, for servers that serve them precompressed.
+pub fn write_compressed(cfg: &Config, outputs: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut sources: Vec<PathBuf> = Vec::new();
+    for output in outputs {
+        let path = cfg.output_root_path.join(output);
+        if !is_compressible(cfg, output) {
+            continue;
+        }
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() && metadata.len() >= cfg.compress.min_size => {
+                sources.push(path)
+            }
+            _ => {}
+        }
+    }
+    sources.sort();
+    sources.dedup();
+
+    let formats = formats(cfg);
+    let progress = Progress::new(cfg, "compress", sources.len());
+    let results = parallel::map(cfg.build.jobs, &sources, |source| {
+        let result = compress_file(&formats, source);
+        progress.tick();
+        result
+    });
+    progress.finish();
+
+    let mut written = Vec::new();
+    let mut compressed_files = 0;
+    for result in results {
+        let (paths, count) = result?;
+        written.extend(paths);
+        compressed_files += count;
+    }
+    if compressed_files > 0 {
+        eprintln!("wrote {} compressed files.", compressed_files);
+    }
+    Ok(written)
+}
@@ -0,0 +1,2153 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::authors::{self, Profiles};
+use crate::citations::{self, Bibliography};
+use crate::content::{friendly_filename, title_case};
+use crate::dates::{self, DEFAULT_DATE_FORMAT};
+use crate::discovery::{glob_match, DRAFTS_DIR};
+use crate::error::WebWeaverError;
+use crate::highlight::{self, Theme, THEMES};
+use crate::i18n::is_language_tag;
+use crate::ignore::IgnoreFiles;
+use crate::parallel;
+use crate::toml;
+use crate::url;
+use crate::value::{Table, Value};
+
+pub const DEFAULT_CONFIG_FILE: &str = "webweaver.toml";
+const DEFAULT_TEMPLATES_DIR: &str = "templates";
+const DEFAULT_TITLE: &str = "Home";
+const DEFAULT_STATIC_DIR: &str = "static";
+const DEFAULT_ARCHETYPES_DIR: &str = "archetypes";
+const DEFAULT_DATA_DIR: &str = "data";
+const DEFAULT_AUTHORS_FILE: &str = "authors.toml";
+const CONTENT_DIR: &str = ".content";
+pub const CATEGORY_FILE: &str = "_category.toml";
+const DEFAULT_ASCIIDOC_INCLUDES: [&str; 1] = ["{base-path}/head.adoc"];
+const DEFAULT_COMPRESS_EXTENSIONS: [&str; 9] = [
+    "html", "css", "js", "json", "xml", "svg", "txt", "adoc", "map",
+];
+
+#[derive(Clone, Debug)]
+pub struct SiteConfig {
+    pub title: String,
+    pub description: String,
+    pub base_url: String,
+    pub canonical_base_url: String,
+    pub language: Option<String>,
+    pub image: Option<String>,
+    pub twitter: Option<String>,
+    pub date_format: String,
+    pub locale: String,
+    pub words_per_minute: usize,
+    pub jsonld: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct FeedConfig {
+    pub copyright: Option<String>,
+    pub webmaster: Option<String>,
+    pub rss: bool,
+    pub atom: bool,
+    pub json: bool,
+    pub path: String,
+    pub atom_path: String,
+    pub json_path: String,
+    pub autodiscovery: bool,
+    pub limit: Option<usize>,
+    pub content: FeedContent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeedContent {
+    Full,
+    Summary,
+}
+
+// From the category's _category.toml; the title defaults to the directory name.
+#[derive(Clone, Debug, Default)]
+pub struct CategoryConfig {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiscoveryConfig {
+    pub max_depth: Option<usize>,
+    pub ignore: Vec<String>,
+    pub symlinks: Symlinks,
+    // PDFs and images named like posts become posts of their own.
+    pub attachments: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symlinks {
+    Follow,
+    Skip,
+}
+
+#[derive(Clone, Debug)]
+pub struct SitemapConfig {
+    pub enabled: bool,
+    pub max_urls: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct RobotsConfig {
+    pub enabled: bool,
+    pub user_agent: String,
+    pub allow: Vec<String>,
+    pub disallow: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RedirectsConfig {
+    pub stubs: bool,
+    pub format: RedirectFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RedirectFormat {
+    None,
+    Netlify,
+    Nginx,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    pub enabled: bool,
+    pub path: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImagesConfig {
+    pub enabled: bool,
+    pub widths: Vec<u32>,
+    pub webp: bool,
+    pub quality: u32,
+    pub command: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompressConfig {
+    pub gzip: bool,
+    pub brotli: bool,
+    pub extensions: Vec<String>,
+    pub min_size: u64,
+    pub gzip_command: String,
+    pub brotli_command: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProtectedConfig {
+    pub password: String,
+    pub iterations: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeployTarget {
+    Rsync,
+    S3,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeployConfig {
+    pub target: Option<DeployTarget>,
+    pub destination: Option<String>,
+    pub endpoint: Option<String>,
+    pub delete: bool,
+    pub dry_run: bool,
+    pub rsync: String,
+    pub aws: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ActivityPubConfig {
+    pub enabled: bool,
+    pub username: Option<String>,
+    pub inbox: Option<String>,
+    pub public_key: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WebmentionConfig {
+    pub endpoint: Option<String>,
+    pub pingback: Option<String>,
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinksConfig {
+    pub rate: u32,
+    pub timeout_secs: u64,
+    pub curl: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexConfig {
+    pub page_size: Option<usize>,
+    pub group_by: IndexGrouping,
+    pub order: SortOrder,
+    pub same_day_by_title: bool,
+    pub microformats: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Desc,
+    Asc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexGrouping {
+    Year,
+    Month,
+    Category,
+    None,
+}
+
+// None unsets an attribute; includes replace, rather than add to, the global ones.
+#[derive(Clone, Debug, Default)]
+pub struct AsciidocAttributes {
+    pub attributes: BTreeMap<String, Option<String>>,
+    pub includes: Option<Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PodcastType {
+    Episodic,
+    Serial,
+}
+
+#[derive(Clone, Debug)]
+pub struct PodcastConfig {
+    // Categories whose feeds are podcasts, with their subcategories.
+    pub categories: Vec<String>,
+    pub author: Option<String>,
+    pub owner_name: Option<String>,
+    pub owner_email: Option<String>,
+    pub image: Option<String>,
+    pub category: Option<String>,
+    pub explicit: bool,
+    pub kind: PodcastType,
+}
+
+impl PodcastConfig {
+    pub fn is_podcast(&self, category: &str) -> bool {
+        self.categories.iter().any(|podcast| {
+            category == podcast
+                || category
+                    .strip_prefix(podcast.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AsciidocConfig {
+    pub global: AsciidocAttributes,
+    pub categories: BTreeMap<String, AsciidocAttributes>,
+}
+
+impl AsciidocConfig {
+    // The attributes and includes in the prologue of the category's pages.
+    pub fn for_category(&self, category: &str) -> (BTreeMap<String, Option<String>>, Vec<String>) {
+        let mut attributes = self.global.attributes.clone();
+        let mut includes = self.global.includes.clone().unwrap_or_else(|| {
+            DEFAULT_ASCIIDOC_INCLUDES
+                .iter()
+                .map(|include| include.to_string())
+                .collect()
+        });
+        if let Some(overrides) = self.categories.get(category) {
+            attributes.extend(overrides.attributes.clone());
+            if let Some(category_includes) = &overrides.includes {
+                includes = category_includes.clone();
+            }
+        }
+        (attributes, includes)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommentsProvider {
+    Giscus,
+    Utterances,
+    Isso,
+}
+
+#[derive(Clone, Debug)]
+pub struct CommentsConfig {
+    pub provider: Option<CommentsProvider>,
+    pub repo: Option<String>,
+    pub repo_id: Option<String>,
+    pub category: Option<String>,
+    pub category_id: Option<String>,
+    pub label: Option<String>,
+    pub theme: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TocConfig {
+    pub enabled: bool,
+    pub min_headings: usize,
+    pub levels: usize,
+}
+
+// Each command is a program and its arguments, where {input} and {output} stand for the
+// diagram source and SVG files; without them the source is piped in and the SVG read back.
+#[derive(Clone, Debug)]
+pub struct DiagramsConfig {
+    pub enabled: bool,
+    pub mermaid: Vec<String>,
+    pub plantuml: Vec<String>,
+    pub graphviz: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HighlightConfig {
+    pub enabled: bool,
+    pub theme: &'static Theme,
+    // The stylesheet written under the output root; None styles each token inline.
+    pub stylesheet: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MathConfig {
+    pub enabled: bool,
+    // The KaTeX distribution HTML pages load it from, without a trailing slash.
+    pub katex: String,
+    // The :stem: value of AsciiDoc pages.
+    pub stem: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct CitationsConfig {
+    // The shared BibTeX or CSL-JSON file posts cite; None leaves citations as they are written.
+    pub bibliography: Option<PathBuf>,
+    // The heading of each post's list of references.
+    pub title: String,
+    pub entries: Bibliography,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxonomyConfig {
+    // The front matter key, which also names the directory the term pages go in.
+    pub name: String,
+    // The heading of the term list, like Locations.
+    pub title: String,
+    pub feeds: bool,
+    // Headings show it instead of the title, as 🏷️ for tags.
+    pub icon: Option<String>,
+    // The term list is a cloud weighted by post count rather than a list.
+    pub cloud: bool,
+    // Terms list their posts oldest first and numbered, as the parts of a series.
+    pub ordered: bool,
+}
+
+pub const TAGS: &str = "tags";
+pub const SERIES: &str = "series";
+
+impl TaxonomyConfig {
+    fn new(name: &str) -> TaxonomyConfig {
+        TaxonomyConfig {
+            name: name.to_string(),
+            title: title_case(name),
+            feeds: true,
+            icon: None,
+            cloud: false,
+            ordered: false,
+        }
+    }
+}
+
+// Tags and series, which every site has; [taxonomies.tags] and [taxonomies.series] adjust them.
+fn builtin_taxonomies() -> Vec<TaxonomyConfig> {
+    vec![
+        TaxonomyConfig {
+            feeds: false,
+            icon: Some(String::from("\u{1F3F7}\u{FE0F}")),
+            cloud: true,
+            ..TaxonomyConfig::new(TAGS)
+        },
+        TaxonomyConfig {
+            feeds: false,
+            icon: Some(String::from("\u{1F4DA}")),
+            ordered: true,
+            ..TaxonomyConfig::new(SERIES)
+        },
+    ]
+}
+
+#[derive(Clone, Debug)]
+pub struct SmartypantsConfig {
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuildConfig {
+    pub incremental: bool,
+    pub drafts: bool,
+    pub future: bool,
+    pub clean: bool,
+    pub atomic: bool,
+    // Set by --force: build without taking the output lock.
+    pub force: bool,
+    pub progress: bool,
+    // Set by --profile-build: time each file and write a report.
+    pub profile: bool,
+    // Bytes; posts this large keep their bodies out of feeds and search, and AsciiDoc ones
+    // are streamed onto their pages. 0 never streams.
+    pub stream_threshold: u64,
+    pub prune: bool,
+    pub jobs: usize,
+    pub watch: bool,
+    pub watch_interval_ms: u64,
+    pub html: bool,
+    pub asciidoctor: String,
+    pub keep_going: bool,
+    pub mtime_updated: bool,
+    pub git_dates: bool,
+    pub git: String,
+    pub heading_titles: bool,
+    // Expand :name: emoji shortcodes in titles, summaries and bodies.
+    pub emoji: bool,
+    pub all: bool,
+    pub manifest: bool,
+    pub minify: bool,
+    pub checksums: bool,
+    pub slug_collisions: SlugCollisions,
+    pub check_links: LinkCheck,
+}
+
+#[derive(Clone, Debug)]
+pub struct ServeConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkCheck {
+    Off,
+    Warn,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SlugCollisions {
+    Error,
+    Suffix,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Build,
+    New {
+        title: String,
+        date: Option<NaiveDate>,
+        file_ext: String,
+    },
+    Clean,
+    Serve,
+    CheckLinks,
+    Deploy,
+    Validate,
+    Stats,
+    Webmentions,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub command: Command,
+    pub input_content_root_path: PathBuf,
+    pub output_content_root_path: PathBuf,
+    pub output_root_path: PathBuf,
+    pub templates_root_path: Option<PathBuf>,
+    pub static_root_path: Option<PathBuf>,
+    pub archetypes_root_path: Option<PathBuf>,
+    pub data_root_path: Option<PathBuf>,
+    pub author: Option<String>,
+    // Bios and links from authors.toml, by author slug.
+    pub author_profiles: Profiles,
+    pub category: String,
+    pub category_config: CategoryConfig,
+    pub site: SiteConfig,
+    pub feed: FeedConfig,
+    pub discovery: DiscoveryConfig,
+    pub sitemap: SitemapConfig,
+    pub robots: RobotsConfig,
+    pub search: SearchConfig,
+    pub redirects: RedirectsConfig,
+    pub images: ImagesConfig,
+    pub links: LinksConfig,
+    pub deploy: DeployConfig,
+    pub webmention: WebmentionConfig,
+    pub activitypub: ActivityPubConfig,
+    pub index: IndexConfig,
+    pub toc: TocConfig,
+    pub math: MathConfig,
+    pub smartypants: SmartypantsConfig,
+    pub citations: CitationsConfig,
+    pub taxonomies: Vec<TaxonomyConfig>,
+    pub highlight: HighlightConfig,
+    pub diagrams: DiagramsConfig,
+    pub asciidoc: AsciidocConfig,
+    pub podcast: PodcastConfig,
+    pub comments: CommentsConfig,
+    pub compress: CompressConfig,
+    pub protected: ProtectedConfig,
+    pub build: BuildConfig,
+    pub serve: ServeConfig,
+    // Set on the copies that write a translation's index and feeds.
+    pub translation: Option<String>,
+}
+
+const USAGE: &str = "usage: webweaver <command> [options]
+
+commands:
+  build        generate the site from the content tree
+  new          create a dated content file
+  clean        remove the output directory
+  serve        build, then preview the output with live reload
+  check-links  report dead and redirected external links in the content
+  deploy       upload the changed output files to the configured target
+  validate     check the whole .content tree for problems without building
+  stats        print post counts, word counts, top tags and posting gaps
+  webmentions  send webmentions for the links in published posts
+  help         show help for a command
+
+Run `webweaver help <command>` for the options of a command.
+";
+
+const BUILD_HELP: &str = "usage: webweaver build [options] [input]
+
+Generate the site from the content tree.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -o, --output <dir>     output root (default content)
+      --base-url <url>   absolute base URL for feed and sitemap links
+      --title <title>    site title
+      --author <name>    site author
+      --drafts           include drafts in the output, index and feeds
+      --include-future   include posts dated after today
+      --clean            remove the output directory before building
+      --force            build even while another build holds the output lock
+      --html             render AsciiDoc pages to HTML with asciidoctor
+  -k, --keep-going       skip content files with errors and report them at the end
+  -a, --all              build every category below the input with a combined index
+  -j, --jobs <n>         worker threads (default: available cores)
+  -w, --watch            rebuild whenever the input changes
+      --profile-build    time each file and write a report to the output root
+";
+
+const NEW_HELP: &str = "usage: webweaver new [options] <title>
+
+Create a dated content file named YYYY-MM-DD_<title> in the input directory,
+filled in from the archetype for its category.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+      --category <path>  create it in .content/<path> instead of the input
+  -d, --date <date>      YYYY-MM-DD date (default today)
+  -f, --format <format>  adoc, md or org (default adoc)
+";
+
+const CLEAN_HELP: &str = "usage: webweaver clean [options] [input]
+
+Remove the output directory.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -o, --output <dir>     output root (default content)
+      --force            remove it even while a build holds the output lock
+";
+
+const SERVE_HELP: &str = "usage: webweaver serve [options] [input]
+
+Build the site, then serve the output over HTTP, rebuilding and reloading
+the browser whenever the input changes.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -o, --output <dir>     output root (default content)
+      --base-url <url>   absolute base URL for feed and sitemap links
+      --title <title>    site title
+      --author <name>    site author
+      --drafts           include drafts in the output, index and feeds
+      --include-future   include posts dated after today
+      --clean            remove the output directory before building
+      --force            build even while another build holds the output lock
+      --html             render AsciiDoc pages to HTML with asciidoctor
+  -k, --keep-going       skip content files with errors and report them at the end
+  -a, --all              build every category below the input with a combined index
+  -j, --jobs <n>         worker threads (default: available cores)
+      --host <host>      address to listen on (default 127.0.0.1)
+  -p, --port <port>      port to listen on (default 8080)
+";
+
+const CHECK_LINKS_HELP: &str = "usage: webweaver check-links [options] [input]
+
+Request every external URL in the content and report dead and redirected
+links with the file and line they occur on. Needs curl.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -j, --jobs <n>         concurrent requests (default: available cores)
+      --rate <n>         at most n requests per second (default 5)
+";
+
+const DEPLOY_HELP: &str = "usage: webweaver deploy [options] [input]
+
+Upload the files that changed since the last deploy to the [deploy] target,
+with rsync or the aws CLI, using the build manifest of the last build.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -o, --output <dir>     output root (default content)
+  -j, --jobs <n>         concurrent S3 uploads (default: available cores)
+  -n, --dry-run          list the uploads and deletions without running them
+";
+
+const VALIDATE_HELP: &str = "usage: webweaver validate [options] [input]
+
+Check every file in the .content tree holding the input without building:
+file name dates, duplicate slugs, missing extensions, empty files, front matter
+and UTF-8. Prints one line per problem and exits 1 if there are any.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -j, --jobs <n>         worker threads (default: available cores)
+";
+
+const STATS_HELP: &str = "usage: webweaver stats [options] [input]
+
+Print posts per year and month, total and average words, the most used tags
+and the longest gaps between posts, for the posts a build would publish.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+      --drafts           count drafts
+      --include-future   count posts dated after today
+  -a, --all              count every category below the input
+  -j, --jobs <n>         worker threads (default: available cores)
+";
+
+const WEBMENTIONS_HELP: &str = "usage: webweaver webmentions send [options] [input]
+
+Find the external links in the posts a build would publish and send a
+webmention to every target that advertises an endpoint, skipping the ones
+sent before. Needs curl.
+
+options:
+  -c, --config <file>    config file (default webweaver.toml)
+      --profile <name>   apply [profile.<name>] from the config file
+  -i, --input <dir>      content directory below .content
+  -o, --output <dir>     output root, which keeps the record of sent mentions
+  -a, --all              send for every category below the input
+  -j, --jobs <n>         concurrent requests (default: available cores)
+      --rate <n>         at most n requests per second (default 5)
+  -n, --dry-run          list the mentions without sending them
+";
+
+#[derive(Default)]
+struct CliArgs {
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    base_url: Option<String>,
+    title: Option<String>,
+    author: Option<String>,
+    watch: bool,
+    drafts: bool,
+    future: bool,
+    clean: bool,
+    force: bool,
+    profile_build: bool,
+    html: bool,
+    keep_going: bool,
+    all: bool,
+    dry_run: bool,
+    jobs: Option<usize>,
+    rate: Option<u32>,
+    host: Option<String>,
+    port: Option<u16>,
+    date: Option<NaiveDate>,
+    file_ext: Option<String>,
+    category: Option<String>,
+    words: Vec<String>,
+}
+
+fn usage_err(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg.to_string())
+}
+
+fn command_help(command: &str) -> Option<&'static str> {
+    match command {
+        "build" => Some(BUILD_HELP),
+        "new" => Some(NEW_HELP),
+        "clean" => Some(CLEAN_HELP),
+        "serve" => Some(SERVE_HELP),
+        "check-links" => Some(CHECK_LINKS_HELP),
+        "deploy" => Some(DEPLOY_HELP),
+        "validate" => Some(VALIDATE_HELP),
+        "stats" => Some(STATS_HELP),
+        "webmentions" => Some(WEBMENTIONS_HELP),
+        _ => None,
+    }
+}
+
+pub fn help(argv: &[String]) -> Option<&'static str> {
+    let args = &argv[argv.len().min(1)..];
+    match args.first().map(String::as_str) {
+        Some("help") => match args.get(1) {
+            Some(command) => command_help(command).or(Some(USAGE)),
+            None => Some(USAGE),
+        },
+        Some("--help") | Some("-h") => Some(USAGE),
+        Some(command) if args.iter().any(|arg| arg == "--help" || arg == "-h") => {
+            command_help(command).or(Some(USAGE))
+        }
+        _ => None,
+    }
+}
+
+fn long_flag(flag: &str) -> &str {
+    match flag {
+        "-c" => "--config",
+        "-i" => "--input",
+        "-o" => "--output",
+        "-w" => "--watch",
+        "-p" => "--port",
+        "-d" => "--date",
+        "-f" => "--format",
+        "-j" => "--jobs",
+        "-k" => "--keep-going",
+        "-a" => "--all",
+        "-n" => "--dry-run",
+        flag => flag,
+    }
+}
+
+fn command_flags(command: &str) -> &'static [&'static str] {
+    match command {
+        "build" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--output",
+            "--base-url",
+            "--title",
+            "--author",
+            "--drafts",
+            "--include-future",
+            "--clean",
+            "--force",
+            "--html",
+            "--keep-going",
+            "--all",
+            "--jobs",
+            "--watch",
+            "--profile-build",
+        ],
+        "new" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--category",
+            "--date",
+            "--format",
+        ],
+        "clean" => &["--config", "--profile", "--input", "--output", "--force"],
+        "check-links" => &["--config", "--profile", "--input", "--jobs", "--rate"],
+        "deploy" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--output",
+            "--jobs",
+            "--dry-run",
+        ],
+        "validate" => &["--config", "--profile", "--input", "--jobs"],
+        "webmentions" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--output",
+            "--all",
+            "--jobs",
+            "--rate",
+            "--dry-run",
+        ],
+        "stats" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--drafts",
+            "--include-future",
+            "--all",
+            "--jobs",
+        ],
+        "serve" => &[
+            "--config",
+            "--profile",
+            "--input",
+            "--output",
+            "--base-url",
+            "--title",
+            "--author",
+            "--drafts",
+            "--include-future",
+            "--clean",
+            "--force",
+            "--html",
+            "--keep-going",
+            "--all",
+            "--jobs",
+            "--host",
+            "--port",
+        ],
+        _ => &[],
+    }
+}
+
+fn cli_args(argv: &[String]) -> io::Result<(Command, CliArgs)> {
+    let mut args = CliArgs::default();
+    let mut iter = argv.iter().skip(1);
+
+    let command = match iter.next() {
+        Some(command) if command_help(command).is_some() => command.as_str(),
+        Some(command) => {
+            return Err(usage_err(&format!(
+                "unknown command {}; run `webweaver help` for usage.",
+                command
+            )))
+        }
+        None => {
+            return Err(usage_err(
+                "missing command; run `webweaver help` for usage.",
+            ))
+        }
+    };
+
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| usage_err(&format!("{} requires a value.", flag)))
+        };
+
+        let flag = long_flag(arg);
+        if flag.starts_with('-') && !command_flags(command).contains(&flag) {
+            return Err(usage_err(&format!(
+                "unknown option {} for {}; run `webweaver help {}` for usage.",
+                arg, command, command
+            )));
+        }
+
+        match flag {
+            "--config" => args.config_path = Some(PathBuf::from(value(arg)?)),
+            "--profile" => args.profile = Some(value(arg)?),
+            "--input" => args.input = Some(PathBuf::from(value(arg)?)),
+            "--output" => args.output = Some(PathBuf::from(value(arg)?)),
+            "--base-url" => args.base_url = Some(value(arg)?),
+            "--title" => args.title = Some(value(arg)?),
+            "--author" => args.author = Some(value(arg)?),
+            "--watch" => args.watch = true,
+            "--drafts" => args.drafts = true,
+            "--include-future" => args.future = true,
+            "--clean" => args.clean = true,
+            "--force" => args.force = true,
+            "--profile-build" => args.profile_build = true,
+            "--html" => args.html = true,
+            "--keep-going" => args.keep_going = true,
+            "--all" => args.all = true,
+            "--dry-run" => args.dry_run = true,
+            "--jobs" => {
+                let jobs = value(arg)?;
+                args.jobs = Some(
+                    jobs.parse()
+                        .ok()
+                        .filter(|jobs| *jobs > 0)
+                        .ok_or_else(|| usage_err(&format!("invalid job count {}.", jobs)))?,
+                );
+            }
+            "--rate" => {
+                let rate = value(arg)?;
+                args.rate = Some(
+                    rate.parse()
+                        .ok()
+                        .filter(|rate| *rate > 0)
+                        .ok_or_else(|| usage_err(&format!("invalid rate {}.", rate)))?,
+                );
+            }
+            "--host" => args.host = Some(value(arg)?),
+            "--port" => {
+                let port = value(arg)?;
+                args.port = Some(
+                    port.parse()
+                        .map_err(|_| usage_err(&format!("invalid port {}.", port)))?,
+                );
+            }
+            "--date" => {
+                let date = value(arg)?;
+                args.date = Some(
+                    NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                        .map_err(|_| usage_err(&format!("invalid date {}.", date)))?,
+                );
+            }
+            "--format" => {
+                let format = value(arg)?;
+                args.file_ext = match format.as_str() {
+                    "adoc" | "asciidoc" => Some(String::from("adoc")),
+                    "md" | "markdown" => Some(String::from("md")),
+                    "org" => Some(String::from("org")),
+                    _ => return Err(usage_err(&format!("unknown format {}.", format))),
+                };
+            }
+            "--category" => {
+                let category = value(arg)?;
+                let valid = !category.is_empty()
+                    && Path::new(&category)
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_)));
+                if !valid {
+                    return Err(usage_err(&format!(
+                        "invalid category {}; it must be a path below .content.",
+                        category
+                    )));
+                }
+                args.category = Some(category);
+            }
+            flag => args.words.push(flag.to_string()),
+        }
+    }
+
+    let command = match command {
+        "build" => Command::Build,
+        "clean" => Command::Clean,
+        "serve" => Command::Serve,
+        "check-links" => Command::CheckLinks,
+        "deploy" => Command::Deploy,
+        "validate" => Command::Validate,
+        "stats" => Command::Stats,
+        "webmentions" => {
+            if args.words.first().map(String::as_str) != Some("send") {
+                return Err(usage_err(
+                    "webmentions requires a subcommand; run `webweaver help webmentions` for usage.",
+                ));
+            }
+            args.words.remove(0);
+            Command::Webmentions
+        }
+        _ => {
+            if args.words.is_empty() {
+                return Err(usage_err("new requires a title."));
+            }
+            let title = args.words.drain(..).collect::<Vec<String>>().join(" ");
+            Command::New {
+                title,
+                date: args.date,
+                file_ext: args.file_ext.take().unwrap_or_else(|| String::from("adoc")),
+            }
+        }
+    };
+
+    if !args.words.is_empty() {
+        if args.input.is_some() || args.words.len() > 1 {
+            return Err(usage_err(&format!(
+                "unexpected argument {}.",
+                args.words[args.words.len() - 1]
+            )));
+        }
+        args.input = args.words.pop().map(PathBuf::from);
+    }
+
+    Ok((command, args))
+}
+
+fn config_file(path: Option<&Path>, profile: Option<&str>) -> io::Result<(Table, PathBuf)> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+    };
+
+    if !path.exists() {
+        if required {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("config file {} does not exist.", path.display()),
+            ));
+        }
+        return Ok((with_profile(Table::new(), profile)?, PathBuf::from(".")));
+    }
+
+    let table = parse_config_file(&path, profile)?;
+
+    let config_dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    Ok((table, config_dir))
+}
+
+// [profile.<name>] holds the same sections as the file, and its keys win over theirs.
+fn with_profile(mut file: Table, profile: Option<&str>) -> io::Result<Table> {
+    let profiles = file.remove("profile");
+    let name = match profile {
+        Some(name) => name,
+        None => return Ok(file),
+    };
+    match profiles.as_ref().and_then(|profiles| profiles.get(name)) {
+        Some(Value::Table(overrides)) => {
+            merge_table(&mut file, overrides);
+            Ok(file)
+        }
+        Some(_) => Err(config_type_err(Some("profile"), name, "a table")),
+        None => Err(usage_err(&format!(
+            "profile {} is not defined; add a [profile.{}] table to the config file.",
+            name, name
+        ))),
+    }
+}
+
+fn merge_table(table: &mut Table, overrides: &Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(key), value) {
+            (Some(Value::Table(inner)), Value::Table(value)) => merge_table(inner, value),
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+// Environment variables are expanded once the profile is merged, so unselected profiles may use unset ones.
+fn parse_config_file(path: &Path, profile: Option<&str>) -> io::Result<Table> {
+    let contents = fs::read_to_string(path)?;
+    let in_file =
+        |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+    let table = toml::parse(&contents).map_err(in_file)?;
+    let mut table = with_profile(table, profile)?;
+    for (key, value) in table.iter_mut() {
+        expand_env(key, value).map_err(in_file)?;
+    }
+    Ok(table)
+}
+
+// Strings may use ${VAR} and ${VAR:-default}; $${ writes a literal ${.
+fn expand_env(key: &str, value: &mut Value) -> io::Result<()> {
+    match value {
+        Value::String(s) => {
+            *s = expand_env_str(s).map_err(|name| {
+                io::Error::other(format!(
+                    "config key {} uses ${{{}}}, which is not set.",
+                    key, name
+                ))
+            })?
+        }
+        Value::Array(values) => {
+            for value in values {
+                expand_env(key, value)?;
+            }
+        }
+        Value::Table(table) => {
+            for (inner, value) in table.iter_mut() {
+                expand_env(&format!("{}.{}", key, inner), value)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_env_str(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+        let (inner, after) = match rest
+            .strip_prefix("${")
+            .and_then(|inner| inner.split_once('}'))
+        {
+            Some(parts) => parts,
+            None => {
+                out.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let (name, default) = match inner.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner, None),
+        };
+        // As in the shell, :- also stands in for a variable set to the empty string.
+        match (env::var(name), default) {
+            (Ok(value), Some(default)) if value.is_empty() => out.push_str(default),
+            (Ok(value), _) => out.push_str(&value),
+            (Err(_), Some(default)) => out.push_str(default),
+            (Err(_), None) => return Err(name.to_string()),
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn config_value<'a>(table: &'a Table, section: Option<&str>, key: &str) -> Option<&'a Value> {
+    match section {
+        Some(section) => table.get(section).and_then(|s| s.get(key)),
+        None => table.get(key),
+    }
+}
+
+fn asciidoc_attributes(section: &str, table: Option<&Value>) -> io::Result<AsciidocAttributes> {
+    let table = match table {
+        None => return Ok(AsciidocAttributes::default()),
+        Some(Value::Table(table)) => table,
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("config key {} must be a table.", section),
+            ))
+        }
+    };
+
+    let mut attributes = BTreeMap::new();
+    match table.get("attributes") {
+        None => {}
+        Some(Value::Table(values)) => {
+            for (name, value) in values {
+                let value = match value {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Integer(i) => Some(i.to_string()),
+                    Value::Boolean(true) => Some(String::new()),
+                    Value::Boolean(false) => None,
+                    _ => {
+                        return Err(config_type_err(
+                            Some(section),
+                            &format!("attributes.{}", name),
+                            "a string, number or boolean",
+                        ))
+                    }
+                };
+                attributes.insert(name.clone(), value);
+            }
+        }
+        Some(_) => return Err(config_type_err(Some(section), "attributes", "a table")),
+    }
+
+    let includes = match table.get("includes") {
+        None => None,
+        Some(value) => Some(
+            value
+                .as_array()
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_str().map(String::from))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .ok_or_else(|| config_type_err(Some(section), "includes", "an array of strings"))?,
+        ),
+    };
+
+    Ok(AsciidocAttributes {
+        attributes,
+        includes,
+    })
+}
+
+fn config_type_err(section: Option<&str>, key: &str, expected: &str) -> io::Error {
+    let name = match section {
+        Some(section) => format!("{}.{}", section, key),
+        None => key.to_string(),
+    };
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("config key {} must be {}.", name, expected),
+    )
+}
+
+// Directories the build writes other pages into under a category, which a taxonomy cannot share;
+// language directories and year directories are kept clear as well.
+const RESERVED_TAXONOMIES: [&str; 3] = ["authors", "page", "diagrams"];
+
+fn check_taxonomy_name(name: &str) -> io::Result<()> {
+    if name.is_empty()
+        || friendly_filename(name) != name
+        || RESERVED_TAXONOMIES.contains(&name)
+        || is_language_tag(name)
+        || name.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "taxonomy name {} must be lowercase letters and digits, not a language code like de, \
+                 not a year, and not one of {}.",
+                name,
+                RESERVED_TAXONOMIES.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn taxonomy_config(
+    name: &str,
+    value: &Value,
+    taxonomy: TaxonomyConfig,
+) -> io::Result<TaxonomyConfig> {
+    let section = format!("taxonomies.{}", name);
+    let table = match value {
+        Value::Table(table) => table,
+        _ => return Err(config_type_err(Some("taxonomies"), name, "a table")),
+    };
+    Ok(TaxonomyConfig {
+        title: match table.get("title") {
+            None => taxonomy.title,
+            Some(Value::String(title)) => title.clone(),
+            Some(_) => return Err(config_type_err(Some(&section), "title", "a string")),
+        },
+        feeds: match table.get("feeds") {
+            None => taxonomy.feeds,
+            Some(Value::Boolean(feeds)) => *feeds,
+            Some(_) => return Err(config_type_err(Some(&section), "feeds", "a boolean")),
+        },
+        ..taxonomy
+    })
+}
+
+fn feed_content(table: &Table) -> io::Result<Option<FeedContent>> {
+    match config_str(table, Some("feed"), "content")?.as_deref() {
+        None => Ok(None),
+        Some("full") => Ok(Some(FeedContent::Full)),
+        Some("summary") => Ok(Some(FeedContent::Summary)),
+        Some(_) => Err(config_type_err(
+            Some("feed"),
+            "content",
+            "\"full\" or \"summary\"",
+        )),
+    }
+}
+
+// _category.toml names the category, overrides [feed] keys for its feeds
+// and may turn [math] or [smartypants] on or off.
+fn apply_category_file(cfg: &mut Config) -> io::Result<()> {
+    let path = cfg.input_content_root_path.join(CATEGORY_FILE);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let table = parse_config_file(&path, None)?;
+    let in_file =
+        |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+
+    cfg.category_config = CategoryConfig {
+        title: config_str(&table, None, "title").map_err(in_file)?,
+        description: config_str(&table, None, "description").map_err(in_file)?,
+    };
+    if let Some(enabled) = config_bool(&table, Some("math"), "enabled").map_err(in_file)? {
+        cfg.math.enabled = enabled;
+    }
+    if let Some(enabled) = config_bool(&table, Some("smartypants"), "enabled").map_err(in_file)? {
+        cfg.smartypants.enabled = enabled;
+    }
+    category_feed(&table, &mut cfg.feed).map_err(in_file)
+}
+
+fn category_feed(table: &Table, feed: &mut FeedConfig) -> io::Result<()> {
+    let section = Some("feed");
+    if let Some(copyright) = config_str(table, section, "copyright")? {
+        feed.copyright = Some(copyright);
+    }
+    if let Some(webmaster) = config_str(table, section, "webmaster")? {
+        feed.webmaster = Some(webmaster);
+    }
+    if let Some(rss) = config_bool(table, section, "rss")? {
+        feed.rss = rss;
+    }
+    if let Some(atom) = config_bool(table, section, "atom")? {
+        feed.atom = atom;
+    }
+    if let Some(json) = config_bool(table, section, "json")? {
+        feed.json = json;
+    }
+    if let Some(path) = config_str(table, section, "path")? {
+        feed.path = path;
+    }
+    if let Some(atom_path) = config_str(table, section, "atom_path")? {
+        feed.atom_path = atom_path;
+    }
+    if let Some(json_path) = config_str(table, section, "json_path")? {
+        feed.json_path = json_path;
+    }
+    if let Some(autodiscovery) = config_bool(table, section, "autodiscovery")? {
+        feed.autodiscovery = autodiscovery;
+    }
+    if let Some(limit) = config_usize(table, section, "limit")? {
+        feed.limit = Some(limit);
+    }
+    if let Some(content) = feed_content(table)? {
+        feed.content = content;
+    }
+    Ok(())
+}
+
+fn config_str(table: &Table, section: Option<&str>, key: &str) -> io::Result<Option<String>> {
+    match config_value(table, section, key) {
+        None => Ok(None),
+        Some(value) => match value.as_str() {
+            Some(s) => Ok(Some(s.to_string())),
+            None => Err(config_type_err(section, key, "a string")),
+        },
+    }
+}
+
+fn config_bool(table: &Table, section: Option<&str>, key: &str) -> io::Result<Option<bool>> {
+    match config_value(table, section, key) {
+        None => Ok(None),
+        Some(value) => match value.as_bool() {
+            Some(b) => Ok(Some(b)),
+            None => Err(config_type_err(section, key, "a boolean")),
+        },
+    }
+}
+
+fn config_usize(table: &Table, section: Option<&str>, key: &str) -> io::Result<Option<usize>> {
+    match config_value(table, section, key) {
+        None => Ok(None),
+        Some(value) => match value.as_integer().and_then(|i| usize::try_from(i).ok()) {
+            Some(i) => Ok(Some(i)),
+            None => Err(config_type_err(section, key, "a non-negative integer")),
+        },
+    }
+}
+
+fn config_str_array(table: &Table, section: Option<&str>, key: &str) -> io::Result<Vec<String>> {
+    match config_value(table, section, key) {
+        None => Ok(Vec::new()),
+        Some(value) => value
+            .as_array()
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect::<Option<Vec<String>>>()
+            })
+            .ok_or_else(|| config_type_err(section, key, "an array of strings")),
+    }
+}
+
+fn config_u32_array(table: &Table, section: Option<&str>, key: &str) -> io::Result<Vec<u32>> {
+    match config_value(table, section, key) {
+        None => Ok(Vec::new()),
+        Some(value) => value
+            .as_array()
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_integer().and_then(|i| u32::try_from(i).ok()))
+                    .collect::<Option<Vec<u32>>>()
+            })
+            .ok_or_else(|| config_type_err(section, key, "an array of positive integers")),
+    }
+}
+
+fn config_path(table: &Table, key: &str, config_dir: &Path) -> io::Result<Option<PathBuf>> {
+    Ok(config_str(table, None, key)?.map(|path| config_dir.join(path)))
+}
+
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    let mut absolute = PathBuf::new();
+    for component in env::current_dir()?.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    Ok(absolute)
+}
+
+// The .content directory an input path lies in.
+pub fn content_root(input_content_root_path: &Path) -> Option<PathBuf> {
+    let components: Vec<Component> = input_content_root_path.components().collect();
+    let content_index = components
+        .iter()
+        .position(|c| c.as_os_str() == CONTENT_DIR)?;
+    Some(PathBuf::from_iter(&components[..=content_index]))
+}
+
+fn content_path_parts(
+    input_content_root_path: &Path,
+) -> io::Result<(PathBuf, Option<String>, String)> {
+    let components: Vec<Component> = input_content_root_path.components().collect();
+
+    let content_index = match components.iter().position(|c| c.as_os_str() == CONTENT_DIR) {
+        Some(content_index) => content_index,
+        None => return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "error: did not find root content directory named .content which is a prerequisite.",
+        )),
+    };
+
+    let after_content = &components[content_index + 1..];
+
+    let author = after_content
+        .first()
+        .filter(|&c| c.as_os_str().to_string_lossy().starts_with('.'))
+        .map(|c| {
+            c.as_os_str()
+                .to_string_lossy()
+                .trim_start_matches('.')
+                .to_string()
+        });
+
+    let categories: Vec<String> = after_content
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    let after_path = PathBuf::from_iter(after_content.iter().map(|c| c.as_os_str()));
+    Ok((after_path, author, categories.join("/")))
+}
+
+fn category_dirs(
+    root: &Path,
+    discovery: &DiscoveryConfig,
+    author_dirs: bool,
+    ignores: &mut IgnoreFiles,
+) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(root)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    ignores.enter(root)?;
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        if discovery.symlinks == Symlinks::Skip && entry.file_type()?.is_symlink() {
+            eprintln!("warning: skipped symlink {}.", path.display());
+            continue;
+        }
+        if !path.is_dir()
+            || name == DRAFTS_DIR
+            || discovery
+                .ignore
+                .iter()
+                .any(|pattern| glob_match(pattern, &name))
+            || ignores.is_ignored(&path, true)
+        {
+            continue;
+        }
+        // A dot directory holds one author's categories.
+        if !name.starts_with('.') {
+            dirs.push(path);
+        } else if author_dirs {
+            dirs.extend(category_dirs(&path, discovery, false, ignores)?);
+        }
+    }
+    ignores.leave();
+
+    Ok(dirs)
+}
+
+impl Config {
+    pub fn category_path(&self, path: &str) -> String {
+        let category_path = url::from_path(&self.output_content_root_path);
+        if category_path.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", category_path, path)
+        }
+    }
+
+    pub fn category_configs(&self) -> io::Result<Vec<Config>> {
+        let mut configs = Vec::new();
+        for input in category_dirs(
+            &self.input_content_root_path,
+            &self.discovery,
+            true,
+            &mut IgnoreFiles::default(),
+        )? {
+            let (output_content_root_path, derived_author, category) = content_path_parts(&input)?;
+            let mut cfg = self.clone();
+            cfg.input_content_root_path = input;
+            cfg.output_content_root_path = output_content_root_path;
+            cfg.author = self.author.clone().or(derived_author);
+            cfg.category = category;
+            cfg.category_config = CategoryConfig::default();
+            apply_category_file(&mut cfg)?;
+            configs.push(cfg);
+        }
+        Ok(configs)
+    }
+
+    pub fn from_args(argv: &[String]) -> Result<Config, WebWeaverError> {
+        Config::load(argv).map_err(|err| WebWeaverError::Config(err.to_string()))
+    }
+
+    fn load(argv: &[String]) -> io::Result<Config> {
+        let (command, args) = cli_args(argv)?;
+        let (file, config_dir) = config_file(args.config_path.as_deref(), args.profile.as_deref())?;
+
+        let input = match args.input {
+            Some(input) => Some(input),
+            None => config_path(&file, "input", &config_dir)?,
+        };
+        let input_content_root_path = match (input, &args.category) {
+            (input, Some(category)) => input
+                .as_deref()
+                .and_then(content_root)
+                .unwrap_or_else(|| config_dir.join(CONTENT_DIR))
+                .join(category),
+            (Some(input), None) => input,
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "specify content path in first argument or as input in webweaver.toml.",
+                ))
+            }
+        };
+
+        let output_root_path = match args.output {
+            Some(output) => output,
+            None => config_path(&file, "output", &config_dir)?
+                .unwrap_or_else(|| PathBuf::from("content")),
+        };
+
+        let templates_root_path = match config_path(&file, "templates", &config_dir)? {
+            Some(templates) => Some(templates),
+            None => Some(config_dir.join(DEFAULT_TEMPLATES_DIR)).filter(|dir| dir.is_dir()),
+        };
+
+        let static_root_path = match config_path(&file, "static", &config_dir)? {
+            Some(static_root) => Some(static_root),
+            None => Some(config_dir.join(DEFAULT_STATIC_DIR)).filter(|dir| dir.is_dir()),
+        };
+
+        let archetypes_root_path = match config_path(&file, "archetypes", &config_dir)? {
+            Some(archetypes) => Some(archetypes),
+            None => Some(config_dir.join(DEFAULT_ARCHETYPES_DIR)).filter(|dir| dir.is_dir()),
+        };
+
+        let data_root_path = match config_path(&file, "data", &config_dir)? {
+            Some(data) => Some(data),
+            None => Some(config_dir.join(DEFAULT_DATA_DIR)).filter(|dir| dir.is_dir()),
+        };
+
+        let author_profiles = match config_path(&file, "authors", &config_dir)? {
+            Some(authors) => authors::load_profiles(&authors)?,
+            None => match Some(config_dir.join(DEFAULT_AUTHORS_FILE)).filter(|file| file.is_file())
+            {
+                Some(authors) => authors::load_profiles(&authors)?,
+                None => Profiles::new(),
+            },
+        };
+
+        let (output_content_root_path, derived_author, derived_category) =
+            content_path_parts(&input_content_root_path)?;
+
+        let category = config_str(&file, None, "category")?.unwrap_or(derived_category);
+
+        let author = match args.author {
+            Some(author) => Some(author),
+            None => config_str(&file, Some("site"), "author")?.or(derived_author),
+        };
+
+        let title = match args.title {
+            Some(title) => title,
+            None => match config_str(&file, Some("site"), "title")? {
+                Some(title) => title,
+                None if category.is_empty() => String::from(DEFAULT_TITLE),
+                None => category.clone(),
+            },
+        };
+
+        let base_url = match args.base_url {
+            Some(base_url) => base_url,
+            None => {
+                config_str(&file, Some("site"), "base_url")?.unwrap_or_else(|| String::from("/"))
+            }
+        };
+
+        let language =
+            config_str(&file, Some("site"), "language")?.or_else(|| Some(String::from("en-us")));
+
+        let date_format = config_str(&file, Some("site"), "date_format")?
+            .unwrap_or_else(|| String::from(DEFAULT_DATE_FORMAT));
+        if !dates::is_valid_format(&date_format) {
+            return Err(config_type_err(
+                Some("site"),
+                "date_format",
+                "a valid strftime format",
+            ));
+        }
+
+        let site = SiteConfig {
+            description: config_str(&file, Some("site"), "description")?
+                .unwrap_or_else(|| title.clone()),
+            title,
+            canonical_base_url: config_str(&file, Some("site"), "canonical_base_url")?
+                .unwrap_or_else(|| base_url.clone()),
+            base_url,
+            locale: config_str(&file, Some("site"), "locale")?
+                .or_else(|| language.clone())
+                .unwrap_or_default(),
+            language,
+            image: config_str(&file, Some("site"), "image")?,
+            twitter: config_str(&file, Some("site"), "twitter")?,
+            date_format,
+            words_per_minute: config_usize(&file, Some("site"), "words_per_minute")?.unwrap_or(200),
+            jsonld: config_bool(&file, Some("site"), "jsonld")?.unwrap_or(true),
+        };
+
+        let feed = FeedConfig {
+            copyright: config_str(&file, Some("feed"), "copyright")?,
+            webmaster: config_str(&file, Some("feed"), "webmaster")?,
+            rss: config_bool(&file, Some("feed"), "rss")?.unwrap_or(true),
+            atom: config_bool(&file, Some("feed"), "atom")?.unwrap_or(true),
+            json: config_bool(&file, Some("feed"), "json")?.unwrap_or(true),
+            path: config_str(&file, Some("feed"), "path")?
+                .unwrap_or_else(|| String::from("feed.xml")),
+            atom_path: config_str(&file, Some("feed"), "atom_path")?
+                .unwrap_or_else(|| String::from("atom.xml")),
+            json_path: config_str(&file, Some("feed"), "json_path")?
+                .unwrap_or_else(|| String::from("feed.json")),
+            autodiscovery: config_bool(&file, Some("feed"), "autodiscovery")?.unwrap_or(true),
+            limit: config_usize(&file, Some("feed"), "limit")?,
+            content: feed_content(&file)?.unwrap_or(FeedContent::Full),
+        };
+
+        let discovery = DiscoveryConfig {
+            max_depth: config_usize(&file, Some("discovery"), "max_depth")?,
+            attachments: config_bool(&file, Some("discovery"), "attachments")?.unwrap_or(false),
+            ignore: config_str_array(&file, Some("discovery"), "ignore")?,
+            symlinks: match config_str(&file, Some("discovery"), "symlinks")?.as_deref() {
+                None | Some("follow") => Symlinks::Follow,
+                Some("skip") => Symlinks::Skip,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("discovery"),
+                        "symlinks",
+                        "\"follow\" or \"skip\"",
+                    ))
+                }
+            },
+        };
+
+        let sitemap = SitemapConfig {
+            enabled: config_bool(&file, Some("sitemap"), "enabled")?.unwrap_or(true),
+            max_urls: config_usize(&file, Some("sitemap"), "max_urls")?.unwrap_or(50_000),
+        };
+
+        let robots = RobotsConfig {
+            enabled: config_bool(&file, Some("robots"), "enabled")?.unwrap_or(true),
+            user_agent: config_str(&file, Some("robots"), "user_agent")?
+                .unwrap_or_else(|| String::from("*")),
+            allow: config_str_array(&file, Some("robots"), "allow")?,
+            disallow: config_str_array(&file, Some("robots"), "disallow")?,
+        };
+
+        let redirects = RedirectsConfig {
+            stubs: config_bool(&file, Some("redirects"), "stubs")?.unwrap_or(true),
+            format: match config_str(&file, Some("redirects"), "format")?.as_deref() {
+                None | Some("netlify") => RedirectFormat::Netlify,
+                Some("nginx") => RedirectFormat::Nginx,
+                Some("none") => RedirectFormat::None,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("redirects"),
+                        "format",
+                        "\"netlify\", \"nginx\" or \"none\"",
+                    ))
+                }
+            },
+        };
+
+        let search = SearchConfig {
+            enabled: config_bool(&file, Some("search"), "enabled")?.unwrap_or(true),
+            path: config_str(&file, Some("search"), "path")?
+                .unwrap_or_else(|| String::from("search.json")),
+        };
+
+        let images = ImagesConfig {
+            enabled: config_bool(&file, Some("images"), "enabled")?.unwrap_or(false),
+            widths: config_u32_array(&file, Some("images"), "widths")?,
+            webp: config_bool(&file, Some("images"), "webp")?.unwrap_or(true),
+            quality: config_usize(&file, Some("images"), "quality")?.unwrap_or(82) as u32,
+            command: config_str(&file, Some("images"), "command")?
+                .unwrap_or_else(|| String::from("magick")),
+        };
+
+        let links = LinksConfig {
+            rate: match args.rate {
+                Some(rate) => rate,
+                None => config_usize(&file, Some("links"), "rate")?
+                    .filter(|rate| *rate > 0)
+                    .map_or(5, |rate| rate.min(u32::MAX as usize) as u32),
+            },
+            timeout_secs: config_usize(&file, Some("links"), "timeout_secs")?.unwrap_or(10) as u64,
+            curl: config_str(&file, Some("links"), "curl")?.unwrap_or_else(|| String::from("curl")),
+        };
+
+        let deploy = DeployConfig {
+            target: match config_str(&file, Some("deploy"), "target")?.as_deref() {
+                None => None,
+                Some("rsync") => Some(DeployTarget::Rsync),
+                Some("s3") => Some(DeployTarget::S3),
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("deploy"),
+                        "target",
+                        "\"rsync\" or \"s3\"",
+                    ))
+                }
+            },
+            destination: config_str(&file, Some("deploy"), "destination")?,
+            endpoint: config_str(&file, Some("deploy"), "endpoint")?,
+            delete: config_bool(&file, Some("deploy"), "delete")?.unwrap_or(true),
+            dry_run: args.dry_run,
+            rsync: config_str(&file, Some("deploy"), "rsync")?
+                .unwrap_or_else(|| String::from("rsync")),
+            aws: config_str(&file, Some("deploy"), "aws")?.unwrap_or_else(|| String::from("aws")),
+        };
+
+        let webmention = WebmentionConfig {
+            endpoint: config_str(&file, Some("webmention"), "endpoint")?,
+            pingback: config_str(&file, Some("webmention"), "pingback")?,
+            dry_run: args.dry_run,
+        };
+
+        let activitypub = ActivityPubConfig {
+            enabled: config_bool(&file, Some("activitypub"), "enabled")?.unwrap_or(false),
+            username: config_str(&file, Some("activitypub"), "username")?,
+            inbox: config_str(&file, Some("activitypub"), "inbox")?,
+            public_key: config_str(&file, Some("activitypub"), "public_key")?
+                .map(|path| config_dir.join(path)),
+        };
+
+        let index = IndexConfig {
+            page_size: config_usize(&file, Some("index"), "page_size")?,
+            group_by: match config_str(&file, Some("index"), "group_by")?.as_deref() {
+                None | Some("year") => IndexGrouping::Year,
+                Some("month") => IndexGrouping::Month,
+                Some("category") => IndexGrouping::Category,
+                Some("none") => IndexGrouping::None,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("index"),
+                        "group_by",
+                        "\"year\", \"month\", \"category\" or \"none\"",
+                    ))
+                }
+            },
+            order: match config_str(&file, Some("index"), "order")?.as_deref() {
+                None | Some("desc") => SortOrder::Desc,
+                Some("asc") => SortOrder::Asc,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("index"),
+                        "order",
+                        "\"desc\" or \"asc\"",
+                    ))
+                }
+            },
+            same_day_by_title: config_bool(&file, Some("index"), "same_day_by_title")?
+                .unwrap_or(false),
+            microformats: config_bool(&file, Some("index"), "microformats")?.unwrap_or(false),
+        };
+
+        let asciidoc = AsciidocConfig {
+            global: asciidoc_attributes("asciidoc", file.get("asciidoc"))?,
+            categories: match config_value(&file, Some("asciidoc"), "categories") {
+                None => BTreeMap::new(),
+                Some(Value::Table(categories)) => categories
+                    .iter()
+                    .map(|(category, table)| {
+                        let section = format!("asciidoc.categories.{}", category);
+                        Ok((
+                            category.clone(),
+                            asciidoc_attributes(&section, Some(table))?,
+                        ))
+                    })
+                    .collect::<io::Result<_>>()?,
+                Some(_) => return Err(config_type_err(Some("asciidoc"), "categories", "a table")),
+            },
+        };
+
+        let podcast = PodcastConfig {
+            categories: config_str_array(&file, Some("podcast"), "categories")?,
+            author: config_str(&file, Some("podcast"), "author")?,
+            owner_name: config_str(&file, Some("podcast"), "owner_name")?,
+            owner_email: config_str(&file, Some("podcast"), "owner_email")?,
+            image: config_str(&file, Some("podcast"), "image")?,
+            category: config_str(&file, Some("podcast"), "category")?,
+            explicit: config_bool(&file, Some("podcast"), "explicit")?.unwrap_or(false),
+            kind: match config_str(&file, Some("podcast"), "type")?.as_deref() {
+                None | Some("episodic") => PodcastType::Episodic,
+                Some("serial") => PodcastType::Serial,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("podcast"),
+                        "type",
+                        "\"episodic\" or \"serial\"",
+                    ))
+                }
+            },
+        };
+
+        let compress = CompressConfig {
+            gzip: config_bool(&file, Some("compress"), "gzip")?.unwrap_or(false),
+            brotli: config_bool(&file, Some("compress"), "brotli")?.unwrap_or(false),
+            extensions: match config_value(&file, Some("compress"), "extensions") {
+                Some(_) => config_str_array(&file, Some("compress"), "extensions")?,
+                None => DEFAULT_COMPRESS_EXTENSIONS
+                    .iter()
+                    .map(|ext| ext.to_string())
+                    .collect(),
+            },
+            min_size: config_usize(&file, Some("compress"), "min_size")?.unwrap_or(256) as u64,
+            gzip_command: config_str(&file, Some("compress"), "gzip_command")?
+                .unwrap_or_else(|| String::from("gzip")),
+            brotli_command: config_str(&file, Some("compress"), "brotli_command")?
+                .unwrap_or_else(|| String::from("brotli")),
+        };
+
+        let protected = ProtectedConfig {
+            password: config_str(&file, Some("protected"), "password")?.unwrap_or_default(),
+            iterations: match config_usize(&file, Some("protected"), "iterations")? {
+                None => 200_000,
+                Some(iterations) => u32::try_from(iterations)
+                    .ok()
+                    .filter(|iterations| *iterations > 0)
+                    .ok_or_else(|| {
+                        config_type_err(Some("protected"), "iterations", "a positive integer")
+                    })?,
+            },
+        };
+
+        let comments = CommentsConfig {
+            provider: match config_str(&file, Some("comments"), "provider")?.as_deref() {
+                None => None,
+                Some("giscus") => Some(CommentsProvider::Giscus),
+                Some("utterances") => Some(CommentsProvider::Utterances),
+                Some("isso") => Some(CommentsProvider::Isso),
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("comments"),
+                        "provider",
+                        "\"giscus\", \"utterances\" or \"isso\"",
+                    ))
+                }
+            },
+            repo: config_str(&file, Some("comments"), "repo")?,
+            repo_id: config_str(&file, Some("comments"), "repo_id")?,
+            category: config_str(&file, Some("comments"), "category")?,
+            category_id: config_str(&file, Some("comments"), "category_id")?,
+            label: config_str(&file, Some("comments"), "label")?,
+            theme: config_str(&file, Some("comments"), "theme")?,
+            url: config_str(&file, Some("comments"), "url")?,
+        };
+        let required: &[(&str, &Option<String>)] = match comments.provider {
+            None => &[],
+            Some(CommentsProvider::Giscus) => &[
+                ("repo", &comments.repo),
+                ("repo_id", &comments.repo_id),
+                ("category_id", &comments.category_id),
+            ],
+            Some(CommentsProvider::Utterances) => &[("repo", &comments.repo)],
+            Some(CommentsProvider::Isso) => &[("url", &comments.url)],
+        };
+        if let Some((key, _)) = required.iter().find(|(_, value)| value.is_none()) {
+            return Err(config_type_err(
+                Some("comments"),
+                key,
+                "set for this comments provider",
+            ));
+        }
+
+        let toc = TocConfig {
+            enabled: config_bool(&file, Some("toc"), "enabled")?.unwrap_or(true),
+            min_headings: config_usize(&file, Some("toc"), "min_headings")?.unwrap_or(4),
+            levels: config_usize(&file, Some("toc"), "levels")?
+                .unwrap_or(2)
+                .clamp(1, 5),
+        };
+
+        let highlight = HighlightConfig {
+            enabled: config_bool(&file, Some("highlight"), "enabled")?.unwrap_or(false),
+            theme: match config_str(&file, Some("highlight"), "theme")? {
+                None => &THEMES[0],
+                Some(name) => highlight::theme(&name).ok_or_else(|| {
+                    let names: Vec<String> = THEMES
+                        .iter()
+                        .map(|theme| format!("\"{}\"", theme.name))
+                        .collect();
+                    config_type_err(
+                        Some("highlight"),
+                        "theme",
+                        &format!("one of {}", names.join(", ")),
+                    )
+                })?,
+            },
+            stylesheet: config_str(&file, Some("highlight"), "stylesheet")?
+                .filter(|stylesheet| !stylesheet.is_empty()),
+        };
+
+        let diagram_command = |key: &str, default: &[&str]| -> io::Result<Vec<String>> {
+            let command = config_str_array(&file, Some("diagrams"), key)?;
+            if command.is_empty() {
+                return Ok(default.iter().map(|arg| arg.to_string()).collect());
+            }
+            Ok(command)
+        };
+        let diagrams = DiagramsConfig {
+            enabled: config_bool(&file, Some("diagrams"), "enabled")?.unwrap_or(false),
+            mermaid: diagram_command("mermaid", &["mmdc", "-i", "{input}", "-o", "{output}"])?,
+            plantuml: diagram_command("plantuml", &["plantuml", "-tsvg", "-pipe"])?,
+            graphviz: diagram_command("graphviz", &["dot", "-Tsvg"])?,
+        };
+
+        let math = MathConfig {
+            enabled: config_bool(&file, Some("math"), "enabled")?.unwrap_or(false),
+            katex: config_str(&file, Some("math"), "katex")?
+                .unwrap_or_else(|| String::from("https://cdn.jsdelivr.net/npm/katex@0.16.11/dist"))
+                .trim_end_matches('/')
+                .to_string(),
+            stem: config_str(&file, Some("math"), "stem")?
+                .unwrap_or_else(|| String::from("latexmath")),
+        };
+
+        let smartypants = SmartypantsConfig {
+            enabled: config_bool(&file, Some("smartypants"), "enabled")?.unwrap_or(false),
+        };
+
+        let bibliography =
+            config_str(&file, Some("citations"), "bibliography")?.map(|path| config_dir.join(path));
+        let citations = CitationsConfig {
+            entries: match &bibliography {
+                Some(path) => citations::load(path)?,
+                None => Bibliography::new(),
+            },
+            bibliography,
+            title: config_str(&file, Some("citations"), "title")?
+                .unwrap_or_else(|| String::from("References")),
+        };
+
+        let mut taxonomies = builtin_taxonomies();
+        match file.get("taxonomies") {
+            None => {}
+            Some(Value::Table(configured)) => {
+                for (name, value) in configured {
+                    match taxonomies
+                        .iter()
+                        .position(|taxonomy| taxonomy.name == *name)
+                    {
+                        Some(i) => {
+                            taxonomies[i] = taxonomy_config(name, value, taxonomies[i].clone())?
+                        }
+                        None => {
+                            check_taxonomy_name(name)?;
+                            taxonomies.push(taxonomy_config(
+                                name,
+                                value,
+                                TaxonomyConfig::new(name),
+                            )?);
+                        }
+                    }
+                }
+            }
+            Some(_) => return Err(config_type_err(None, "taxonomies", "a table")),
+        }
+
+        let build = BuildConfig {
+            incremental: config_bool(&file, Some("build"), "incremental")?.unwrap_or(true),
+            drafts: args.drafts || config_bool(&file, Some("build"), "drafts")?.unwrap_or(false),
+            future: args.future || config_bool(&file, Some("build"), "future")?.unwrap_or(false),
+            clean: args.clean || config_bool(&file, Some("build"), "clean")?.unwrap_or(false),
+            atomic: config_bool(&file, Some("build"), "atomic")?.unwrap_or(false),
+            force: args.force,
+            profile: args.profile_build,
+            progress: config_bool(&file, Some("build"), "progress")?.unwrap_or(true),
+            stream_threshold: config_usize(&file, Some("build"), "stream_threshold_kb")?
+                .unwrap_or(4096) as u64
+                * 1024,
+            prune: config_bool(&file, Some("build"), "prune")?.unwrap_or(true),
+            // Profiling runs one job at a time, so each file's time is its own.
+            jobs: match (args.jobs, args.profile_build) {
+                (Some(jobs), _) => jobs,
+                (None, true) => 1,
+                (None, false) => config_usize(&file, Some("build"), "jobs")?
+                    .filter(|jobs| *jobs > 0)
+                    .unwrap_or_else(parallel::default_jobs),
+            },
+            watch: args.watch,
+            watch_interval_ms: config_usize(&file, Some("build"), "watch_interval_ms")?
+                .unwrap_or(500) as u64,
+            html: args.html || config_bool(&file, Some("build"), "html")?.unwrap_or(false),
+            asciidoctor: config_str(&file, Some("build"), "asciidoctor")?
+                .unwrap_or_else(|| String::from("asciidoctor")),
+            keep_going: args.keep_going
+                || config_bool(&file, Some("build"), "keep_going")?.unwrap_or(false),
+            mtime_updated: config_bool(&file, Some("build"), "mtime_updated")?.unwrap_or(false),
+            git_dates: config_bool(&file, Some("build"), "git_dates")?.unwrap_or(false),
+            heading_titles: config_bool(&file, Some("build"), "heading_titles")?.unwrap_or(false),
+            emoji: config_bool(&file, Some("build"), "emoji")?.unwrap_or(true),
+            git: config_str(&file, Some("build"), "git")?.unwrap_or_else(|| String::from("git")),
+            all: args.all || config_bool(&file, Some("build"), "all")?.unwrap_or(false),
+            manifest: config_bool(&file, Some("build"), "manifest")?.unwrap_or(true),
+            minify: config_bool(&file, Some("build"), "minify")?.unwrap_or(false),
+            checksums: config_bool(&file, Some("build"), "checksums")?.unwrap_or(false),
+            check_links: match config_str(&file, Some("build"), "check_links")?.as_deref() {
+                Some("off") => LinkCheck::Off,
+                None | Some("warn") => LinkCheck::Warn,
+                Some("error") => LinkCheck::Error,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("build"),
+                        "check_links",
+                        "\"off\", \"warn\" or \"error\"",
+                    ))
+                }
+            },
+            slug_collisions: match config_str(&file, Some("build"), "slug_collisions")?.as_deref() {
+                None | Some("error") => SlugCollisions::Error,
+                Some("suffix") => SlugCollisions::Suffix,
+                Some(_) => {
+                    return Err(config_type_err(
+                        Some("build"),
+                        "slug_collisions",
+                        "\"error\" or \"suffix\"",
+                    ))
+                }
+            },
+        };
+
+        let serve = ServeConfig {
+            host: match args.host {
+                Some(host) => host,
+                None => config_str(&file, Some("serve"), "host")?
+                    .unwrap_or_else(|| String::from("127.0.0.1")),
+            },
+            port: match args.port {
+                Some(port) => port,
+                None => match config_usize(&file, Some("serve"), "port")? {
+                    Some(port) => u16::try_from(port).map_err(|_| {
+                        config_type_err(Some("serve"), "port", "a valid port number")
+                    })?,
+                    None => 8080,
+                },
+            },
+        };
+
+        let mut cfg = Config {
+            command,
+            input_content_root_path,
+            output_content_root_path,
+            output_root_path,
+            templates_root_path,
+            static_root_path,
+            archetypes_root_path,
+            data_root_path,
+            author,
+            author_profiles,
+            category,
+            category_config: CategoryConfig::default(),
+            site,
+            feed,
+            discovery,
+            sitemap,
+            robots,
+            search,
+            redirects,
+            images,
+            links,
+            deploy,
+            webmention,
+            activitypub,
+            index,
+            toc,
+            math,
+            smartypants,
+            citations,
+            taxonomies,
+            highlight,
+            diagrams,
+            asciidoc,
+            podcast,
+            comments,
+            compress,
+            protected,
+            build,
+            serve,
+            translation: None,
+        };
+
+        let input_exists = cfg.input_content_root_path.exists();
+
+        // new creates the directory of a category that has no posts yet.
+        if !input_exists && !matches!(cfg.command, Command::New { .. }) {
+            return Err(io::Error::other("input content path does not exist."));
+        }
+
+        let input_is_dir = cfg.input_content_root_path.is_dir();
+
+        if input_exists && !input_is_dir {
+            return Err(io::Error::other("input content path is not a directory."));
+        }
+
+        if input_is_dir {
+            apply_category_file(&mut cfg)?;
+        }
+
+        if cfg.output_root_path.exists() && !cfg.output_root_path.is_dir() {
+            return Err(io::Error::other(format!(
+                "output path {} is not a directory.",
+                cfg.output_root_path.display()
+            )));
+        }
+
+        if absolute(&cfg.output_root_path)?.starts_with(absolute(&cfg.input_content_root_path)?) {
+            return Err(io::Error::other(format!(
+                "output path {} must not be inside the input content path.",
+                cfg.output_root_path.display()
+            )));
+        }
+
+        Ok(cfg)
+    }
+}
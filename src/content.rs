@@ -0,0 +1,1369 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+
+use crate::attachment::{self, Attachment};
+use crate::citations;
+use crate::comments::comments_block;
+use crate::config::{Config, SortOrder, SERIES, TAGS};
+use crate::dates::format_date;
+use crate::diagrams;
+use crate::discovery::ContentFile;
+use crate::emoji;
+use crate::error::WebWeaverError;
+use crate::feed;
+use crate::footnotes;
+use crate::frontmatter::{self, FrontMatter};
+use crate::highlight;
+use crate::html;
+use crate::i18n;
+use crate::markdown;
+use crate::math;
+use crate::notebook;
+use crate::org;
+use crate::protect;
+use crate::shortcodes;
+use crate::smartypants;
+use crate::stream;
+use crate::template::{context, context_value, optional, strings, Templates};
+use crate::toc;
+use crate::url;
+use crate::value::{Table, Value};
+
+pub const DRAFT_PREFIX: &str = "DRAFT_";
+// _header.adoc and _footer.adoc in a category directory wrap every post below it.
+const PARTIALS: [&str; 2] = ["_header", "_footer"];
+const MORE_MARKERS: [&str; 2] = ["<!--more-->", "// more"];
+// How far into the body the excerpt looks for a more marker.
+const EXCERPT_LIMIT: usize = 256 * 1024;
+const ASCIIDOC_LINK_MACROS: [&str; 7] = [
+    "image:",
+    "link:",
+    "xref:",
+    "mailto:",
+    "https://",
+    "http://",
+    "footnote:",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ContentFormat {
+    #[default]
+    AsciiDoc,
+    Markdown,
+    Org,
+}
+
+impl ContentFormat {
+    pub fn from_ext(file_ext: &str) -> ContentFormat {
+        match file_ext.to_ascii_lowercase().as_str() {
+            // Notebooks are read as the Markdown notebook::to_markdown makes of them.
+            "md" | "markdown" | "ipynb" => ContentFormat::Markdown,
+            "org" => ContentFormat::Org,
+            _ => ContentFormat::AsciiDoc,
+        }
+    }
+
+    pub fn is_content_file(path: &Path) -> bool {
+        matches!(
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+                .as_deref(),
+            Some("adoc" | "asciidoc" | "asc" | "md" | "markdown" | "ipynb" | "org")
+        )
+    }
+
+    pub fn output_ext<'a>(&self, file_ext: &'a str) -> &'a str {
+        match self {
+            ContentFormat::AsciiDoc => file_ext,
+            ContentFormat::Markdown | ContentFormat::Org => "html",
+        }
+    }
+
+    pub fn split_front_matter(self, contents: &str) -> io::Result<(Option<FrontMatter>, &str)> {
+        match self {
+            ContentFormat::Org => frontmatter::split_org(contents),
+            _ => frontmatter::split(contents),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ContentMeta {
+    pub date: NaiveDate,
+    pub updated: Option<NaiveDate>,
+    pub name: String,
+    // The name came from the body's first heading, which the page leaves out.
+    pub title_heading: bool,
+    pub filesystem_friendly_name: String,
+    // The source file's name, which orders posts that share a date and slug.
+    pub file_name: String,
+    pub file_ext: String,
+    pub format: ContentFormat,
+    pub categories: Vec<String>,
+    pub tags: Vec<String>,
+    pub summary: Option<String>,
+    pub draft: bool,
+    pub author: Option<String>,
+    pub guid: Option<String>,
+    pub enclosure: Option<Enclosure>,
+    pub series: Option<String>,
+    // The terms of each [taxonomies] taxonomy, by its name.
+    pub taxonomies: BTreeMap<String, Vec<String>>,
+    pub image: Option<String>,
+    pub aliases: Vec<String>,
+    pub canonical: Option<String>,
+    pub toc: Option<bool>,
+    // false leaves the comments block off this post.
+    pub comments: Option<bool>,
+    // Overrides [math] enabled for this post.
+    pub math: Option<bool>,
+    // Overrides [smartypants] enabled for this post.
+    pub smartypants: Option<bool>,
+    // Properties merged over the page's JSON-LD; None leaves the block out.
+    pub jsonld: Option<Table>,
+    pub words: usize,
+    // Minutes at [site] words_per_minute, filled in by the build; 0 hides it.
+    pub reading_time: usize,
+    pub language: Option<String>,
+    pub translations: Vec<Translation>,
+    pub path: String,
+    // The front matter permalink, which replaces the dated path above.
+    pub permalink: Option<String>,
+    // An undated page from pages/, left out of the index, feeds and archives.
+    pub page: bool,
+    // The body is encrypted on the page and left out of feeds, search and the excerpt.
+    pub protected: bool,
+    // The front matter password, which overrides [protected] password.
+    pub password: Option<String>,
+    // Set by the build for large posts, whose AsciiDoc pages are copied together a line at a time;
+    // feeds and search get their summary in place of the body.
+    pub streamed: bool,
+    // A PDF or image post, whose page is a wrapper linking to the file.
+    pub attachment: Option<Attachment>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Translation {
+    pub language: String,
+    pub path: String,
+    pub format: ContentFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct Enclosure {
+    pub url: String,
+    pub length: u64,
+    pub mime_type: String,
+    // Podcast episode details, written as itunes: elements.
+    pub duration: Option<String>,
+    pub episode: Option<u64>,
+    pub season: Option<u64>,
+    pub explicit: Option<bool>,
+}
+
+impl ContentMeta {
+    pub fn output_path(&self) -> String {
+        match &self.language {
+            Some(language) => format!(
+                "{}/{}.{}.{}",
+                self.path, self.filesystem_friendly_name, language, self.file_ext
+            ),
+            None => format!(
+                "{}/{}.{}",
+                self.path, self.filesystem_friendly_name, self.file_ext
+            ),
+        }
+    }
+
+    pub fn modified(&self) -> NaiveDate {
+        self.updated.unwrap_or(self.date)
+    }
+}
+
+pub struct ContentUnit {
+    pub meta: ContentMeta,
+    pub contents: String,
+    pub description: String,
+    pub excerpt: String,
+}
+
+fn metadata_err(path: &Path, message: impl ToString) -> WebWeaverError {
+    WebWeaverError::Metadata {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    }
+}
+
+fn osstr_to_str_err(path: &Path) -> WebWeaverError {
+    metadata_err(path, "file name is not valid UTF-8.")
+}
+
+fn pathbuf_filename_get_err(path: &Path) -> WebWeaverError {
+    metadata_err(path, "file name has no stem or extension.")
+}
+
+fn parse_content_meta_data_err(path: &Path) -> WebWeaverError {
+    metadata_err(
+        path,
+        "file name must start with a YYYY-MM-DD_ date, or front matter must set date.",
+    )
+}
+
+fn enclosure_mime_type(url: &str) -> &'static str {
+    let ext = url
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn enclosure(front_matter: &FrontMatter) -> io::Result<Option<Enclosure>> {
+    let enclosure = match front_matter.table("enclosure")? {
+        Some(enclosure) => enclosure,
+        None => return Ok(None),
+    };
+    let url = enclosure.str("url")?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "front matter enclosure needs a url.",
+        )
+    })?;
+    let length = match enclosure.integer("length")? {
+        Some(length) => u64::try_from(length).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "front matter enclosure length must not be negative.",
+            )
+        })?,
+        None => 0,
+    };
+    let mime_type = enclosure
+        .str("type")?
+        .unwrap_or_else(|| enclosure_mime_type(&url).to_string());
+    let duration = enclosure.str("duration")?;
+    // Seconds, or [[hh:]mm:]ss.
+    if let Some(duration) = &duration {
+        let parts: Vec<&str> = duration.split(':').collect();
+        if parts.len() > 3
+            || parts
+                .iter()
+                .any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "front matter enclosure duration must be seconds or hh:mm:ss.",
+            ));
+        }
+    }
+    let number = |key: &str| -> io::Result<Option<u64>> {
+        match enclosure.integer(key)? {
+            Some(number) => u64::try_from(number).map(Some).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("front matter enclosure {} must not be negative.", key),
+                )
+            }),
+            None => Ok(None),
+        }
+    };
+
+    Ok(Some(Enclosure {
+        url,
+        length,
+        mime_type,
+        duration,
+        episode: number("episode")?,
+        season: number("season")?,
+        explicit: enclosure.bool("explicit")?,
+    }))
+}
+
+// Words with a letter or digit in them, over a body read in pieces that may split a word.
+#[derive(Default)]
+pub struct WordCount {
+    words: usize,
+    // Whether the word the last piece ended inside has a letter or digit so far.
+    open: Option<bool>,
+}
+
+impl WordCount {
+    pub fn add(&mut self, text: &str) {
+        let is_word = |word: &str| word.chars().any(char::is_alphanumeric);
+        if text.starts_with(char::is_whitespace) {
+            self.close();
+        }
+        let mut words = text.split_whitespace();
+        if let Some(word) = words.next() {
+            self.open = Some(self.open.take().unwrap_or(false) || is_word(word));
+        }
+        for word in words {
+            self.close();
+            self.open = Some(is_word(word));
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.close();
+        }
+    }
+
+    fn close(&mut self) {
+        if self.open.take() == Some(true) {
+            self.words += 1;
+        }
+    }
+
+    pub fn total(mut self) -> usize {
+        self.close();
+        self.words
+    }
+}
+
+pub fn reading_time(words: usize, words_per_minute: usize) -> usize {
+    if words_per_minute == 0 || words == 0 {
+        return 0;
+    }
+    words.div_ceil(words_per_minute).max(1)
+}
+
+// Posts from the same day by slug, then by source file name, so every run lists them alike;
+// the output path settles posts of the same name in different categories.
+pub fn same_day_order(a: &ContentMeta, b: &ContentMeta) -> Ordering {
+    a.filesystem_friendly_name
+        .cmp(&b.filesystem_friendly_name)
+        .then_with(|| a.file_name.cmp(&b.file_name))
+        .then_with(|| a.output_path().cmp(&b.output_path()))
+}
+
+pub fn newest_first(a: &ContentMeta, b: &ContentMeta) -> Ordering {
+    b.date.cmp(&a.date).then_with(|| same_day_order(a, b))
+}
+
+// Newest first unless [index] order = "asc"; same_day_by_title puts the title before the slug.
+pub fn sort_posts<T>(cfg: &Config, posts: &mut [T], meta: impl Fn(&T) -> &ContentMeta) {
+    posts.sort_by(|a, b| {
+        let (a, b) = (meta(a), meta(b));
+        let date = match cfg.index.order {
+            SortOrder::Desc => b.date.cmp(&a.date),
+            SortOrder::Asc => a.date.cmp(&b.date),
+        };
+        let title = if cfg.index.same_day_by_title {
+            a.name.to_lowercase().cmp(&b.name.to_lowercase())
+        } else {
+            Ordering::Equal
+        };
+        date.then(title).then_with(|| same_day_order(a, b))
+    });
+}
+
+// The first "= Title" (AsciiDoc) or "# Title" (Markdown) line outside code blocks, looked for a line at a time.
+pub struct TitleScan {
+    marker: Option<&'static str>,
+    fence: Option<String>,
+}
+
+impl TitleScan {
+    pub fn new(format: ContentFormat) -> TitleScan {
+        TitleScan {
+            marker: match format {
+                ContentFormat::AsciiDoc => Some("= "),
+                ContentFormat::Markdown => Some("# "),
+                ContentFormat::Org => None,
+            },
+            fence: None,
+        }
+    }
+
+    pub fn line(&mut self, line: &str) -> Option<String> {
+        let marker = self.marker?;
+        let line = line.trim_end();
+        if let Some(open) = &self.fence {
+            if line == open {
+                self.fence = None;
+            }
+            return None;
+        }
+        if matches!(line, "----" | "...." | "```" | "~~~" | "////" | "++++") {
+            self.fence = Some(line.to_string());
+            return None;
+        }
+        let text = line.strip_prefix(marker)?.trim_end_matches('#').trim();
+        Some(text.to_string()).filter(|text| !text.is_empty())
+    }
+}
+
+// The title heading and where it is in the body.
+fn title_heading(format: ContentFormat, body: &str) -> Option<(String, Range<usize>)> {
+    let mut scan = TitleScan::new(format);
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if let Some(text) = scan.line(line) {
+            return Some((text, start..offset));
+        }
+    }
+    None
+}
+
+// The body below the front matter, less the heading a title was taken from.
+fn post_body(meta: &ContentMeta, contents: &str) -> io::Result<String> {
+    let (_, body) = meta.format.split_front_matter(contents)?;
+    if !meta.title_heading {
+        return Ok(body.to_string());
+    }
+    match title_heading(meta.format, body) {
+        Some((_, line)) => Ok(format!(
+            "{}{}",
+            &body[..line.start],
+            body[line.end..].trim_start_matches(['\r', '\n'])
+        )),
+        None => Ok(body.to_string()),
+    }
+}
+
+fn expand_emoji(cfg: &Config, meta: &ContentMeta, body: String) -> String {
+    if cfg.build.emoji {
+        emoji::expand_body(meta.format, &body)
+    } else {
+        body
+    }
+}
+
+pub fn file_modified(path: &Path) -> Option<NaiveDate> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+    modified
+        .ok()
+        .map(|modified| DateTime::<Local>::from(modified).date_naive())
+}
+
+pub fn content_file_metadata(
+    cfg: &Config,
+    content_file: &ContentFile,
+) -> Result<ContentMeta, WebWeaverError> {
+    let heading_titles = cfg.build.heading_titles;
+    let path = &content_file.path;
+    let meta_err = |err: io::Error| metadata_err(path, err);
+    let file_stem = match path.file_stem() {
+        Some(file_stem) => match file_stem.to_str() {
+            Some(file_stem) => file_stem,
+            None => return Err(osstr_to_str_err(path)),
+        },
+        None => return Err(pathbuf_filename_get_err(path)),
+    };
+
+    let (file_stem, draft_prefix) = match file_stem.strip_prefix(DRAFT_PREFIX) {
+        Some(file_stem) => (file_stem, true),
+        None => (file_stem, false),
+    };
+    let (file_stem, language) = i18n::split_language(file_stem);
+
+    let file_ext = match path.extension() {
+        Some(file_ext) => match file_ext.to_str() {
+            Some(file_ext) => file_ext,
+            None => return Err(osstr_to_str_err(path)),
+        },
+        None => return Err(pathbuf_filename_get_err(path)),
+    };
+
+    let format = ContentFormat::from_ext(file_ext);
+    let mut words = WordCount::default();
+    let mut body_heading = None;
+    // An attachment's front matter can only come from its sidecar; its wrapper page is AsciiDoc.
+    let (front_matter, file_ext, attachment) = if content_file.attachment {
+        let attachment = Attachment {
+            mime_type: attachment::mime_type(path).unwrap_or("application/octet-stream"),
+            length: fs::metadata(path).map_err(meta_err)?.len(),
+            url: String::new(),
+        };
+        (None, "adoc", Some(attachment))
+    } else {
+        let (front_matter, body) = stream::open(path, format).map_err(meta_err)?;
+        let mut title_scan = TitleScan::new(format);
+        for piece in body {
+            let piece = piece.map_err(meta_err)?;
+            words.add(&piece.text);
+            if piece.line_start && body_heading.is_none() {
+                body_heading = title_scan.line(&piece.text);
+            }
+        }
+        (front_matter, file_ext, None)
+    };
+    let front_matter = frontmatter::with_sidecar(front_matter, path).map_err(meta_err)?;
+
+    let (front_matter_date, updated) = match &front_matter {
+        Some(front_matter) => (
+            front_matter.date("date").map_err(meta_err)?,
+            front_matter.date("updated").map_err(meta_err)?,
+        ),
+        None => (None, None),
+    };
+    // With [build] git_dates the first commit stands in for the file name's date.
+    let git_dates = content_file.git_dates;
+    let front_matter_date = front_matter_date.or(git_dates.map(|dates| dates.created));
+    let updated = updated.or(git_dates.map(|dates| dates.modified));
+
+    let split: Vec<&str> = file_stem.splitn(2, '_').collect();
+    let (date, name) = match (split.len(), front_matter_date) {
+        // Pages take their whole file name as the title, and are dated only for the sitemap.
+        _ if content_file.page.is_some() => match front_matter_date.or(file_modified(path)) {
+            Some(date) => (date, file_stem),
+            None => return Err(parse_content_meta_data_err(path)),
+        },
+        (2, Some(date)) => match NaiveDate::parse_from_str(split[0], "%Y-%m-%d") {
+            Ok(_) => (date, split[1]),
+            Err(_) => (date, file_stem),
+        },
+        (2, None) => match NaiveDate::parse_from_str(split[0], "%Y-%m-%d") {
+            Ok(date) => (date, split[1]),
+            Err(err) => {
+                return Err(metadata_err(
+                    path,
+                    format!("invalid date {}: {}.", split[0], err),
+                ))
+            }
+        },
+        (_, Some(date)) => (date, file_stem),
+        // A bare date file name leaves the title to the first heading.
+        (_, None) if heading_titles => match NaiveDate::parse_from_str(file_stem, "%Y-%m-%d") {
+            Ok(date) => (date, ""),
+            Err(_) => return Err(parse_content_meta_data_err(path)),
+        },
+        (_, None) => return Err(parse_content_meta_data_err(path)),
+    };
+
+    let (title, tags, summary, draft, author, guid, enclosure, series, image) = match &front_matter
+    {
+        Some(front_matter) => (
+            front_matter.str("title").map_err(meta_err)?,
+            front_matter.str_list("tags").map_err(meta_err)?,
+            front_matter.str("summary").map_err(meta_err)?,
+            front_matter.bool("draft").map_err(meta_err)?,
+            front_matter.str("author").map_err(meta_err)?,
+            front_matter.str("guid").map_err(meta_err)?,
+            enclosure(front_matter).map_err(meta_err)?,
+            front_matter.str("series").map_err(meta_err)?,
+            front_matter.str("image").map_err(meta_err)?,
+        ),
+        None => (None, Vec::new(), None, None, None, None, None, None, None),
+    };
+    let (aliases, canonical, toc, slug, comments, jsonld, permalink) = match &front_matter {
+        Some(front_matter) => (
+            front_matter.str_list("aliases").map_err(meta_err)?,
+            front_matter.str("canonical").map_err(meta_err)?,
+            front_matter.bool("toc").map_err(meta_err)?,
+            front_matter.str("slug").map_err(meta_err)?,
+            front_matter.bool("comments").map_err(meta_err)?,
+            front_matter.bool_or_table("jsonld").map_err(meta_err)?,
+            match front_matter.str("permalink").map_err(meta_err)? {
+                Some(permalink) => Some(permalink),
+                None => front_matter.str("path").map_err(meta_err)?,
+            },
+        ),
+        None => (Vec::new(), None, None, None, None, None, None),
+    };
+    let mut taxonomies = BTreeMap::new();
+    if let Some(front_matter) = &front_matter {
+        // Tags and series are read above, into fields of their own.
+        for taxonomy in cfg
+            .taxonomies
+            .iter()
+            .filter(|taxonomy| taxonomy.name != TAGS && taxonomy.name != SERIES)
+        {
+            let terms = front_matter.str_list(&taxonomy.name).map_err(meta_err)?;
+            if !terms.is_empty() {
+                taxonomies.insert(taxonomy.name.clone(), terms);
+            }
+        }
+    }
+    let (protected, password) = match &front_matter {
+        Some(front_matter) => (
+            front_matter.bool("protected").map_err(meta_err)?,
+            front_matter.str("password").map_err(meta_err)?,
+        ),
+        None => (None, None),
+    };
+    let (math, smartypants) = match &front_matter {
+        Some(front_matter) => (
+            front_matter.bool("math").map_err(meta_err)?,
+            front_matter.bool("smartypants").map_err(meta_err)?,
+        ),
+        None => (None, None),
+    };
+    if attachment.is_some() && protected.unwrap_or(password.is_some()) {
+        return Err(metadata_err(
+            path,
+            "a PDF or image post cannot be protected; its file is copied as it is.",
+        ));
+    }
+    let permalink = match (permalink, &content_file.page) {
+        (Some(permalink), _) => Some(permalink_path(&permalink).map_err(meta_err)?),
+        (None, Some(page)) => {
+            let name = friendly_filename(
+                slug.as_deref()
+                    .filter(|slug| !slug.trim().is_empty())
+                    .unwrap_or(file_stem),
+            );
+            let page = url::from_path(&page.join(name));
+            Some(permalink_path(&page).map_err(meta_err)?)
+        }
+        (None, None) => None,
+    };
+
+    let categories: Vec<String> = content_file
+        .categories_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let content_categories_path = categories.join("/");
+
+    let (year, month, day) = (date.year_ce().1, date.month(), date.day());
+    let year_month_day = format!("{}/{:02}/{:02}", year, month, day);
+
+    let categories_and_date_stamped_content_path =
+        format!("{}/{}", content_categories_path, year_month_day);
+
+    let heading = match &title {
+        None if heading_titles => body_heading,
+        _ => None,
+    };
+    let title_heading = heading.is_some();
+    let file_name = name;
+    let name = match (title, heading) {
+        (Some(title), _) | (None, Some(title)) => title,
+        (None, None) if !file_name.is_empty() => file_name.to_string(),
+        (None, None) => {
+            return Err(metadata_err(
+                path,
+                "file name has no title after the date, and the file has no title heading.",
+            ))
+        }
+    };
+
+    // A permalink post is the index page of its own directory.
+    let filesystem_friendly_name = match &permalink {
+        Some(_) => String::from("index"),
+        None => friendly_filename(
+            slug.as_deref()
+                .filter(|slug| !slug.trim().is_empty())
+                .unwrap_or(if file_name.is_empty() {
+                    &name
+                } else {
+                    file_name
+                }),
+        ),
+    };
+
+    let unit = ContentMeta {
+        date,
+        updated: updated.filter(|updated| *updated > date),
+        name,
+        title_heading,
+        filesystem_friendly_name,
+        file_name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        file_ext: format.output_ext(file_ext).to_string(),
+        format,
+        categories,
+        tags,
+        summary,
+        draft: draft.unwrap_or(draft_prefix || content_file.draft),
+        author: author.or_else(|| content_file.author.clone()),
+        guid,
+        enclosure,
+        series,
+        taxonomies,
+        image,
+        aliases,
+        canonical,
+        toc,
+        comments,
+        math,
+        smartypants,
+        jsonld: match jsonld {
+            Some(Value::Table(properties)) => Some(properties),
+            Some(Value::Boolean(false)) => None,
+            _ => Some(Table::new()),
+        },
+        words: words.total(),
+        reading_time: 0,
+        language,
+        translations: Vec::new(),
+        path: permalink
+            .clone()
+            .unwrap_or(categories_and_date_stamped_content_path),
+        permalink,
+        page: content_file.page.is_some(),
+        // A password protects the post unless protected: false says otherwise.
+        protected: protected.unwrap_or(password.is_some()),
+        password,
+        streamed: false,
+        attachment,
+    };
+
+    Ok(unit)
+}
+
+pub fn index_entry(cfg: &Config, meta: &ContentMeta, link_prefix: &str) -> String {
+    let macro_name = match meta.format {
+        ContentFormat::AsciiDoc => "xref",
+        ContentFormat::Markdown | ContentFormat::Org => "link",
+    };
+    format!(
+        "{}:{}{}[{}] — {}{}{}{}{}",
+        macro_name,
+        link_prefix,
+        meta.output_path(),
+        meta.name,
+        format_date(cfg, meta.date),
+        match meta.updated {
+            Some(updated) => format!(" · Updated on {}", format_date(cfg, updated)),
+            None => String::new(),
+        },
+        match meta.reading_time {
+            0 => String::new(),
+            minutes => format!(" · {} min read", minutes),
+        },
+        if meta.draft { " (draft)" } else { "" },
+        if meta.protected { " 🔒" } else { "" }
+    )
+}
+
+// A heading, in any format: one or more markers followed by a space.
+fn is_heading(line: &str, marker: char) -> bool {
+    let rest = line.trim_start_matches(marker);
+    rest.len() < line.len() && (rest.is_empty() || rest.starts_with(' '))
+}
+
+// Lines of the format's own markup, which the excerpt leaves out: AsciiDoc attribute entries,
+// titles, block attributes, delimiters, comments and block macros; Markdown headings, link definitions
+// and comments; Org keywords, headings and comments.
+fn is_markup_line(format: ContentFormat, line: &str) -> bool {
+    match format {
+        ContentFormat::AsciiDoc => {
+            let delimiter = line.len() >= 4
+                && ['-', '.', '+', '/', '*', '=', '_']
+                    .iter()
+                    .any(|delimiter| line.chars().all(|c| c == *delimiter));
+            delimiter
+                || is_heading(line, '=')
+                || (line.starts_with(':') && line[1..].contains(':'))
+                || (line.starts_with('[') && line.ends_with(']'))
+                || line.starts_with("//")
+                || line.starts_with("image::")
+                || line.starts_with("include::")
+        }
+        ContentFormat::Markdown => {
+            let link_definition = line.starts_with('[')
+                && line
+                    .split_once("]:")
+                    .is_some_and(|(label, _)| !label.contains(']'));
+            is_heading(line, '#') || link_definition || line.starts_with("<!--")
+        }
+        ContentFormat::Org => {
+            line.starts_with("#+") || line == "#" || line.starts_with("# ") || is_heading(line, '*')
+        }
+    }
+}
+
+// Reduce AsciiDoc link and image macros to their text so the excerpt reads the same anywhere.
+fn strip_macros(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((at, marker)) = ASCIIDOC_LINK_MACROS
+        .iter()
+        .filter_map(|marker| rest.find(marker).map(|at| (at, *marker)))
+        .min()
+    {
+        out.push_str(&rest[..at]);
+        let target_start = at + marker.len();
+        let target_len = rest[target_start..]
+            .find(|c: char| c == '[' || c.is_whitespace())
+            .unwrap_or(rest.len() - target_start);
+        let attrs_start = target_start + target_len;
+        let attrs_len = match rest[attrs_start..].strip_prefix('[') {
+            Some(attrs) => attrs.find(']'),
+            None => None,
+        };
+        let attrs_len = match attrs_len {
+            Some(attrs_len) => attrs_len,
+            None => {
+                out.push_str(&rest[at..attrs_start]);
+                rest = &rest[attrs_start..];
+                continue;
+            }
+        };
+
+        let text = rest[attrs_start + 1..attrs_start + 1 + attrs_len]
+            .split(',')
+            .next()
+            .unwrap_or("");
+        if text.is_empty() && marker.ends_with("//") {
+            out.push_str(&rest[at..attrs_start]);
+        } else {
+            out.push_str(text);
+        }
+        rest = &rest[attrs_start + attrs_len + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+pub fn excerpt(format: ContentFormat, body: &str) -> String {
+    let lines: Vec<&str> = body.lines().collect();
+    let text = match lines
+        .iter()
+        .position(|line| MORE_MARKERS.contains(&line.trim()))
+    {
+        Some(more) => lines[..more]
+            .iter()
+            .filter(|line| !is_markup_line(format, line.trim_start()))
+            .copied()
+            .collect::<Vec<&str>>(),
+        None => lines
+            .iter()
+            .map(|line| line.trim())
+            .skip_while(|line| line.is_empty() || is_markup_line(format, line))
+            .take_while(|line| !line.is_empty() && !is_markup_line(format, line))
+            .collect::<Vec<&str>>(),
+    }
+    .join("\n");
+    let text = shortcodes::strip(&text);
+
+    let text = match format {
+        ContentFormat::AsciiDoc => strip_macros(&text),
+        ContentFormat::Markdown => {
+            markdown::strip_tags(&markdown::to_html(&footnotes::strip(format, &text)))
+        }
+        ContentFormat::Org => markdown::strip_tags(&org::to_html(&footnotes::strip(format, &text))),
+    };
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub fn content_excerpt(meta: &ContentMeta, content_file_path: &Path) -> io::Result<String> {
+    if meta.protected || meta.attachment.is_some() {
+        return Ok(String::new());
+    }
+    // Read only up to a more marker, or EXCERPT_LIMIT; the title heading is markup the excerpt skips.
+    let (_, body) = stream::open(content_file_path, meta.format)?;
+    let mut text = String::new();
+    for piece in body {
+        let piece = piece?;
+        text.push_str(&piece.text);
+        let more = piece.line_start && MORE_MARKERS.contains(&piece.text.trim());
+        if more || text.len() >= EXCERPT_LIMIT {
+            break;
+        }
+    }
+    Ok(excerpt(meta.format, &text))
+}
+
+// "/about/" and "about" both give "about"; the page is written to about/index.html.
+fn permalink_path(permalink: &str) -> io::Result<String> {
+    let segments: Vec<&str> = permalink.trim_matches('/').split('/').collect();
+    let valid = segments.iter().all(|segment| {
+        !segment.is_empty()
+            && segment.chars().any(|c| c != '.')
+            && segment
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    });
+    if !valid {
+        return Err(io::Error::other(format!(
+            "permalink {:?} must be a path of letters, digits, '-', '_' and '.' below the output root.",
+            permalink
+        )));
+    }
+    Ok(segments.join("/"))
+}
+
+pub fn friendly_filename(name: &str) -> String {
+    let mut result = Vec::with_capacity(name.len());
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            result.push(c.to_ascii_lowercase());
+        } else if c == ' ' {
+            result.push('_');
+        }
+    }
+
+    result.iter().collect()
+}
+
+// Translations share the post's directory, so they link by file name.
+fn translation_values(meta: &ContentMeta) -> Value {
+    Value::Array(
+        meta.translations
+            .iter()
+            .map(|translation| {
+                let macro_name = match translation.format {
+                    ContentFormat::AsciiDoc => "xref",
+                    ContentFormat::Markdown | ContentFormat::Org => "link",
+                };
+                let file_name = translation.path.rsplit('/').next().unwrap_or("");
+                context_value([
+                    ("language", Value::String(translation.language.clone())),
+                    ("macro", Value::String(macro_name.to_string())),
+                    ("url", Value::String(file_name.to_string())),
+                ])
+            })
+            .collect(),
+    )
+}
+
+pub fn meta_context(meta: &ContentMeta) -> Table {
+    context([
+        ("title", Value::String(meta.name.clone())),
+        (
+            "date",
+            Value::String(meta.date.format("%Y-%m-%d").to_string()),
+        ),
+        (
+            "updated",
+            optional(
+                meta.updated
+                    .map(|updated| updated.format("%Y-%m-%d").to_string())
+                    .as_deref(),
+            ),
+        ),
+        ("summary", optional(meta.summary.as_deref())),
+        ("tags", strings(&meta.tags)),
+        ("categories", strings(&meta.categories)),
+        ("draft", Value::Boolean(meta.draft)),
+        ("protected", Value::Boolean(meta.protected)),
+        ("author", optional(meta.author.as_deref())),
+        ("path", Value::String(meta.output_path())),
+        ("language", optional(meta.language.as_deref())),
+        ("translations", translation_values(meta)),
+        ("words", Value::Integer(meta.words as i64)),
+        (
+            "reading_time",
+            match meta.reading_time {
+                0 => Value::Null,
+                minutes => Value::Integer(minutes as i64),
+            },
+        ),
+    ])
+}
+
+// The attribute lines of [asciidoc] and the category's overrides, as they go in a page header.
+fn adoc_attributes(cfg: &Config) -> (Vec<String>, Vec<String>) {
+    let (attributes, includes) = cfg.asciidoc.for_category(&cfg.category);
+    let attributes = feed::docinfo_attributes(cfg, "{base-path}")
+        .into_iter()
+        .chain(attributes.into_iter().map(|(name, value)| match value {
+            Some(value) if value.is_empty() => format!(":{}:", name),
+            Some(value) => format!(":{}: {}", name, value),
+            None => format!(":{}!:", name),
+        }))
+        .collect();
+    (attributes, includes)
+}
+
+// The nearest _header or _footer in the post's format, looking from its directory up to the input.
+fn partial(
+    cfg: &Config,
+    meta: &ContentMeta,
+    content_file_path: &Path,
+    name: &str,
+) -> io::Result<Option<String>> {
+    let exts: &[&str] = match meta.format {
+        ContentFormat::AsciiDoc => &["adoc", "asciidoc", "asc"],
+        ContentFormat::Markdown => &["md", "markdown"],
+        ContentFormat::Org => &["org"],
+    };
+    for dir in content_file_path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(&cfg.input_content_root_path))
+    {
+        for ext in exts {
+            let path = dir.join(format!("{}.{}", name, ext));
+            if path.is_file() {
+                let partial = fs::read_to_string(&path)?;
+                return Ok(Some(partial.trim_end().to_string()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// The _header and _footer the post gets, for the build cache.
+pub fn partials(
+    cfg: &Config,
+    meta: &ContentMeta,
+    content_file_path: &Path,
+) -> io::Result<Vec<Option<String>>> {
+    PARTIALS
+        .iter()
+        .map(|name| partial(cfg, meta, content_file_path, name))
+        .collect()
+}
+
+pub fn is_partial(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| PARTIALS.contains(&stem))
+}
+
+// The way back to the output root from a page in dir, which is relative to it.
+pub fn base_path(dir: &str) -> String {
+    dir.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|_| "..")
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+// The header every generated AsciiDoc page starts with, up to its title.
+pub fn adoc_prologue(cfg: &Config, base_path: &str) -> String {
+    let (attributes, includes) = adoc_attributes(cfg);
+    let mut prologue = format!(":base-path: {}\n", base_path);
+    for attribute in attributes {
+        prologue.push_str(&attribute);
+        prologue.push('\n');
+    }
+    prologue.push('\n');
+    for include in &includes {
+        prologue.push_str(&format!("include::{}[]\n", include));
+    }
+    if !includes.is_empty() {
+        prologue.push('\n');
+    }
+    prologue
+}
+
+pub fn content_unit_contents(
+    cfg: &Config,
+    meta: &ContentMeta,
+    content_file_path: &Path,
+    excerpt: &str,
+    series: &Value,
+    templates: &Templates,
+) -> io::Result<(String, BTreeSet<PathBuf>)> {
+    // A streamed body is copied in by stream::write_page in place of its placeholder.
+    let (body, headings) = if meta.attachment.is_some() {
+        (
+            attachment::attachment_body(meta, content_file_path, templates),
+            0,
+        )
+    } else if meta.streamed {
+        let headings = stream::adoc_headings(meta, content_file_path)?;
+        (String::from(stream::BODY), headings)
+    } else {
+        let body = post_body(meta, &fs::read_to_string(content_file_path)?)?;
+        let body = expand_emoji(cfg, meta, body);
+        let (body, _) = shortcodes::expand(&templates.shortcodes, meta.format, &body);
+        let (body, citations) = citations::extract(cfg, meta, &body);
+        let body = body + &citations::bibliography_adoc(cfg, &citations);
+        let headings = toc::adoc_headings(&body);
+        (body, headings)
+    };
+    let (body, mut assets) = diagrams::render_diagrams(cfg, meta, body)?;
+    let (mut attributes, includes) = adoc_attributes(cfg);
+    if math::wants_math(cfg, meta) {
+        attributes.push(format!(":stem: {}", cfg.math.stem));
+    }
+    // A protected body is rendered to HTML on its own, with the site attributes, and passed through encrypted.
+    let (body, protected_assets) = if meta.protected {
+        let source = format!(
+            ":base-path: {}\n{}\n\n{}",
+            base_path(&meta.path),
+            attributes.join("\n"),
+            body
+        );
+        let html = html::render_fragment(cfg, &source)?;
+        let (block, assets) =
+            protect::protected_body(cfg, meta, content_file_path, &html, templates)?;
+        (format!("++++\n{}\n++++\n", block), assets)
+    } else {
+        (body, BTreeSet::new())
+    };
+
+    let mut context = meta_context(meta);
+    context.insert(
+        String::from("base_path"),
+        Value::String(base_path(&meta.path)),
+    );
+    context.insert(
+        String::from("description"),
+        optional(
+            meta.summary
+                .as_ref()
+                .map(|summary| summary.replace('\n', " "))
+                .as_deref()
+                .or(Some(excerpt).filter(|excerpt| !excerpt.is_empty())),
+        ),
+    );
+    context.insert(String::from("excerpt"), Value::String(excerpt.to_string()));
+    context.insert(String::from("body"), Value::String(body.to_string()));
+    context.insert(String::from("series"), series.clone());
+    context.insert(String::from("attributes"), strings(&attributes));
+    context.insert(String::from("includes"), strings(&includes));
+    for name in PARTIALS {
+        context.insert(
+            name.trim_start_matches('_').to_string(),
+            optional(partial(cfg, meta, content_file_path, name)?.as_deref()),
+        );
+    }
+    context.insert(
+        String::from("toc"),
+        Value::Boolean(!meta.protected && toc::wants_toc(cfg, meta, headings)),
+    );
+    context.insert(
+        String::from("toc_levels"),
+        Value::String(cfg.toc.levels.to_string()),
+    );
+    context.insert(
+        String::from("comments"),
+        Value::String(comments_block(cfg, meta, templates)),
+    );
+
+    assets.extend(protected_assets);
+    Ok((templates.content_adoc.render(&context), assets))
+}
+
+pub fn html_unit_contents(
+    cfg: &Config,
+    meta: &ContentMeta,
+    content_file_path: &Path,
+    templates: &Templates,
+) -> io::Result<(String, BTreeSet<PathBuf>)> {
+    let contents = notebook::read_source(content_file_path)?;
+    let body = expand_emoji(cfg, meta, post_body(meta, &contents)?);
+    let (body, shortcode_html) = shortcodes::expand(&templates.shortcodes, meta.format, &body);
+    let (body, mut assets) = diagrams::render_diagrams(cfg, meta, body)?;
+    let (body, formulas) = if math::wants_math(cfg, meta) {
+        math::protect(&body)
+    } else {
+        (body, Vec::new())
+    };
+    let (body, citations) = citations::extract(cfg, meta, &body);
+    let (body, notes) = footnotes::extract(meta.format, &body);
+    let convert: fn(&str) -> String = match meta.format {
+        ContentFormat::Org => org::to_html,
+        _ => markdown::to_html,
+    };
+    // The references come before the footnotes, which may cite them.
+    let body = convert(&body) + &citations::bibliography_html(cfg, &citations);
+    let body = footnotes::restore(&body, &notes, convert);
+    let body = citations::restore(&body, &citations);
+    let body = shortcodes::restore(&body, &shortcode_html);
+    let body = math::restore(&body, &formulas);
+    let body = if cfg.highlight.enabled {
+        highlight::highlight_html(cfg, &body)
+    } else {
+        body
+    };
+    let body = if smartypants::wants_smartypants(cfg, meta) {
+        smartypants::smarten_html(&body)
+    } else {
+        body
+    };
+    let body = toc::with_html_toc(cfg, meta, body);
+    let (body, protected_assets) = if meta.protected {
+        protect::protected_body(cfg, meta, content_file_path, &body, templates)?
+    } else {
+        (body, BTreeSet::new())
+    };
+    let [header, footer] = PARTIALS.map(|name| {
+        partial(cfg, meta, content_file_path, name).map(|partial| match (partial, meta.format) {
+            (None, _) => String::new(),
+            (Some(partial), ContentFormat::Org) => org::to_html(&partial),
+            (Some(partial), _) => markdown::to_html(&partial),
+        })
+    });
+
+    let contents = format!(
+        "<h2>{}</h2>\n{}{}{}",
+        markdown::escape_html(&meta.name),
+        header?,
+        body,
+        footer?
+    );
+
+    assets.extend(protected_assets);
+    Ok((contents, assets))
+}
+
+pub fn html_page(
+    cfg: &Config,
+    meta: &ContentMeta,
+    body: &str,
+    excerpt: &str,
+    series: &Value,
+    opengraph: &str,
+    templates: &Templates,
+) -> String {
+    let mut context = meta_context(meta);
+    context.insert(String::from("excerpt"), Value::String(excerpt.to_string()));
+    context.insert(
+        String::from("opengraph"),
+        Value::String(opengraph.to_string()),
+    );
+    context.insert(
+        String::from("language"),
+        Value::String(
+            meta.language
+                .as_deref()
+                .or(cfg.site.language.as_deref())
+                .unwrap_or("en")
+                .to_string(),
+        ),
+    );
+    context.insert(String::from("content"), Value::String(body.to_string()));
+    context.insert(String::from("series"), series.clone());
+    context.insert(
+        String::from("math"),
+        Value::Boolean(math::wants_math(cfg, meta)),
+    );
+    context.insert(String::from("katex"), Value::String(cfg.math.katex.clone()));
+    context.insert(
+        String::from("highlight_css"),
+        optional(highlight::stylesheet_url(cfg, &meta.path).as_deref()),
+    );
+    context.insert(
+        String::from("comments"),
+        Value::String(comments_block(cfg, meta, templates)),
+    );
+
+    templates.content_html.render(&context)
+}
+
+pub fn feed_description(meta: &ContentMeta, excerpt: &str, templates: &Templates) -> String {
+    let mut context = meta_context(meta);
+    context.insert(String::from("excerpt"), Value::String(excerpt.to_string()));
+    templates.feed_description.render(&context)
+}
+
+pub fn title_case(input: &str) -> String {
+    input
+        .split_whitespace() // Split the string into words
+        .map(|word| {
+            let mut chars = word.chars(); // Get the characters of the word
+            match chars.next() {
+                // Take the first character
+                Some(first) => first.to_uppercase().chain(chars).collect(), // Capitalize it and append the rest
+                None => String::new(),                                      // Handle empty words
+            }
+        })
+        .collect::<Vec<String>>() // Collect the capitalized words into a vector
+        .join(" ") // Join them back into a single string with spaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(date: &str, slug: &str, file_name: &str) -> ContentMeta {
+        ContentMeta {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            filesystem_friendly_name: slug.to_string(),
+            file_name: file_name.to_string(),
+            file_ext: String::from("adoc"),
+            path: format!("poetry/{}", date.replace('-', "/")),
+            ..ContentMeta::default()
+        }
+    }
+
+    fn order(posts: &[ContentMeta]) -> Vec<&str> {
+        posts.iter().map(|post| post.file_name.as_str()).collect()
+    }
+
+    #[test]
+    fn same_date_posts_order_by_slug_then_file_name() {
+        let mut posts = vec![
+            post("2024-05-01", "walk", "2024-05-01_walk.md"),
+            post("2024-05-02", "later", "2024-05-02_later.adoc"),
+            post("2024-05-01", "walk", "2024-05-01_walk.adoc"),
+            post("2024-05-01", "dawn", "2024-05-01_dawn.adoc"),
+        ];
+        posts.sort_by(newest_first);
+        assert_eq!(
+            order(&posts),
+            [
+                "2024-05-02_later.adoc",
+                "2024-05-01_dawn.adoc",
+                "2024-05-01_walk.adoc",
+                "2024-05-01_walk.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn time_stamped_date_ties_with_date_only() {
+        let (front_matter, _) =
+            frontmatter::split("---\ndate: 2024-05-01T23:59:00\n---\n").unwrap();
+        let stamped = front_matter.unwrap().date("date").unwrap().unwrap();
+        let mut late = post("2024-05-01", "zebra", "late.adoc");
+        late.date = stamped;
+        let early = post("2024-05-01", "aardvark", "early.adoc");
+
+        assert_eq!(late.date, early.date);
+        assert_eq!(newest_first(&early, &late), Ordering::Less);
+        assert_eq!(newest_first(&late, &early), Ordering::Greater);
+    }
+
+    #[test]
+    fn sorting_again_gives_the_same_order() {
+        let posts = vec![
+            post("2024-05-01", "b", "2024-05-01_b.adoc"),
+            post("2024-05-01", "a", "2024-05-01_a.org"),
+            post("2024-04-30", "c", "2024-04-30_c.adoc"),
+            post("2024-05-01", "a", "2024-05-01_a.adoc"),
+        ];
+        let mut first = posts.clone();
+        first.sort_by(newest_first);
+        let mut reversed: Vec<ContentMeta> = posts.into_iter().rev().collect();
+        reversed.sort_by(newest_first);
+        let mut again = first.clone();
+        again.sort_by(newest_first);
+
+        assert_eq!(order(&first), order(&reversed));
+        assert_eq!(order(&first), order(&again));
+    }
+
+    #[test]
+    fn excerpt_skips_only_the_formats_own_markup() {
+        assert_eq!(
+            excerpt(
+                ContentFormat::Markdown,
+                "# Title\n\n[Rust](https://rust-lang.org) is nice.\n"
+            ),
+            "Rust is nice."
+        );
+        assert_eq!(
+            excerpt(ContentFormat::Org, "#+TITLE: Post\n* Heading\nSome text.\n"),
+            "Some text."
+        );
+        assert_eq!(
+            excerpt(
+                ContentFormat::AsciiDoc,
+                "= Title\n:toc:\n\n[quote]\n____\nA quote.\n____\n"
+            ),
+            "A quote."
+        );
+    }
+}
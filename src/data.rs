@@ -0,0 +1,76 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::json;
+use crate::toml;
+use crate::value::{Table, Value};
+use crate::yaml;
+
+fn data_err(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+fn file_value(path: &Path) -> io::Result<Option<Value>> {
+    let ext = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    let parse: fn(&str) -> io::Result<Value> = match ext.as_str() {
+        "toml" => |source: &str| toml::parse(source).map(Value::Table),
+        "json" => json::parse,
+        "yaml" | "yml" => |source: &str| yaml::parse(source).map(Value::Table),
+        _ => return Ok(None),
+    };
+    let source = fs::read_to_string(path).map_err(|err| data_err(path, err))?;
+    parse(&source).map(Some).map_err(|err| data_err(path, err))
+}
+
+fn load_dir(dir: &Path) -> io::Result<Table> {
+    let mut table = Table::new();
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+    for path in paths {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let (key, value) = if path.is_dir() {
+            (name, Value::Table(load_dir(&path)?))
+        } else {
+            let stem = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            match file_value(&path)? {
+                Some(value) => (stem, value),
+                None => continue,
+            }
+        };
+        if table.contains_key(&key) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: more than one data file for {}", dir.display(), key),
+            ));
+        }
+        table.insert(key, value);
+    }
+    Ok(table)
+}
+
+// The .toml, .json and .yaml files of the data directory, by file stem;
+// those in subdirectories are nested under the directory's name, so data/menus/main.toml is data.menus.main.
+pub fn load(dir: Option<&Path>) -> io::Result<Table> {
+    match dir {
+        Some(dir) if dir.is_dir() => load_dir(dir),
+        _ => Ok(Table::new()),
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, NaiveDate};
+
+use crate::config::Config;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%B %d, %Y";
+
+struct Names {
+    locale: &'static str,
+    months: [&'static str; 12],
+    weekdays: [&'static str; 7],
+}
+
+// Weekdays start on Monday, as chrono's num_days_from_monday counts them.
+const NAMES: [Names; 10] = [
+    Names {
+        locale: "en",
+        months: [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        weekdays: [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+    },
+    Names {
+        locale: "de",
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        weekdays: [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+    },
+    Names {
+        locale: "fr",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        weekdays: [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+    },
+    Names {
+        locale: "es",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        weekdays: [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+    },
+    Names {
+        locale: "it",
+        months: [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        weekdays: [
+            "lunedì",
+            "martedì",
+            "mercoledì",
+            "giovedì",
+            "venerdì",
+            "sabato",
+            "domenica",
+        ],
+    },
+    Names {
+        locale: "pt",
+        months: [
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        weekdays: [
+            "segunda-feira",
+            "terça-feira",
+            "quarta-feira",
+            "quinta-feira",
+            "sexta-feira",
+            "sábado",
+            "domingo",
+        ],
+    },
+    Names {
+        locale: "nl",
+        months: [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        weekdays: [
+            "maandag",
+            "dinsdag",
+            "woensdag",
+            "donderdag",
+            "vrijdag",
+            "zaterdag",
+            "zondag",
+        ],
+    },
+    Names {
+        locale: "sv",
+        months: [
+            "januari",
+            "februari",
+            "mars",
+            "april",
+            "maj",
+            "juni",
+            "juli",
+            "augusti",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        weekdays: [
+            "måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag",
+        ],
+    },
+    Names {
+        locale: "da",
+        months: [
+            "januar",
+            "februar",
+            "marts",
+            "april",
+            "maj",
+            "juni",
+            "juli",
+            "august",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        weekdays: [
+            "mandag", "tirsdag", "onsdag", "torsdag", "fredag", "lørdag", "søndag",
+        ],
+    },
+    Names {
+        locale: "nb",
+        months: [
+            "januar",
+            "februar",
+            "mars",
+            "april",
+            "mai",
+            "juni",
+            "juli",
+            "august",
+            "september",
+            "oktober",
+            "november",
+            "desember",
+        ],
+        weekdays: [
+            "mandag", "tirsdag", "onsdag", "torsdag", "fredag", "lørdag", "søndag",
+        ],
+    },
+];
+
+// "de-AT" and "de_AT.UTF-8" both fall back to "de"; unknown locales read as English.
+fn names(locale: &str) -> &'static Names {
+    let language = locale
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let language = match language.as_str() {
+        "no" | "nn" => "nb",
+        language => language,
+    };
+    NAMES
+        .iter()
+        .find(|names| names.locale == language)
+        .unwrap_or(&NAMES[0])
+}
+
+fn abbreviate(name: &str) -> String {
+    name.chars().take(3).collect()
+}
+
+pub fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+pub fn month_name(locale: &str, month: u32) -> &'static str {
+    match month {
+        1..=12 => names(locale).months[month as usize - 1],
+        _ => "",
+    }
+}
+
+// chrono only knows English names, so the name specifiers are filled in before it formats the rest.
+pub fn format_date_with(format: &str, locale: &str, date: NaiveDate) -> String {
+    let names = names(locale);
+    let month = names.months[date.month0() as usize];
+    let weekday = names.weekdays[date.weekday().num_days_from_monday() as usize];
+
+    let mut localized = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('B') => localized.push_str(month),
+            Some('b' | 'h') => localized.push_str(&abbreviate(month)),
+            Some('A') => localized.push_str(weekday),
+            Some('a') => localized.push_str(&abbreviate(weekday)),
+            Some(spec) => {
+                localized.push('%');
+                localized.push(spec);
+            }
+            None => localized.push('%'),
+        }
+    }
+
+    date.format(&localized).to_string()
+}
+
+pub fn format_date(cfg: &Config, date: NaiveDate) -> String {
+    format_date_with(&cfg.site.date_format, &cfg.site.locale, date)
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::config::{Config, DeployTarget};
+use crate::json;
+use crate::manifest::MANIFEST_FILE;
+use crate::parallel;
+use crate::tools::spawn_err;
+use crate::value::{Table, Value};
+
+const DEPLOY_STATE_FILE: &str = ".webweaver-deploy.json";
+const DEPLOY_STATE_VERSION: i64 = 1;
+
+pub struct DeployPlan {
+    pub upload: Vec<String>,
+    pub delete: Vec<String>,
+}
+
+fn file_hashes(files: &[Value]) -> BTreeMap<String, String> {
+    files
+        .iter()
+        .filter_map(|file| {
+            Some((
+                file.get("output")?.as_str()?.to_string(),
+                file.get("hash")?.as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn read_manifest(cfg: &Config) -> io::Result<BTreeMap<String, String>> {
+    let path = cfg.output_root_path.join(MANIFEST_FILE);
+    let contents = fs::read_to_string(&path).map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} does not exist; run webweaver build first.",
+                    path.display()
+                ),
+            )
+        } else {
+            err
+        }
+    })?;
+    let manifest = json::parse(&contents)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    match manifest.get("files").and_then(Value::as_array) {
+        Some(files) => Ok(file_hashes(files)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has no files list.", path.display()),
+        )),
+    }
+}
+
+// What the destination got last time; a different destination starts from nothing.
+fn read_state(cfg: &Config, destination: &str) -> BTreeMap<String, String> {
+    let contents = match fs::read_to_string(cfg.output_root_path.join(DEPLOY_STATE_FILE)) {
+        Ok(contents) => contents,
+        Err(_) => return BTreeMap::new(),
+    };
+    let state = match json::parse(&contents) {
+        Ok(state) => state,
+        Err(_) => return BTreeMap::new(),
+    };
+    if state.get("version").and_then(Value::as_integer) != Some(DEPLOY_STATE_VERSION)
+        || state.get("destination").and_then(Value::as_str) != Some(destination)
+    {
+        return BTreeMap::new();
+    }
+    match state.get("files") {
+        Some(Value::Table(files)) => files
+            .iter()
+            .filter_map(|(output, hash)| Some((output.clone(), hash.as_str()?.to_string())))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn save_state(cfg: &Config, destination: &str, files: &BTreeMap<String, String>) -> io::Result<()> {
+    let mut state = Table::new();
+    state.insert(
+        String::from("version"),
+        Value::Integer(DEPLOY_STATE_VERSION),
+    );
+    state.insert(
+        String::from("destination"),
+        Value::String(destination.to_string()),
+    );
+    state.insert(
+        String::from("files"),
+        Value::Table(
+            files
+                .iter()
+                .map(|(output, hash)| (output.clone(), Value::String(hash.clone())))
+                .collect(),
+        ),
+    );
+    fs::write(
+        cfg.output_root_path.join(DEPLOY_STATE_FILE),
+        json::to_string(&Value::Table(state)),
+    )
+}
+
+pub fn deploy_plan(
+    current: &BTreeMap<String, String>,
+    previous: &BTreeMap<String, String>,
+    delete: bool,
+) -> DeployPlan {
+    DeployPlan {
+        upload: current
+            .iter()
+            .filter(|(output, hash)| previous.get(*output) != Some(*hash))
+            .map(|(output, _)| output.clone())
+            .collect(),
+        delete: if delete {
+            previous
+                .keys()
+                .filter(|output| !current.contains_key(*output))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+// Deleted files are listed too: --delete-missing-args removes them on the other side.
+fn rsync(cfg: &Config, destination: &str, plan: &DeployPlan) -> io::Result<()> {
+    let mut source = cfg.output_root_path.to_string_lossy().into_owned();
+    if !source.ends_with('/') {
+        source.push('/');
+    }
+
+    let mut command = Command::new(&cfg.deploy.rsync);
+    command
+        .arg("--archive")
+        .arg("--compress")
+        .arg("--files-from=-")
+        .arg("--from0");
+    if !plan.delete.is_empty() {
+        command.arg("--delete-missing-args");
+    }
+    let mut child = command
+        .arg(&source)
+        .arg(destination)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| spawn_err(&cfg.deploy.rsync, "it", "[deploy] rsync to its path", err))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for output in plan.upload.iter().chain(&plan.delete) {
+            stdin.write_all(output.as_bytes())?;
+            stdin.write_all(b"\0")?;
+        }
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed with {}.",
+            cfg.deploy.rsync, status
+        )));
+    }
+    Ok(())
+}
+
+fn aws(cfg: &Config, args: &[&str]) -> io::Result<()> {
+    let mut command = Command::new(&cfg.deploy.aws);
+    command.arg("s3").args(args).arg("--only-show-errors");
+    if let Some(endpoint) = &cfg.deploy.endpoint {
+        command.arg("--endpoint-url").arg(endpoint);
+    }
+    let status = command
+        .status()
+        .map_err(|err| spawn_err(&cfg.deploy.aws, "it", "[deploy] aws to its path", err))?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} s3 {} failed with {}.",
+            cfg.deploy.aws,
+            args.join(" "),
+            status
+        )));
+    }
+    Ok(())
+}
+
+fn s3(cfg: &Config, destination: &str, plan: &DeployPlan) -> io::Result<()> {
+    let bucket = destination.trim_end_matches('/');
+    let uploads = parallel::map(cfg.build.jobs, &plan.upload, |output| {
+        let local = cfg.output_root_path.join(output);
+        aws(
+            cfg,
+            &[
+                "cp",
+                &local.to_string_lossy(),
+                &format!("{}/{}", bucket, output),
+            ],
+        )
+    });
+    let deletes = parallel::map(cfg.build.jobs, &plan.delete, |output| {
+        aws(cfg, &["rm", &format!("{}/{}", bucket, output)])
+    });
+    uploads.into_iter().chain(deletes).collect()
+}
+
+pub fn deploy(cfg: &Config) -> io::Result<DeployPlan> {
+    let (target, destination) = match (cfg.deploy.target, &cfg.deploy.destination) {
+        (Some(target), Some(destination)) => (target, destination),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nothing to deploy to; set [deploy] target and destination.",
+            ))
+        }
+    };
+
+    let current = read_manifest(cfg)?;
+    let plan = deploy_plan(&current, &read_state(cfg, destination), cfg.deploy.delete);
+
+    if cfg.deploy.dry_run {
+        for output in &plan.upload {
+            println!("upload {}", output);
+        }
+        for output in &plan.delete {
+            println!("delete {}", output);
+        }
+        return Ok(plan);
+    }
+
+    if !plan.upload.is_empty() || !plan.delete.is_empty() {
+        match target {
+            DeployTarget::Rsync => rsync(cfg, destination, &plan)?,
+            DeployTarget::S3 => s3(cfg, destination, &plan)?,
+        }
+    }
+    save_state(cfg, destination, &current)?;
+
+    Ok(plan)
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::cache::content_hash;
+use crate::config::Config;
+use crate::content::{base_path, ContentFormat, ContentMeta};
+use crate::tools::spawn_err;
+
+// Under the category's output directory.
+const DIAGRAMS_DIR: &str = "diagrams";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Diagram {
+    Mermaid,
+    PlantUml,
+    Graphviz,
+}
+
+impl Diagram {
+    fn from_name(name: &str) -> Option<Diagram> {
+        match name.to_ascii_lowercase().as_str() {
+            "mermaid" => Some(Diagram::Mermaid),
+            "plantuml" | "puml" => Some(Diagram::PlantUml),
+            "graphviz" | "dot" => Some(Diagram::Graphviz),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Diagram::Mermaid => "mermaid",
+            Diagram::PlantUml => "plantuml",
+            Diagram::Graphviz => "graphviz",
+        }
+    }
+
+    fn command(self, cfg: &Config) -> &[String] {
+        match self {
+            Diagram::Mermaid => &cfg.diagrams.mermaid,
+            Diagram::PlantUml => &cfg.diagrams.plantuml,
+            Diagram::Graphviz => &cfg.diagrams.graphviz,
+        }
+    }
+}
+
+struct Block {
+    // None for other fenced blocks, which are copied as they are.
+    diagram: Option<Diagram>,
+    // The lines the block takes up, fences included.
+    lines: usize,
+    source: String,
+}
+
+fn is_delimiter(line: &str) -> bool {
+    line.len() >= 4
+        && ['-', '.', '+', '/']
+            .iter()
+            .any(|delimiter| line.chars().all(|c| c == *delimiter))
+}
+
+// A fenced block: ```mermaid in Markdown, #+begin_src mermaid in Org,
+// or [mermaid] over a ---- or .... delimited block in AsciiDoc for a diagram.
+fn block_at(format: ContentFormat, lines: &[&str]) -> Option<Block> {
+    let first = lines.first()?.trim();
+    let (diagram, close, body_start) = match format {
+        ContentFormat::Markdown => {
+            let fence_char = first.chars().next().filter(|c| *c == '`' || *c == '~')?;
+            let fence_len = first.chars().take_while(|c| *c == fence_char).count();
+            if fence_len < 3 {
+                return None;
+            }
+            let info = first[fence_len..].split_whitespace().next();
+            (
+                info.and_then(Diagram::from_name),
+                fence_char.to_string().repeat(fence_len),
+                1,
+            )
+        }
+        ContentFormat::Org => {
+            let lower = first.to_ascii_lowercase();
+            let kind = lower.strip_prefix("#+begin_")?;
+            let mut words = kind.split_whitespace();
+            let name = words.next()?;
+            let diagram = match name {
+                "src" => words.next().and_then(Diagram::from_name),
+                _ => None,
+            };
+            (diagram, format!("#+end_{}", name), 1)
+        }
+        ContentFormat::AsciiDoc if is_delimiter(first) => (None, first.to_string(), 1),
+        ContentFormat::AsciiDoc => {
+            let style = first.strip_prefix('[')?.strip_suffix(']')?;
+            let delimiter = lines.get(1)?.trim();
+            if !is_delimiter(delimiter) {
+                return None;
+            }
+            let diagram = style
+                .split(',')
+                .next()
+                .and_then(|style| Diagram::from_name(style.trim()));
+            (diagram, delimiter.to_string(), 2)
+        }
+    };
+
+    let closes = |line: &str| match format {
+        ContentFormat::Markdown => {
+            let line = line.trim();
+            line.starts_with(&close) && line.chars().all(|c| close.starts_with(c))
+        }
+        ContentFormat::Org => line.trim().eq_ignore_ascii_case(&close),
+        ContentFormat::AsciiDoc => line.trim() == close,
+    };
+    let end = body_start + lines[body_start..].iter().position(|line| closes(line))?;
+    Some(Block {
+        diagram,
+        lines: end + 1,
+        source: lines[body_start..end].join("\n") + "\n",
+    })
+}
+
+// Runs the [diagrams] command for the diagram. {input} and {output} in its arguments stand for
+// a file holding the source and the SVG to write; without them the source goes to stdin
+// and the SVG is read from stdout.
+fn render_svg(cfg: &Config, diagram: Diagram, source: &str, target: &Path) -> io::Result<()> {
+    let (program, args) = match diagram.command(cfg).split_first() {
+        Some(command) => command,
+        None => {
+            return Err(io::Error::other(format!(
+                "[diagrams] {} is empty.",
+                diagram.name()
+            )))
+        }
+    };
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let input = target.with_file_name(format!(".{}.input", name));
+    let partial = target.with_file_name(format!(".{}.webweaver-partial", name));
+    let uses_input = args.iter().any(|arg| arg.contains("{input}"));
+    let uses_output = args.iter().any(|arg| arg.contains("{output}"));
+    if uses_input {
+        fs::write(&input, source)?;
+    }
+
+    let mut child = Command::new(program)
+        .args(args.iter().map(|arg| {
+            arg.replace("{input}", &input.to_string_lossy())
+                .replace("{output}", &partial.to_string_lossy())
+        }))
+        .stdin(if uses_input {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            spawn_err(
+                program,
+                "it",
+                &format!("[diagrams] {}", diagram.name()),
+                err,
+            )
+        })?;
+    // Written from another thread, as in html::render_fragment.
+    let stdin = child.stdin.take();
+    let output = thread::scope(|scope| {
+        scope.spawn(move || match stdin {
+            Some(mut stdin) => stdin.write_all(source.as_bytes()),
+            None => Ok(()),
+        });
+        child.wait_with_output()
+    });
+    if uses_input {
+        let _ = fs::remove_file(&input);
+    }
+    let output = output?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(io::Error::other(format!(
+            "{} failed with {} on a {} diagram: {}",
+            program,
+            output.status,
+            diagram.name(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    if !uses_output {
+        fs::write(&partial, &output.stdout)?;
+    }
+    fs::rename(&partial, target)
+}
+
+// The image that stands in for a rendered block, in the post's own format.
+fn image(format: ContentFormat, diagram: Diagram, url: &str) -> String {
+    let alt = format!("{} diagram", diagram.name());
+    match format {
+        ContentFormat::AsciiDoc => format!("image::{}[{}]", url, alt),
+        ContentFormat::Markdown => format!("![{}]({})", alt, url),
+        ContentFormat::Org => format!("[[file:{}]]", url),
+    }
+}
+
+// Renders each diagram block in body to an SVG under <category>/diagrams, named by its hash
+// so an unchanged diagram is not rendered again, and puts an image of it in the block's place.
+// Returns the body and the SVGs, which are outputs, not inputs to copy.
+pub fn render_diagrams(
+    cfg: &Config,
+    meta: &ContentMeta,
+    body: String,
+) -> io::Result<(String, BTreeSet<PathBuf>)> {
+    let mut svgs = BTreeSet::new();
+    if !cfg.diagrams.enabled {
+        return Ok((body, svgs));
+    }
+    let lines: Vec<&str> = body.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let block = match block_at(meta.format, &lines[i..]) {
+            Some(block) => block,
+            None => {
+                out.push(lines[i].to_string());
+                i += 1;
+                continue;
+            }
+        };
+        let diagram = match block.diagram {
+            Some(diagram) => diagram,
+            None => {
+                out.extend(
+                    lines[i..i + block.lines]
+                        .iter()
+                        .map(|line| line.to_string()),
+                );
+                i += block.lines;
+                continue;
+            }
+        };
+
+        let hash = content_hash(format!("{}\n{}", diagram.name(), block.source).as_bytes());
+        let url = cfg.category_path(&format!("{}/{}-{}.svg", DIAGRAMS_DIR, diagram.name(), hash));
+        let target = cfg.output_root_path.join(&url);
+        if !target.is_file() {
+            if let Some(dir) = target.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            render_svg(cfg, diagram, &block.source, &target)?;
+        }
+        let href = match base_path(&meta.path) {
+            base if base.is_empty() => url,
+            base => format!("{}/{}", base, url),
+        };
+        out.push(image(meta.format, diagram, &href));
+        svgs.insert(target);
+        i += block.lines;
+    }
+    let mut rendered = out.join("\n");
+    if body.ends_with('\n') {
+        rendered.push('\n');
+    }
+    Ok((rendered, svgs))
+}
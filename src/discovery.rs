@@ -0,0 +1,233 @@
+use std::fs::{self, DirEntry};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::attachment;
+use crate::config::{content_root, DiscoveryConfig, Symlinks};
+use crate::content::{is_partial, ContentFormat};
+use crate::git::GitDates;
+use crate::ignore::IgnoreFiles;
+
+pub const DRAFTS_DIR: &str = ".drafts";
+pub const PAGES_DIR: &str = "pages";
+
+pub struct ContentFile {
+    pub path: PathBuf,
+    pub categories_path: PathBuf,
+    pub draft: bool,
+    pub author: Option<String>,
+    pub git_dates: Option<GitDates>,
+    // The directory below pages/ for an undated page, which is its URL without the name.
+    pub page: Option<PathBuf>,
+    // A PDF or image that is a post of its own, with [discovery] attachments.
+    pub attachment: bool,
+}
+
+fn content_files_dir_entries(content_path: &Path) -> io::Result<Vec<DirEntry>> {
+    let content_files: Vec<Result<DirEntry, io::Error>> = fs::read_dir(content_path)?.collect();
+
+    let errors: Vec<io::Error> = content_files
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .map(|err| io::Error::new(err.kind(), err.to_string()))
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(io::Error::other(format!("{:#?}", errors)));
+    }
+
+    let mut dir_entries: Vec<DirEntry> = content_files
+        .into_iter()
+        .filter_map(|result| result.ok())
+        .collect();
+
+    dir_entries.sort_by_key(|dir_entry| dir_entry.file_name());
+
+    Ok(dir_entries)
+}
+
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+            }
+            Some(&c) if c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn is_ignored(name: &str, ignore: &[String]) -> bool {
+    ignore.iter().any(|pattern| glob_match(pattern, name))
+}
+
+struct Walk<'a> {
+    discovery: &'a DiscoveryConfig,
+    // Canonical directories on the way down, to stop at a symlink back into one of them.
+    ancestors: Vec<PathBuf>,
+    ignores: IgnoreFiles,
+    files: Vec<ContentFile>,
+}
+
+impl Walk<'_> {
+    // None skips the entry: a symlink under symlinks = "skip", a dangling one, or a loop.
+    fn follow(&self, path: &Path, symlink: bool) -> Option<bool> {
+        if !symlink {
+            return Some(path.is_dir());
+        }
+        if self.discovery.symlinks == Symlinks::Skip {
+            eprintln!("warning: skipped symlink {}.", path.display());
+            return None;
+        }
+        let target = match fs::canonicalize(path) {
+            Ok(target) => target,
+            Err(err) => {
+                eprintln!("warning: skipped symlink {}: {}.", path.display(), err);
+                return None;
+            }
+        };
+        if !target.is_dir() {
+            return Some(false);
+        }
+        if self.ancestors.contains(&target) {
+            eprintln!(
+                "warning: skipped symlink {}: it loops back to {}.",
+                path.display(),
+                target.display()
+            );
+            return None;
+        }
+        Some(true)
+    }
+
+    fn dir(
+        &mut self,
+        dir: &Path,
+        categories_path: &Path,
+        depth: usize,
+        draft: bool,
+        author: Option<&str>,
+        page: Option<&Path>,
+    ) -> io::Result<()> {
+        self.ancestors.push(fs::canonicalize(dir)?);
+        self.ignores.enter(dir)?;
+        for dir_entry in content_files_dir_entries(dir)? {
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+            if is_ignored(&name, &self.discovery.ignore) {
+                continue;
+            }
+
+            let path = dir_entry.path();
+            let is_dir = match self.follow(&path, dir_entry.file_type()?.is_symlink()) {
+                Some(is_dir) => is_dir,
+                None => continue,
+            };
+            if self.ignores.is_ignored(&path, is_dir) {
+                continue;
+            }
+            if is_dir && name == DRAFTS_DIR {
+                self.dir(&path, categories_path, depth, true, author, page)?;
+                continue;
+            }
+            if let Some(dir_author) = name.strip_prefix('.').filter(|_| is_dir) {
+                self.dir(&path, categories_path, depth, draft, Some(dir_author), page)?;
+                continue;
+            }
+            // Directories under pages/ name the page's URL rather than a category.
+            if is_dir && (page.is_some() || name == PAGES_DIR) {
+                let page = match page {
+                    Some(page) => page.join(&name),
+                    None => PathBuf::new(),
+                };
+                self.dir(&path, categories_path, depth, draft, author, Some(&page))?;
+                continue;
+            }
+            if is_dir {
+                let within_depth = self.discovery.max_depth.is_none_or(|max| depth < max);
+                if within_depth {
+                    self.dir(
+                        &path,
+                        &categories_path.join(&name),
+                        depth + 1,
+                        draft,
+                        author,
+                        None,
+                    )?;
+                }
+                continue;
+            }
+
+            // Anything else is an asset, copied when a post references it.
+            let attachment = self.discovery.attachments
+                && page.is_none()
+                && attachment::is_attachment_post(&path);
+            if !attachment && (!ContentFormat::is_content_file(&path) || is_partial(&path)) {
+                continue;
+            }
+
+            self.files.push(ContentFile {
+                path,
+                categories_path: categories_path.to_path_buf(),
+                draft,
+                author: author.map(String::from),
+                git_dates: None,
+                page: page.map(Path::to_path_buf),
+                attachment,
+            });
+        }
+        self.ignores.leave();
+        self.ancestors.pop();
+
+        Ok(())
+    }
+}
+
+pub fn content_files(
+    input_content_path: &Path,
+    content_output_root_path: &Path,
+    discovery: &DiscoveryConfig,
+) -> io::Result<Vec<ContentFile>> {
+    let mut walk = Walk {
+        discovery,
+        ancestors: Vec::new(),
+        ignores: IgnoreFiles::default(),
+        files: Vec::with_capacity(64),
+    };
+    walk.ignores.enter_parents(
+        content_root(input_content_path).as_deref(),
+        input_content_path,
+    )?;
+    walk.dir(
+        input_content_path,
+        content_output_root_path,
+        0,
+        false,
+        None,
+        None,
+    )?;
+    Ok(walk.files)
+}
@@ -0,0 +1,292 @@
+use crate::content::ContentFormat;
+
+// GitHub's names for the emoji posts use most.
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("airplane", "✈️"),
+    ("alarm_clock", "⏰"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("art", "🎨"),
+    ("baby", "👶"),
+    ("balloon", "🎈"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("birthday", "🎂"),
+    ("black_heart", "🖤"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bow", "🙇"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("car", "🚗"),
+    ("cat", "🐱"),
+    ("chart_with_upwards_trend", "📈"),
+    ("christmas_tree", "🎄"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cool", "🆒"),
+    ("cry", "😢"),
+    ("crystal_ball", "🔮"),
+    ("dart", "🎯"),
+    ("dog", "🐶"),
+    ("door", "🚪"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("fearful", "😨"),
+    ("fire", "🔥"),
+    ("fish", "🐟"),
+    ("flushed", "😳"),
+    ("gem", "💎"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("guitar", "🎸"),
+    ("hammer", "🔨"),
+    ("hand", "✋"),
+    ("headphones", "🎧"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔️"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("hushed", "😯"),
+    ("innocent", "😇"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("kiss", "💋"),
+    ("laughing", "😆"),
+    ("leaves", "🍃"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("mailbox", "📫"),
+    ("memo", "📝"),
+    ("microphone", "🎤"),
+    ("moon", "🌔"),
+    ("muscle", "💪"),
+    ("musical_note", "🎵"),
+    ("neutral_face", "😐"),
+    ("new", "🆕"),
+    ("newspaper", "📰"),
+    ("no_entry", "⛔"),
+    ("notebook", "📓"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("open_mouth", "😮"),
+    ("package", "📦"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("pensive", "😔"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("relaxed", "☺️"),
+    ("relieved", "😌"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("rose", "🌹"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("shrug", "🤷"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stuck_out_tongue", "😛"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("umbrella", "☔"),
+    ("unamused", "😒"),
+    ("v", "✌️"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wine_glass", "🍷"),
+    ("wink", "😉"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("zap", "⚡"),
+];
+
+fn emoji(name: &str) -> Option<&'static str> {
+    EMOJI
+        .binary_search_by(|(known, _)| (*known).cmp(name))
+        .ok()
+        .map(|at| EMOJI[at].1)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)
+}
+
+// Replaces :name: shortcodes standing on their own, so times like 12:30:45 and
+// words:with:colons stay; code spans in backticks are left alone.
+pub fn expand(text: &str) -> String {
+    if !text.contains(':') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut previous: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            let run = rest.chars().take_while(|c| *c == '`').count();
+            let end = rest[run..]
+                .find(&rest[..run])
+                .map_or(run, |end| run + end + run);
+            out.push_str(&rest[..end]);
+            previous = Some('`');
+            rest = &rest[end..];
+            continue;
+        }
+        let standalone = previous.is_none_or(|c| !c.is_alphanumeric());
+        if c == ':' && standalone {
+            let name_len = rest[1..]
+                .find(|c: char| !is_name_char(c))
+                .unwrap_or(rest.len() - 1);
+            let name = &rest[1..1 + name_len];
+            let after = rest[1 + name_len..].strip_prefix(':');
+            let found = after
+                .filter(|after| !after.starts_with(char::is_alphanumeric))
+                .and_then(|_| emoji(name));
+            if let Some(emoji) = found {
+                out.push_str(emoji);
+                rest = &rest[name_len + 2..];
+                previous = Some(':');
+                continue;
+            }
+        }
+        out.push(c);
+        previous = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+// Expands a body a line at a time, passing over code blocks, AsciiDoc attribute entries
+// and the :tags: that end an Org headline.
+#[derive(Default)]
+pub struct BodyScan {
+    // The line that closes the code block the scan is in.
+    fence: Option<String>,
+}
+
+impl BodyScan {
+    fn opens(format: ContentFormat, line: &str) -> Option<String> {
+        match format {
+            ContentFormat::Markdown => {
+                let fence_char = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+                let run = line.chars().take_while(|c| *c == fence_char).count();
+                (run >= 3).then(|| fence_char.to_string().repeat(run))
+            }
+            ContentFormat::Org => {
+                let lower = line.to_ascii_lowercase();
+                let kind = lower.strip_prefix("#+begin_")?.split_whitespace().next()?;
+                ["src", "example", "export"]
+                    .contains(&kind)
+                    .then(|| format!("#+end_{}", kind))
+            }
+            ContentFormat::AsciiDoc => {
+                let delimited = line.len() >= 4
+                    && ['-', '.', '+', '/']
+                        .iter()
+                        .any(|delimiter| line.chars().all(|c| c == *delimiter));
+                delimited.then(|| line.to_string())
+            }
+        }
+    }
+
+    fn closes(format: ContentFormat, line: &str, close: &str) -> bool {
+        match format {
+            ContentFormat::Markdown => {
+                line.starts_with(close) && line.chars().all(|c| close.starts_with(c))
+            }
+            ContentFormat::Org => line.eq_ignore_ascii_case(close),
+            ContentFormat::AsciiDoc => line == close,
+        }
+    }
+
+    // Whether the line opens, closes or lies in a code block, following the blocks through the body.
+    pub fn is_code(&mut self, format: ContentFormat, line: &str) -> bool {
+        let trimmed = line.trim();
+        if let Some(close) = &self.fence {
+            if Self::closes(format, trimmed, close) {
+                self.fence = None;
+            }
+            return true;
+        }
+        self.fence = Self::opens(format, trimmed);
+        self.fence.is_some()
+    }
+
+    pub fn line(&mut self, format: ContentFormat, line: &str) -> String {
+        let attribute = format == ContentFormat::AsciiDoc && line.starts_with(':');
+        if self.is_code(format, line) || attribute {
+            return line.to_string();
+        }
+        if format == ContentFormat::Org && line.starts_with('*') {
+            let headline = line.trim_end();
+            if let Some((title, tags)) = headline.rsplit_once(char::is_whitespace) {
+                if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') {
+                    return format!("{}{}", expand(title), &line[title.len()..]);
+                }
+            }
+        }
+        expand(line)
+    }
+}
+
+pub fn expand_body(format: ContentFormat, body: &str) -> String {
+    let mut scan = BodyScan::default();
+    body.split_inclusive('\n')
+        .map(|line| scan.line(format, line))
+        .collect()
+}
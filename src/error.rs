@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::links::BrokenLink;
+
+#[derive(Debug)]
+pub enum WebWeaverError {
+    Io(io::Error),
+    Config(String),
+    Metadata { path: PathBuf, message: String },
+    Template(String),
+    Feed { path: PathBuf, message: String },
+    Skipped(Vec<WebWeaverError>),
+    BrokenLinks(Vec<BrokenLink>),
+    DeadLinks(usize),
+    Problems(usize),
+    Webmentions(usize),
+}
+
+impl fmt::Display for WebWeaverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebWeaverError::Io(err) => write!(f, "{}", err),
+            WebWeaverError::Config(message) => write!(f, "{}", message),
+            WebWeaverError::Metadata { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            WebWeaverError::Template(message) => write!(f, "{}", message),
+            WebWeaverError::Feed { path, message } => {
+                write!(f, "error writing feed {}: {}", path.display(), message)
+            }
+            WebWeaverError::Skipped(errors) => {
+                write!(f, "skipped {} content files:", errors.len())?;
+                for err in errors {
+                    write!(f, "\n  {}", err)?;
+                }
+                Ok(())
+            }
+            WebWeaverError::DeadLinks(count) => write!(f, "{} dead external links.", count),
+            WebWeaverError::Problems(count) => write!(f, "{} problems in the content tree.", count),
+            WebWeaverError::Webmentions(count) => write!(f, "{} webmentions failed.", count),
+            WebWeaverError::BrokenLinks(links) => {
+                write!(f, "{} broken internal links:", links.len())?;
+                for link in links {
+                    write!(f, "\n  {}: {}", link.page.display(), link.target)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for WebWeaverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WebWeaverError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WebWeaverError {
+    fn from(err: io::Error) -> WebWeaverError {
+        WebWeaverError::Io(err)
+    }
+}
@@ -0,0 +1,738 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use atom_syndication::{
+    Content, Entry, EntryBuilder, Feed, FeedBuilder, FixedDateTime, Generator, Link, Person, Text,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use rss::extension::dublincore::DublinCoreExtension;
+use rss::extension::itunes::{
+    ITunesCategory, ITunesChannelExtension, ITunesItemExtension, ITunesOwner,
+};
+use rss::extension::Extension;
+use rss::{Category, Channel, ChannelBuilder, Guid, Item, ItemBuilder};
+
+use crate::config::{Config, FeedContent, PodcastType};
+use crate::content::{newest_first, sort_posts, Enclosure};
+use crate::error::WebWeaverError;
+use crate::json;
+use crate::markdown;
+use crate::opengraph::html_path;
+use crate::url;
+use crate::value::{Table, Value};
+use crate::webmention;
+use crate::{ContentFormat, ContentMeta, ContentUnit};
+
+const DOCINFO_FILE: &str = "docinfo.html";
+
+fn local_midnight(date: NaiveDate) -> DateTime<Local> {
+    date.and_time(NaiveTime::default())
+        .and_local_timezone(Local)
+        .unwrap()
+}
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+
+fn atom_updated(date: NaiveDate) -> Extension {
+    Extension {
+        name: String::from("atom:updated"),
+        value: Some(local_midnight(date).to_rfc3339()),
+        ..Extension::default()
+    }
+}
+
+fn item_categories(meta: &ContentMeta) -> Vec<String> {
+    let mut categories: Vec<String> = Vec::with_capacity(meta.categories.len() + meta.tags.len());
+    for category in meta.categories.iter().chain(meta.tags.iter()) {
+        if !category.is_empty() && !categories.contains(category) {
+            categories.push(category.clone());
+        }
+    }
+    categories
+}
+
+fn feed_units<'a>(cfg: &Config, content: &[&'a ContentUnit]) -> Vec<&'a ContentUnit> {
+    let mut units = content.to_vec();
+    units.sort_by(|a, b| newest_first(&a.meta, &b.meta));
+    if let Some(limit) = cfg.feed.limit {
+        units.truncate(limit);
+    }
+    // The limit keeps the newest items whatever order they are listed in.
+    sort_posts(cfg, &mut units, |unit| &unit.meta);
+    units
+}
+
+pub fn content_url(cfg: &Config, meta: &ContentMeta) -> String {
+    url::join(&cfg.site.base_url, &meta.output_path())
+}
+
+pub fn resolve_canonical(cfg: &Config, canonical: &str) -> String {
+    if url::is_absolute(canonical) {
+        canonical.to_string()
+    } else {
+        url::join(&cfg.site.canonical_base_url, canonical)
+    }
+}
+
+// With --html an AsciiDoc post's canonical page is its rendered .html.
+pub fn canonical_url(cfg: &Config, meta: &ContentMeta) -> String {
+    match &meta.canonical {
+        Some(canonical) => resolve_canonical(cfg, canonical),
+        None if cfg.build.html => url::join(
+            &cfg.site.canonical_base_url,
+            &html_path(&meta.output_path(), meta.format),
+        ),
+        None => url::join(&cfg.site.canonical_base_url, &meta.output_path()),
+    }
+}
+
+fn item_guid(cfg: &Config, meta: &ContentMeta) -> Guid {
+    match &meta.guid {
+        Some(guid) => Guid {
+            value: guid.clone(),
+            permalink: false,
+        },
+        None => Guid {
+            value: content_url(cfg, meta),
+            permalink: true,
+        },
+    }
+}
+
+pub fn media_url(cfg: &Config, path: &str) -> String {
+    if url::is_absolute(path) {
+        path.to_string()
+    } else {
+        url::join(&cfg.site.base_url, path)
+    }
+}
+
+fn enclosure_url(cfg: &Config, enclosure: &Enclosure) -> String {
+    media_url(cfg, &enclosure.url)
+}
+
+fn itunes_bool(value: bool) -> String {
+    String::from(if value { "true" } else { "false" })
+}
+
+// "Arts/Books" is the Books subcategory of Arts.
+fn itunes_category(category: &str) -> ITunesCategory {
+    let (text, subcategory) = match category.split_once('/') {
+        Some((text, subcategory)) => (text, Some(subcategory)),
+        None => (category, None),
+    };
+    ITunesCategory {
+        text: text.trim().to_string(),
+        subcategory: subcategory.map(|subcategory| Box::new(itunes_category(subcategory))),
+    }
+}
+
+fn itunes_channel(cfg: &Config) -> ITunesChannelExtension {
+    let podcast = &cfg.podcast;
+    let owner = match (&podcast.owner_name, &podcast.owner_email) {
+        (None, None) => None,
+        (name, email) => Some(ITunesOwner {
+            name: name.clone(),
+            email: email.clone(),
+        }),
+    };
+    ITunesChannelExtension {
+        author: podcast.author.clone().or_else(|| cfg.author.clone()),
+        categories: podcast
+            .category
+            .iter()
+            .map(|c| itunes_category(c))
+            .collect(),
+        image: podcast
+            .image
+            .as_ref()
+            .or(cfg.site.image.as_ref())
+            .map(|image| media_url(cfg, image)),
+        explicit: Some(itunes_bool(podcast.explicit)),
+        owner,
+        summary: Some(cfg.site.description.clone()),
+        r#type: Some(String::from(match podcast.kind {
+            PodcastType::Episodic => "episodic",
+            PodcastType::Serial => "serial",
+        })),
+        ..ITunesChannelExtension::default()
+    }
+}
+
+// Only items with an enclosure are episodes; the post's image is the episode artwork.
+fn itunes_item(cfg: &Config, meta: &ContentMeta) -> Option<ITunesItemExtension> {
+    let enclosure = meta.enclosure.as_ref()?;
+    Some(ITunesItemExtension {
+        author: meta.author.clone(),
+        image: meta.image.as_ref().map(|image| media_url(cfg, image)),
+        duration: enclosure.duration.clone(),
+        explicit: enclosure.explicit.map(itunes_bool),
+        episode: enclosure.episode.map(|episode| episode.to_string()),
+        season: enclosure.season.map(|season| season.to_string()),
+        ..ITunesItemExtension::default()
+    })
+}
+
+pub fn category_url(cfg: &Config, path: &str) -> String {
+    url::join(&cfg.site.base_url, &cfg.category_path(path))
+}
+
+// A category's _category.toml description stands in for the site's.
+fn channel_description(cfg: &Config) -> &str {
+    cfg.category_config
+        .description
+        .as_deref()
+        .unwrap_or(&cfg.site.description)
+}
+
+pub fn rss_channel(cfg: &Config, content: &[&ContentUnit]) -> Channel {
+    let content = &feed_units(cfg, content);
+    let now: DateTime<Utc> = Utc::now();
+    let rfc_2822_date = now.to_rfc2822();
+    let podcast = cfg.podcast.is_podcast(&cfg.category);
+
+    let mut items: Vec<Item> = Vec::with_capacity(content.len());
+
+    for unit in content {
+        let (date, name, categories, description, link, contents) = (
+            unit.meta.date,
+            unit.meta.name.clone(),
+            item_categories(&unit.meta),
+            unit.description.clone(),
+            canonical_url(cfg, &unit.meta),
+            unit.contents.clone(),
+        );
+
+        let pub_date = local_midnight(date);
+
+        let mut item = ItemBuilder::default()
+            .title(name.clone())
+            .categories(
+                categories
+                    .into_iter()
+                    .map(|name| Category { name, domain: None })
+                    .collect::<Vec<Category>>(),
+            )
+            .description(description)
+            .content(
+                (cfg.feed.content == FeedContent::Full
+                    && !unit.meta.protected
+                    && !unit.meta.streamed)
+                    .then_some(contents),
+            )
+            .pub_date(pub_date.to_rfc2822())
+            .link(link)
+            .guid(item_guid(cfg, &unit.meta))
+            .enclosure(
+                unit.meta
+                    .enclosure
+                    .as_ref()
+                    .map(|enclosure| rss::Enclosure {
+                        url: enclosure_url(cfg, enclosure),
+                        length: enclosure.length.to_string(),
+                        mime_type: enclosure.mime_type.clone(),
+                    }),
+            )
+            .dublin_core_ext(unit.meta.author.clone().map(|author| DublinCoreExtension {
+                creators: vec![author],
+                ..DublinCoreExtension::default()
+            }))
+            .itunes_ext(podcast.then(|| itunes_item(cfg, &unit.meta)).flatten())
+            .build();
+        if let Some(updated) = unit.meta.updated {
+            item.extensions
+                .entry(String::from("atom"))
+                .or_default()
+                .insert(String::from("updated"), vec![atom_updated(updated)]);
+        }
+
+        items.push(item);
+    }
+    let last_build_date = content
+        .iter()
+        .map(|unit| unit.meta.modified())
+        .max()
+        .map_or(rfc_2822_date, |date| local_midnight(date).to_rfc2822());
+
+    let mut channel = ChannelBuilder::default()
+        .description(channel_description(cfg).to_string())
+        .generator(Some(String::from("WebWeaver")))
+        .items(items)
+        .language(cfg.site.language.clone())
+        .last_build_date(last_build_date)
+        .copyright(cfg.feed.copyright.clone())
+        .link(category_url(cfg, ""))
+        .pub_date(now.to_rfc2822())
+        .title(cfg.site.title.clone())
+        .webmaster(cfg.feed.webmaster.clone())
+        .itunes_ext(podcast.then(|| itunes_channel(cfg)))
+        .build();
+    if content.iter().any(|unit| unit.meta.updated.is_some()) {
+        channel
+            .namespaces
+            .insert(String::from("atom"), String::from(ATOM_NS));
+    }
+    channel
+}
+
+fn atom_entry(cfg: &Config, unit: &ContentUnit) -> Entry {
+    let meta = &unit.meta;
+    let link = content_url(cfg, meta);
+    let published: FixedDateTime = local_midnight(meta.date).fixed_offset();
+
+    // Protected and streamed posts are listed with their summary only, like content = "summary".
+    let full = cfg.feed.content == FeedContent::Full && !meta.protected && !meta.streamed;
+    let content_type = match meta.format {
+        ContentFormat::AsciiDoc => "text",
+        ContentFormat::Markdown | ContentFormat::Org => "html",
+    };
+
+    let content = Content {
+        value: Some(unit.contents.clone()),
+        content_type: Some(content_type.to_string()),
+        ..Content::default()
+    };
+
+    let mut links = vec![Link {
+        href: canonical_url(cfg, meta),
+        rel: String::from("alternate"),
+        ..Link::default()
+    }];
+    if let Some(enclosure) = &meta.enclosure {
+        links.push(Link {
+            href: enclosure_url(cfg, enclosure),
+            rel: String::from("enclosure"),
+            mime_type: Some(enclosure.mime_type.clone()),
+            length: Some(enclosure.length.to_string()),
+            ..Link::default()
+        });
+    }
+
+    EntryBuilder::default()
+        .title(Text::plain(meta.name.clone()))
+        .id(meta.guid.clone().unwrap_or(link))
+        .updated(local_midnight(meta.modified()).fixed_offset())
+        .published(Some(published))
+        .links(links)
+        .categories(
+            item_categories(meta)
+                .into_iter()
+                .map(|term| atom_syndication::Category {
+                    term,
+                    ..atom_syndication::Category::default()
+                })
+                .collect::<Vec<atom_syndication::Category>>(),
+        )
+        .authors(
+            meta.author
+                .iter()
+                .map(|author| Person {
+                    name: author.clone(),
+                    ..Person::default()
+                })
+                .collect::<Vec<Person>>(),
+        )
+        .summary((meta.summary.is_some() || !full).then(|| Text::plain(unit.description.clone())))
+        .content(full.then_some(content))
+        .build()
+}
+
+pub fn atom_feed(cfg: &Config, content: &[&ContentUnit]) -> Feed {
+    let content = &feed_units(cfg, content);
+    let now: FixedDateTime = Utc::now().fixed_offset();
+    let updated = content
+        .iter()
+        .map(|unit| local_midnight(unit.meta.modified()).fixed_offset())
+        .max()
+        .unwrap_or(now);
+
+    let author = Person {
+        name: cfg.author.clone().unwrap_or_else(|| cfg.site.title.clone()),
+        ..Person::default()
+    };
+
+    let generator = Generator {
+        value: String::from("WebWeaver"),
+        ..Generator::default()
+    };
+
+    FeedBuilder::default()
+        .title(Text::plain(cfg.site.title.clone()))
+        .subtitle(Some(Text::plain(channel_description(cfg).to_string())))
+        .id(category_url(cfg, &cfg.feed.atom_path))
+        .updated(updated)
+        .author(author)
+        .link(Link {
+            href: category_url(cfg, ""),
+            rel: String::from("alternate"),
+            ..Link::default()
+        })
+        .link(Link {
+            href: category_url(cfg, &cfg.feed.atom_path),
+            rel: String::from("self"),
+            mime_type: Some(String::from("application/atom+xml")),
+            ..Link::default()
+        })
+        .generator(Some(generator))
+        .rights(cfg.feed.copyright.clone().map(Text::plain))
+        .lang(cfg.site.language.clone())
+        .entries(
+            content
+                .iter()
+                .map(|unit| atom_entry(cfg, unit))
+                .collect::<Vec<Entry>>(),
+        )
+        .build()
+}
+
+fn json_author(name: &str) -> Value {
+    let mut author = Table::new();
+    author.insert(String::from("name"), Value::String(name.to_string()));
+    Value::Table(author)
+}
+
+fn json_item(cfg: &Config, unit: &ContentUnit) -> Value {
+    let meta = &unit.meta;
+    let link = content_url(cfg, meta);
+    let content_key = match meta.format {
+        ContentFormat::AsciiDoc => "content_text",
+        ContentFormat::Markdown | ContentFormat::Org => "content_html",
+    };
+
+    let mut item = Table::new();
+    item.insert(
+        String::from("id"),
+        Value::String(meta.guid.clone().unwrap_or_else(|| link.clone())),
+    );
+    item.insert(String::from("url"), Value::String(canonical_url(cfg, meta)));
+    item.insert(String::from("title"), Value::String(meta.name.clone()));
+    match cfg.feed.content {
+        FeedContent::Full if !meta.protected && !meta.streamed => item.insert(
+            String::from(content_key),
+            Value::String(unit.contents.clone()),
+        ),
+        _ => item.insert(
+            String::from("content_text"),
+            Value::String(unit.description.clone()),
+        ),
+    };
+    if meta.summary.is_some() {
+        item.insert(
+            String::from("summary"),
+            Value::String(unit.description.clone()),
+        );
+    }
+    item.insert(
+        String::from("date_published"),
+        Value::String(local_midnight(meta.date).to_rfc3339()),
+    );
+    if let Some(updated) = meta.updated {
+        item.insert(
+            String::from("date_modified"),
+            Value::String(local_midnight(updated).to_rfc3339()),
+        );
+    }
+    let tags = item_categories(meta);
+    if !tags.is_empty() {
+        item.insert(
+            String::from("tags"),
+            Value::Array(tags.into_iter().map(Value::String).collect()),
+        );
+    }
+    if let Some(author) = &meta.author {
+        item.insert(
+            String::from("authors"),
+            Value::Array(vec![json_author(author)]),
+        );
+    }
+    if let Some(language) = &meta.language {
+        item.insert(String::from("language"), Value::String(language.clone()));
+    }
+    if let Some(enclosure) = &meta.enclosure {
+        let mut attachment = Table::new();
+        attachment.insert(
+            String::from("url"),
+            Value::String(enclosure_url(cfg, enclosure)),
+        );
+        attachment.insert(
+            String::from("mime_type"),
+            Value::String(enclosure.mime_type.clone()),
+        );
+        if enclosure.length > 0 {
+            attachment.insert(
+                String::from("size_in_bytes"),
+                Value::Integer(i64::try_from(enclosure.length).unwrap_or(i64::MAX)),
+            );
+        }
+        item.insert(
+            String::from("attachments"),
+            Value::Array(vec![Value::Table(attachment)]),
+        );
+    }
+
+    Value::Table(item)
+}
+
+pub fn json_feed(cfg: &Config, content: &[&ContentUnit]) -> Table {
+    let content = &feed_units(cfg, content);
+    let mut feed = Table::new();
+    feed.insert(
+        String::from("version"),
+        Value::String(String::from("https://jsonfeed.org/version/1.1")),
+    );
+    feed.insert(String::from("title"), Value::String(cfg.site.title.clone()));
+    feed.insert(
+        String::from("home_page_url"),
+        Value::String(category_url(cfg, "")),
+    );
+    feed.insert(
+        String::from("feed_url"),
+        Value::String(category_url(cfg, &cfg.feed.json_path)),
+    );
+    if !channel_description(cfg).is_empty() {
+        feed.insert(
+            String::from("description"),
+            Value::String(channel_description(cfg).to_string()),
+        );
+    }
+    if let Some(language) = &cfg.site.language {
+        feed.insert(String::from("language"), Value::String(language.clone()));
+    }
+    if let Some(author) = &cfg.author {
+        feed.insert(
+            String::from("authors"),
+            Value::Array(vec![json_author(author)]),
+        );
+    }
+    feed.insert(
+        String::from("items"),
+        Value::Array(content.iter().map(|unit| json_item(cfg, unit)).collect()),
+    );
+    feed
+}
+
+fn feed_file(cfg: &Config, feed_path: &str) -> io::Result<(File, PathBuf)> {
+    let path: PathBuf = cfg
+        .output_root_path
+        .join(&cfg.output_content_root_path)
+        .join(feed_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok((File::create(&path)?, path))
+}
+
+pub fn feed_links(cfg: &Config) -> Vec<(&'static str, String)> {
+    let mut links = Vec::new();
+    if cfg.feed.rss {
+        links.push(("RSS", cfg.category_path(&cfg.feed.path)));
+    }
+    if cfg.feed.atom {
+        links.push(("Atom", cfg.category_path(&cfg.feed.atom_path)));
+    }
+    if cfg.feed.json {
+        links.push(("JSON Feed", cfg.category_path(&cfg.feed.json_path)));
+    }
+    links
+}
+
+fn feed_type(label: &str) -> &'static str {
+    match label {
+        "Atom" => "application/atom+xml",
+        "JSON Feed" => "application/feed+json",
+        _ => "application/rss+xml",
+    }
+}
+
+// <link rel="alternate"> tags for the head of every HTML page, so readers find the feeds.
+pub fn autodiscovery_links(cfg: &Config) -> String {
+    if !cfg.feed.autodiscovery {
+        return String::new();
+    }
+    feed_links(cfg)
+        .into_iter()
+        .map(|(label, path)| {
+            format!(
+                "<link rel=\"alternate\" type=\"{}\" title=\"{}\" href=\"{}\">\n",
+                feed_type(label),
+                markdown::escape_html(&format!("{} ({})", cfg.site.title, label)),
+                markdown::escape_html(&url::join(&cfg.site.base_url, &path))
+            )
+        })
+        .collect()
+}
+
+// Everything a page's head links to: the feeds and the webmention endpoints.
+pub fn head_links(cfg: &Config) -> String {
+    format!(
+        "{}{}",
+        autodiscovery_links(cfg),
+        webmention::endpoint_links(cfg)
+    )
+}
+
+// The docinfo file sits next to the RSS feed.
+fn docinfo_dir(cfg: &Config) -> String {
+    let dir = match cfg.feed.path.rsplit_once('/') {
+        Some((dir, _)) => dir,
+        None => "",
+    };
+    cfg.category_path(dir).trim_end_matches('/').to_string()
+}
+
+// AsciiDoc pages pull the links in through a shared docinfo file; to_root leads from the page to the output root.
+pub fn docinfo_attributes(cfg: &Config, to_root: &str) -> Vec<String> {
+    if head_links(cfg).is_empty() {
+        return Vec::new();
+    }
+    let dir = docinfo_dir(cfg);
+    let parts: Vec<&str> = [to_root, dir.as_str()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    let dir = if parts.is_empty() {
+        String::from(".")
+    } else {
+        parts.join("/")
+    };
+    vec![
+        String::from(":docinfo: shared-head"),
+        format!(":docinfodir: {}", dir),
+    ]
+}
+
+pub fn write_docinfo(cfg: &Config) -> io::Result<PathBuf> {
+    let path = cfg
+        .output_root_path
+        .join(docinfo_dir(cfg))
+        .join(DOCINFO_FILE);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, head_links(cfg))?;
+    Ok(path)
+}
+
+fn feed_err(path: &Path, err: impl ToString) -> WebWeaverError {
+    WebWeaverError::Feed {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+pub fn write_channel(
+    cfg: &Config,
+    channel: &Channel,
+    feed_path: &str,
+) -> Result<PathBuf, WebWeaverError> {
+    let (file, path) = feed_file(cfg, feed_path)?;
+    channel.write_to(file).map_err(|err| feed_err(&path, err))?;
+
+    Ok(path)
+}
+
+pub fn write_atom(cfg: &Config, feed: &Feed, feed_path: &str) -> Result<PathBuf, WebWeaverError> {
+    let (file, path) = feed_file(cfg, feed_path)?;
+    feed.write_to(file).map_err(|err| feed_err(&path, err))?;
+
+    Ok(path)
+}
+
+pub fn write_json(cfg: &Config, feed: &Table, feed_path: &str) -> Result<PathBuf, WebWeaverError> {
+    let (mut file, path) = feed_file(cfg, feed_path)?;
+    file.write_all(json::to_string(&Value::Table(feed.clone())).as_bytes())?;
+
+    Ok(path)
+}
+
+// Feeds of a listing page's posts, like an author's, written next to the page in dir.
+pub fn write_listing_feeds(
+    cfg: &Config,
+    entries: &[&ContentUnit],
+    dir: &str,
+    title: &str,
+    page_url: &str,
+) -> Result<Vec<PathBuf>, WebWeaverError> {
+    let mut written = Vec::new();
+
+    if cfg.feed.rss {
+        let rss_path = format!("{}/{}", dir, cfg.feed.path);
+        let mut channel = rss_channel(cfg, entries);
+        channel.set_title(title.to_string());
+        channel.set_link(page_url.to_string());
+        written.push(write_channel(cfg, &channel, &rss_path)?);
+    }
+
+    if cfg.feed.atom {
+        let atom_path = format!("{}/{}", dir, cfg.feed.atom_path);
+        let self_url = category_url(cfg, &atom_path);
+        let mut atom = atom_feed(cfg, entries);
+        atom.set_title(Text::plain(title.to_string()));
+        atom.set_id(self_url.clone());
+        atom.set_links(vec![
+            Link {
+                href: page_url.to_string(),
+                rel: String::from("alternate"),
+                ..Link::default()
+            },
+            Link {
+                href: self_url,
+                rel: String::from("self"),
+                mime_type: Some(String::from("application/atom+xml")),
+                ..Link::default()
+            },
+        ]);
+        written.push(write_atom(cfg, &atom, &atom_path)?);
+    }
+
+    if cfg.feed.json {
+        let json_path = format!("{}/{}", dir, cfg.feed.json_path);
+        let mut json = json_feed(cfg, entries);
+        json.insert(String::from("title"), Value::String(title.to_string()));
+        json.insert(
+            String::from("home_page_url"),
+            Value::String(page_url.to_string()),
+        );
+        json.insert(
+            String::from("feed_url"),
+            Value::String(category_url(cfg, &json_path)),
+        );
+        written.push(write_json(cfg, &json, &json_path)?);
+    }
+
+    Ok(written)
+}
+
+// The line linking a listing page's feeds, which lie next to it; empty without feeds.
+pub fn listing_feed_links(cfg: &Config) -> String {
+    let mut links = Vec::new();
+    if cfg.feed.rss {
+        links.push(format!("link:{}[RSS]", cfg.feed.path));
+    }
+    if cfg.feed.atom {
+        links.push(format!("link:{}[Atom]", cfg.feed.atom_path));
+    }
+    if cfg.feed.json {
+        links.push(format!("link:{}[JSON Feed]", cfg.feed.json_path));
+    }
+    if links.is_empty() {
+        return String::new();
+    }
+    format!("\u{1F4E1} {}\n\n", links.join(" · "))
+}
+
+pub fn write_rss_feed(cfg: &Config, content: &[ContentUnit]) -> Result<PathBuf, WebWeaverError> {
+    let content: Vec<&ContentUnit> = content.iter().collect();
+    write_channel(cfg, &rss_channel(cfg, &content), &cfg.feed.path)
+}
+
+pub fn write_atom_feed(cfg: &Config, content: &[ContentUnit]) -> Result<PathBuf, WebWeaverError> {
+    let content: Vec<&ContentUnit> = content.iter().collect();
+    write_atom(cfg, &atom_feed(cfg, &content), &cfg.feed.atom_path)
+}
+
+pub fn write_json_feed(cfg: &Config, content: &[ContentUnit]) -> Result<PathBuf, WebWeaverError> {
+    let content: Vec<&ContentUnit> = content.iter().collect();
+    write_json(cfg, &json_feed(cfg, &content), &cfg.feed.json_path)
+}
@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+
+use crate::content::ContentFormat;
+use crate::emoji::BodyScan;
+
+// Stand in for references while the Markdown or Org converter runs, as math::protect's placeholders do.
+const REF_START: char = '\u{e002}';
+const REF_END: char = '\u{e003}';
+
+// A definition line: [^label]: text in Markdown, [fn:label] text in Org.
+fn definition(format: ContentFormat, line: &str) -> Option<(String, String)> {
+    let (label, text) = match format {
+        ContentFormat::Markdown => line.strip_prefix("[^")?.split_once("]:")?,
+        ContentFormat::Org => line.strip_prefix("[fn:")?.split_once(']')?,
+        ContentFormat::AsciiDoc => return None,
+    };
+    if label.is_empty() || label.contains(|c: char| c.is_whitespace() || "[]:".contains(c)) {
+        return None;
+    }
+    Some((label.to_string(), text.trim().to_string()))
+}
+
+// Whether a line carries on the definition before it: indented lines, after blank ones too,
+// in Markdown; the lines up to a blank one, a heading or the next definition in Org.
+fn continues(format: ContentFormat, line: &str) -> bool {
+    match format {
+        ContentFormat::Markdown => line.starts_with("    ") || line.starts_with('\t'),
+        _ => {
+            !line.trim().is_empty() && !line.starts_with('*') && definition(format, line).is_none()
+        }
+    }
+}
+
+// Takes the definitions out of the body, by label.
+fn definitions(format: ContentFormat, text: &str) -> (String, BTreeMap<String, String>) {
+    let mut scan = BodyScan::default();
+    let mut body = String::with_capacity(text.len());
+    let mut definitions = BTreeMap::new();
+    let mut current: Option<(String, String)> = None;
+    // Blank lines that may still fall inside a Markdown definition.
+    let mut blank = String::new();
+    for line in text.split_inclusive('\n') {
+        if let Some((_, note)) = current.as_mut() {
+            if format == ContentFormat::Markdown && line.trim().is_empty() {
+                blank.push_str(line);
+                continue;
+            }
+            if continues(format, line) {
+                note.push_str(&blank);
+                note.push_str(line.trim_start());
+                blank.clear();
+                continue;
+            }
+            if let Some((label, note)) = current.take() {
+                definitions.entry(label).or_insert(note);
+            }
+            body.push_str(&blank);
+            blank.clear();
+        }
+        if scan.is_code(format, line) {
+            body.push_str(line);
+            continue;
+        }
+        match definition(format, line) {
+            Some((label, note)) => current = Some((label, note + "\n")),
+            None => body.push_str(line),
+        }
+    }
+    if let Some((label, note)) = current {
+        definitions.entry(label).or_insert(note);
+    }
+    body.push_str(&blank);
+    (body, definitions)
+}
+
+// The reference at the start of text, its length, and its label and any inline note:
+// [^label] in Markdown; [fn:label], [fn::note] and [fn:label:note] in Org.
+fn reference(format: ContentFormat, text: &str) -> Option<(usize, &str, Option<&str>)> {
+    match format {
+        ContentFormat::Markdown => {
+            let inner = text.strip_prefix("[^")?;
+            let label = &inner[..inner.find(']')?];
+            if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[') {
+                return None;
+            }
+            Some((label.len() + 3, label, None))
+        }
+        ContentFormat::Org => {
+            let inner = text.strip_prefix("[fn:")?;
+            let mut depth = 0;
+            let end = inner.char_indices().find_map(|(at, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' if depth == 0 => return Some(at),
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                None
+            })?;
+            let (label, note) = match inner[..end].split_once(':') {
+                Some((label, note)) => (label, Some(note.trim())),
+                None => (&inner[..end], None),
+            };
+            if label.contains(char::is_whitespace) || (label.is_empty() && note.is_none()) {
+                return None;
+            }
+            Some((end + 5, label, note))
+        }
+        ContentFormat::AsciiDoc => None,
+    }
+}
+
+// Replaces each footnote reference outside code with a placeholder and drops the definitions,
+// returning the notes in the order they are first referenced. AsciiDoc has footnote:[] of its own.
+pub fn extract(format: ContentFormat, text: &str) -> (String, Vec<String>) {
+    let marker = match format {
+        ContentFormat::Markdown => "[^",
+        ContentFormat::Org => "[fn:",
+        ContentFormat::AsciiDoc => return (text.to_string(), Vec::new()),
+    };
+    if !text.contains(marker) {
+        return (text.to_string(), Vec::new());
+    }
+    let (body, definitions) = definitions(format, text);
+    let mut numbers: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut notes: Vec<String> = Vec::new();
+    let mut out = String::with_capacity(body.len());
+    let mut scan = BodyScan::default();
+    for line in body.split_inclusive('\n') {
+        if scan.is_code(format, line) {
+            out.push_str(line);
+            continue;
+        }
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' && format == ContentFormat::Markdown {
+                let run = rest.chars().take_while(|c| *c == '`').count();
+                let end = rest[run..]
+                    .find(&rest[..run])
+                    .map_or(run, |end| run + end + run);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            let number = reference(format, rest).and_then(|(len, label, note)| {
+                let number = match (numbers.get(label), note) {
+                    (Some(number), _) if !label.is_empty() => *number,
+                    (_, Some(note)) => {
+                        notes.push(note.to_string());
+                        notes.len()
+                    }
+                    (_, None) => {
+                        notes.push(definitions.get(label)?.clone());
+                        notes.len()
+                    }
+                };
+                if !label.is_empty() {
+                    numbers.insert(label, number);
+                }
+                Some((len, number))
+            });
+            match number {
+                Some((len, number)) => {
+                    out.push(REF_START);
+                    out.push_str(&number.to_string());
+                    out.push(REF_END);
+                    rest = &rest[len..];
+                }
+                None => {
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+    }
+    (out, notes)
+}
+
+// The text without footnote references and definitions, for excerpts,
+// which may hold a reference without its definition.
+pub fn strip(format: ContentFormat, text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if definition(format, line).is_some() {
+            continue;
+        }
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            match reference(format, rest) {
+                Some((len, _, _)) => rest = &rest[len..],
+                None => {
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+    }
+    out
+}
+
+// A note as inline HTML, without the paragraph a one-paragraph note converts to.
+fn note_html(note: &str, convert: fn(&str) -> String) -> String {
+    let html = convert(note);
+    let html = html.trim();
+    match html
+        .strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>"))
+    {
+        Some(inner) if !inner.contains("<p>") => inner.to_string(),
+        _ => html.to_string(),
+    }
+}
+
+// Puts the references back into the converted HTML and adds the notes after it,
+// in the markup Asciidoctor gives footnote:[], so posts in every format share one stylesheet.
+pub fn restore(html: &str, notes: &[String], convert: fn(&str) -> String) -> String {
+    if notes.is_empty() {
+        return html.to_string();
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut referenced = vec![false; notes.len()];
+    let mut rest = html;
+    while let Some(start) = rest.find(REF_START) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + REF_START.len_utf8()..];
+        let number = after.split_once(REF_END).and_then(|(number, tail)| {
+            let number = number.parse::<usize>().ok()?;
+            (1..=notes.len())
+                .contains(&number)
+                .then_some((number, tail))
+        });
+        match number {
+            Some((number, tail)) => {
+                // Only the first reference to a note is the one its backlink returns to.
+                if referenced[number - 1] {
+                    out.push_str(&format!(
+                        "<sup class=\"footnoteref\">[<a class=\"footnote\" href=\"#_footnotedef_{0}\" title=\"View footnote.\">{0}</a>]</sup>",
+                        number
+                    ));
+                } else {
+                    out.push_str(&format!(
+                        "<sup class=\"footnote\">[<a id=\"_footnoteref_{0}\" class=\"footnote\" href=\"#_footnotedef_{0}\" title=\"View footnote.\">{0}</a>]</sup>",
+                        number
+                    ));
+                }
+                referenced[number - 1] = true;
+                rest = tail;
+            }
+            None => {
+                out.push(REF_START);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out.push_str("<div id=\"footnotes\">\n<hr>\n");
+    for (i, note) in notes.iter().enumerate() {
+        out.push_str(&format!(
+            "<div class=\"footnote\" id=\"_footnotedef_{0}\">\n<a href=\"#_footnoteref_{0}\">{0}</a>. {1}\n</div>\n",
+            i + 1,
+            note_html(note, convert)
+        ));
+    }
+    out.push_str("</div>\n");
+    out
+}
@@ -0,0 +1,230 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::toml;
+use crate::value::{Table, Value};
+use crate::yaml;
+
+pub const SIDECAR_SUFFIX: &str = ".meta.toml";
+
+pub struct FrontMatter {
+    fields: Table,
+}
+
+impl FrontMatter {
+    fn field_err(key: &str, expected: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("front matter key {} must be {}.", key, expected),
+        )
+    }
+
+    pub fn str(&self, key: &str) -> io::Result<Option<String>> {
+        match self.fields.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(Value::Integer(i)) => Ok(Some(i.to_string())),
+            Some(_) => Err(Self::field_err(key, "a string")),
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> io::Result<Option<bool>> {
+        match self.fields.get(key) {
+            None => Ok(None),
+            Some(Value::Boolean(b)) => Ok(Some(*b)),
+            Some(_) => Err(Self::field_err(key, "a boolean")),
+        }
+    }
+
+    pub fn integer(&self, key: &str) -> io::Result<Option<i64>> {
+        match self.fields.get(key) {
+            None => Ok(None),
+            Some(Value::Integer(i)) => Ok(Some(*i)),
+            Some(_) => Err(Self::field_err(key, "an integer")),
+        }
+    }
+
+    pub fn table(&self, key: &str) -> io::Result<Option<FrontMatter>> {
+        match self.fields.get(key) {
+            None => Ok(None),
+            Some(Value::Table(fields)) => Ok(Some(FrontMatter {
+                fields: fields.clone(),
+            })),
+            Some(_) => Err(Self::field_err(key, "a table")),
+        }
+    }
+
+    pub fn bool_or_table(&self, key: &str) -> io::Result<Option<Value>> {
+        match self.fields.get(key) {
+            None => Ok(None),
+            Some(value @ (Value::Boolean(_) | Value::Table(_))) => Ok(Some(value.clone())),
+            Some(_) => Err(Self::field_err(key, "a boolean or a table")),
+        }
+    }
+
+    pub fn str_list(&self, key: &str) -> io::Result<Vec<String>> {
+        match self.fields.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::String(s)) => Ok(s
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|v| v.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| Self::field_err(key, "a list of strings")),
+            Some(_) => Err(Self::field_err(key, "a list of strings")),
+        }
+    }
+
+    pub fn date(&self, key: &str) -> io::Result<Option<NaiveDate>> {
+        let s = match self.str(key)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let date_part = s.get(..10).unwrap_or(&s);
+        NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| Self::field_err(key, "a YYYY-MM-DD date"))
+    }
+}
+
+fn block<'a>(contents: &'a str, fence: &str) -> Option<(&'a str, &'a str)> {
+    let rest = contents.strip_prefix(fence)?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let head = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((head, body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+pub fn split(contents: &str) -> io::Result<(Option<FrontMatter>, &str)> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    if let Some((head, body)) = block(contents, "---") {
+        let fields = yaml::parse(head)?;
+        return Ok((
+            Some(FrontMatter { fields }),
+            body.trim_start_matches(['\r', '\n']),
+        ));
+    }
+
+    if let Some((head, body)) = block(contents, "+++") {
+        let fields = toml::parse(head)?;
+        return Ok((
+            Some(FrontMatter { fields }),
+            body.trim_start_matches(['\r', '\n']),
+        ));
+    }
+
+    Ok((None, contents))
+}
+
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(SIDECAR_SUFFIX);
+    PathBuf::from(sidecar)
+}
+
+// A post.adoc.meta.toml next to the file adds keys to its front matter, replacing any it sets itself.
+pub fn with_sidecar(
+    front_matter: Option<FrontMatter>,
+    path: &Path,
+) -> io::Result<Option<FrontMatter>> {
+    let sidecar = sidecar_path(path);
+    let contents = match fs::read_to_string(&sidecar) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(front_matter),
+        Err(err) => return Err(err),
+    };
+    let fields = toml::parse(&contents)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", sidecar.display(), err)))?;
+    let mut front_matter = front_matter.unwrap_or(FrontMatter {
+        fields: Table::new(),
+    });
+    front_matter.fields.extend(fields);
+    Ok(Some(front_matter))
+}
+
+fn org_keyword(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.trim().strip_prefix("#+")?.split_once(':')?;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key.to_ascii_lowercase(), value.trim()))
+}
+
+fn org_value(key: &str, value: &str) -> Option<(&'static str, Value)> {
+    let string = |value: &str| Value::String(value.to_string());
+    match key {
+        "title" => Some(("title", string(value))),
+        "date" => Some(("date", string(value.trim_matches(['<', '>', '[', ']'])))),
+        "updated" | "modified" => {
+            Some(("updated", string(value.trim_matches(['<', '>', '[', ']']))))
+        }
+        "author" => Some(("author", string(value))),
+        "description" | "summary" => Some(("summary", string(value))),
+        "filetags" => Some((
+            "tags",
+            Value::Array(
+                value
+                    .split([':', ' '])
+                    .filter(|tag| !tag.is_empty())
+                    .map(string)
+                    .collect(),
+            ),
+        )),
+        "tags" => Some(("tags", string(value))),
+        "draft" => Some((
+            "draft",
+            Value::Boolean(value.eq_ignore_ascii_case("t") || value == "true"),
+        )),
+        "series" => Some(("series", string(value))),
+        "image" => Some(("image", string(value))),
+        "guid" => Some(("guid", string(value))),
+        "slug" => Some(("slug", string(value))),
+        _ => None,
+    }
+}
+
+// Org files keep their metadata in leading #+KEY: value lines, mapped onto the front matter keys.
+pub fn split_org(contents: &str) -> io::Result<(Option<FrontMatter>, &str)> {
+    if let (Some(front_matter), body) = split(contents)? {
+        return Ok((Some(front_matter), body));
+    }
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let mut fields = Table::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        if !line.trim().is_empty() {
+            let (key, value) = match org_keyword(line) {
+                Some(keyword) => keyword,
+                None => break,
+            };
+            if let Some((key, value)) = org_value(&key, value) {
+                fields.insert(key.to_string(), value);
+            }
+        }
+        offset += line.len();
+    }
+
+    if fields.is_empty() {
+        return Ok((None, contents));
+    }
+    Ok((Some(FrontMatter { fields }), &contents[offset..]))
+}
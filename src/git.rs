@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::{Local, NaiveDate};
+
+use crate::config::Config;
+use crate::discovery::ContentFile;
+use crate::tools::spawn_err;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GitDates {
+    pub created: NaiveDate,
+    pub modified: NaiveDate,
+}
+
+fn git(cfg: &Config, dir: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(&cfg.build.git)
+        .arg("-c")
+        .arg("core.quotePath=false")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|err| spawn_err(&cfg.build.git, "git", "[build] git to its path", err))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} {} failed in {}: {}",
+            cfg.build.git,
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// The author dates of the first and last commit touching each file below dir, keyed by absolute path.
+pub fn git_dates(cfg: &Config, dir: &Path) -> io::Result<BTreeMap<PathBuf, GitDates>> {
+    let top = git(cfg, dir, &["rev-parse", "--show-toplevel"])?;
+    let top = PathBuf::from(top.trim());
+    let log = git(
+        cfg,
+        dir,
+        &[
+            "log",
+            "--format=%x00%as",
+            "--name-only",
+            "--no-renames",
+            "--",
+            ".",
+        ],
+    )?;
+
+    let mut dates: BTreeMap<PathBuf, GitDates> = BTreeMap::new();
+    let mut date = None;
+    for line in log.lines() {
+        if let Some(commit_date) = line.strip_prefix('\0') {
+            date = NaiveDate::parse_from_str(commit_date, "%Y-%m-%d").ok();
+            continue;
+        }
+        let date = match date {
+            Some(date) if !line.is_empty() => date,
+            _ => continue,
+        };
+        dates
+            .entry(top.join(line))
+            .and_modify(|dates| {
+                dates.created = dates.created.min(date);
+                dates.modified = dates.modified.max(date);
+            })
+            .or_insert(GitDates {
+                created: date,
+                modified: date,
+            });
+    }
+    Ok(dates)
+}
+
+// A file that was never committed is dated today, as it would be if it were committed now.
+pub fn set_git_dates(
+    cfg: &Config,
+    dir: &Path,
+    content_files: &mut [ContentFile],
+) -> io::Result<()> {
+    let dates = git_dates(cfg, dir)?;
+    let today = Local::now().date_naive();
+    for content_file in content_files {
+        content_file.git_dates = Some(
+            fs::canonicalize(&content_file.path)
+                .ok()
+                .and_then(|path| dates.get(&path).copied())
+                .unwrap_or(GitDates {
+                    created: today,
+                    modified: today,
+                }),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,572 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::content::base_path;
+use crate::markdown::{escape_html, unescape_html};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Class {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Literal,
+    Type,
+    Function,
+    Tag,
+    Attribute,
+}
+
+const CLASSES: [Class; 9] = [
+    Class::Comment,
+    Class::String,
+    Class::Number,
+    Class::Keyword,
+    Class::Literal,
+    Class::Type,
+    Class::Function,
+    Class::Tag,
+    Class::Attribute,
+];
+
+impl Class {
+    fn name(self) -> &'static str {
+        match self {
+            Class::Comment => "comment",
+            Class::String => "string",
+            Class::Number => "number",
+            Class::Keyword => "keyword",
+            Class::Literal => "literal",
+            Class::Type => "type",
+            Class::Function => "function",
+            Class::Tag => "tag",
+            Class::Attribute => "attribute",
+        }
+    }
+}
+
+// CSS declarations for each class, in the order of CLASSES.
+#[derive(Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    background: &'static str,
+    foreground: &'static str,
+    styles: [&'static str; 9],
+}
+
+pub const THEMES: [Theme; 4] = [
+    Theme {
+        name: "github",
+        background: "#f6f8fa",
+        foreground: "#24292e",
+        styles: [
+            "color:#6a737d;font-style:italic",
+            "color:#032f62",
+            "color:#005cc5",
+            "color:#d73a49",
+            "color:#005cc5",
+            "color:#6f42c1",
+            "color:#6f42c1",
+            "color:#22863a",
+            "color:#6f42c1",
+        ],
+    },
+    Theme {
+        name: "monokai",
+        background: "#272822",
+        foreground: "#f8f8f2",
+        styles: [
+            "color:#75715e;font-style:italic",
+            "color:#e6db74",
+            "color:#ae81ff",
+            "color:#f92672",
+            "color:#ae81ff",
+            "color:#66d9ef;font-style:italic",
+            "color:#a6e22e",
+            "color:#f92672",
+            "color:#a6e22e",
+        ],
+    },
+    Theme {
+        name: "solarized-light",
+        background: "#fdf6e3",
+        foreground: "#657b83",
+        styles: [
+            "color:#93a1a1;font-style:italic",
+            "color:#2aa198",
+            "color:#d33682",
+            "color:#859900",
+            "color:#cb4b16",
+            "color:#b58900",
+            "color:#268bd2",
+            "color:#268bd2",
+            "color:#b58900",
+        ],
+    },
+    Theme {
+        name: "solarized-dark",
+        background: "#002b36",
+        foreground: "#839496",
+        styles: [
+            "color:#586e75;font-style:italic",
+            "color:#2aa198",
+            "color:#d33682",
+            "color:#859900",
+            "color:#cb4b16",
+            "color:#b58900",
+            "color:#268bd2",
+            "color:#268bd2",
+            "color:#b58900",
+        ],
+    },
+];
+
+pub fn theme(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().find(|theme| theme.name == name)
+}
+
+impl Theme {
+    fn style(&self, class: Class) -> &'static str {
+        self.styles[class as usize]
+    }
+}
+
+struct Language {
+    names: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    // Python's """ and ''' strings, which run over lines.
+    triple_quotes: bool,
+    // Space-separated words.
+    keywords: &'static str,
+    literals: &'static str,
+    // Capitalized names are types.
+    types: bool,
+    // Keywords in any case, as in SQL.
+    ignore_case: bool,
+}
+
+const C_KEYWORDS: &str = "\
+    abstract auto bool break case catch char class const continue default delete do double else \
+    enum extends extern final float for fun func goto guard if implements import inline int \
+    interface let long namespace new override package private protected public return short \
+    signed sizeof static struct switch template this throw throws try typedef typename union \
+    unsigned using val var virtual void volatile when while";
+
+const LANGUAGES: [Language; 11] = [
+    Language {
+        names: &["rust", "rs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        triple_quotes: false,
+        keywords: "\
+            as async await break const continue crate dyn else enum extern fn for if impl in let \
+            loop match mod move mut pub ref return self static struct super trait type unsafe \
+            use where while",
+        literals: "true false",
+        types: true,
+        ignore_case: false,
+    },
+    Language {
+        names: &["python", "py", "python3"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        triple_quotes: true,
+        keywords: "\
+            and as assert async await break case class continue def del elif else except finally \
+            for from global if import in is lambda match nonlocal not or pass raise return try \
+            while with yield",
+        literals: "True False None",
+        types: true,
+        ignore_case: false,
+    },
+    Language {
+        names: &["javascript", "js", "jsx", "typescript", "ts", "tsx", "mjs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        triple_quotes: false,
+        keywords: "\
+            async await break case catch class const continue debugger default delete do else \
+            enum export extends finally for from function if implements import in instanceof \
+            interface let new of private protected public readonly return static super switch \
+            this throw try type typeof var void while with yield",
+        literals: "true false null undefined NaN",
+        types: true,
+        ignore_case: false,
+    },
+    Language {
+        names: &[
+            "c", "h", "cpp", "c++", "cc", "hpp", "java", "cs", "csharp", "kotlin", "kt", "swift",
+            "scala", "dart",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        triple_quotes: false,
+        keywords: C_KEYWORDS,
+        literals: "true false null nullptr NULL nil",
+        types: true,
+        ignore_case: false,
+    },
+    Language {
+        names: &["go", "golang"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        triple_quotes: false,
+        keywords: "\
+            break case chan const continue default defer else fallthrough for func go goto if \
+            import interface map package range return select struct switch type var",
+        literals: "true false nil iota",
+        types: true,
+        ignore_case: false,
+    },
+    Language {
+        names: &["sh", "bash", "shell", "zsh", "console"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        triple_quotes: false,
+        keywords: "\
+            alias case declare do done elif else esac exit export fi for function if in local \
+            readonly return set shift source then unset until while",
+        literals: "true false",
+        types: false,
+        ignore_case: false,
+    },
+    Language {
+        names: &["json", "jsonc"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        triple_quotes: false,
+        keywords: "",
+        literals: "true false null",
+        types: false,
+        ignore_case: false,
+    },
+    Language {
+        names: &["toml", "ini"],
+        line_comments: &["#", ";"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        triple_quotes: false,
+        keywords: "",
+        literals: "true false",
+        types: false,
+        ignore_case: false,
+    },
+    Language {
+        names: &["yaml", "yml"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        triple_quotes: false,
+        keywords: "",
+        literals: "true false null yes no",
+        types: false,
+        ignore_case: false,
+    },
+    Language {
+        names: &["sql", "postgresql", "sqlite", "mysql"],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\''],
+        triple_quotes: false,
+        keywords: "\
+            add all alter and as asc between by case create cross default delete desc distinct \
+            drop else end exists foreign from full group having in index inner insert into is \
+            join key left like limit not offset on or order outer primary references returning \
+            right select set table then union unique update values when where with",
+        literals: "true false null",
+        types: false,
+        ignore_case: true,
+    },
+    Language {
+        names: &["css", "scss", "less"],
+        line_comments: &[],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        triple_quotes: false,
+        keywords: "important",
+        literals: "",
+        types: false,
+        ignore_case: false,
+    },
+];
+
+const MARKUP: [&str; 5] = ["html", "xml", "svg", "xhtml", "vue"];
+
+fn find_language(name: &str) -> Option<&'static Language> {
+    let name = name.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|language| language.names.contains(&name.as_str()))
+}
+
+// The length of the string starting at text, quote included; an unclosed one ends with its line,
+// except for triple-quoted strings.
+fn string_len(text: &str, quote: &str) -> usize {
+    let mut escaped = false;
+    for (at, c) in text.char_indices().skip(quote.chars().count()) {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if c == '\\' {
+            escaped = true;
+        } else if text[at..].starts_with(quote) {
+            return at + quote.len();
+        } else if c == '\n' && quote.len() == 1 {
+            return at;
+        }
+    }
+    text.len()
+}
+
+fn code_tokens<'a>(code: &'a str, language: &Language) -> Vec<(Option<Class>, &'a str)> {
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    while at < code.len() {
+        let rest = &code[at..];
+        let c = rest.chars().next().unwrap_or(' ');
+        let (class, len) = if language
+            .line_comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+        {
+            (Some(Class::Comment), rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some((open, close)) = language
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open))
+        {
+            let len = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+            (Some(Class::Comment), len)
+        } else if language.quotes.contains(&c) {
+            let triple = c.to_string().repeat(3);
+            let quote = if language.triple_quotes && rest.starts_with(&triple) {
+                &rest[..3]
+            } else {
+                &rest[..c.len_utf8()]
+            };
+            (Some(Class::String), string_len(rest, quote))
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            (Some(Class::Number), len)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            let is = |words: &str| {
+                words.split_whitespace().any(|known| {
+                    known == word || (language.ignore_case && known.eq_ignore_ascii_case(word))
+                })
+            };
+            let class = if is(language.keywords) {
+                Some(Class::Keyword)
+            } else if is(language.literals) {
+                Some(Class::Literal)
+            } else if rest[len..].starts_with('(') && language.types {
+                Some(Class::Function)
+            } else if language.types && c.is_uppercase() {
+                Some(Class::Type)
+            } else {
+                None
+            };
+            (class, len)
+        } else {
+            (None, c.len_utf8())
+        };
+        tokens.push((class, &rest[..len]));
+        at += len;
+    }
+    tokens
+}
+
+// Tags, their attributes and values, and comments; the text between tags is left plain.
+fn markup_tokens(code: &str) -> Vec<(Option<Class>, &str)> {
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    let mut in_tag = false;
+    while at < code.len() {
+        let rest = &code[at..];
+        let c = rest.chars().next().unwrap_or(' ');
+        let (class, len) = if rest.starts_with("<!--") {
+            let len = rest.find("-->").map_or(rest.len(), |end| end + 3);
+            (Some(Class::Comment), len)
+        } else if !in_tag
+            && c == '<'
+            && rest[1..].starts_with(|c: char| c.is_alphabetic() || "/!?".contains(c))
+        {
+            // The < or </ goes out plain, then the tag name.
+            in_tag = true;
+            let name = rest[1..].trim_start_matches(['/', '!', '?']);
+            let name_start = rest.len() - name.len();
+            tokens.push((None, &rest[..name_start]));
+            at += name_start;
+            let len = name
+                .find(|c: char| !(c.is_alphanumeric() || "-_:.".contains(c)))
+                .unwrap_or(name.len());
+            (Some(Class::Tag), len)
+        } else if in_tag && (c == '"' || c == '\'') {
+            (Some(Class::String), string_len(rest, &rest[..1]))
+        } else if in_tag && (c.is_alphabetic() || c == '_') {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || "-_:.".contains(c)))
+                .unwrap_or(rest.len());
+            (Some(Class::Attribute), len)
+        } else {
+            if c == '>' {
+                in_tag = false;
+            }
+            (None, c.len_utf8())
+        };
+        if len > 0 {
+            tokens.push((class, &code[at..at + len]));
+        }
+        at += len;
+    }
+    tokens
+}
+
+fn highlight_code(cfg: &Config, language: &str, code: &str) -> Option<String> {
+    let tokens = if MARKUP.contains(&language.to_ascii_lowercase().as_str()) {
+        markup_tokens(code)
+    } else {
+        code_tokens(code, find_language(language)?)
+    };
+    let mut out = String::with_capacity(code.len() * 2);
+    for (class, text) in tokens {
+        match class {
+            Some(class) if cfg.highlight.stylesheet.is_some() => out.push_str(&format!(
+                "<span class=\"hl-{}\">{}</span>",
+                class.name(),
+                escape_html(text)
+            )),
+            Some(class) => out.push_str(&format!(
+                "<span style=\"{}\">{}</span>",
+                cfg.highlight.theme.style(class),
+                escape_html(text)
+            )),
+            None => out.push_str(&escape_html(text)),
+        }
+    }
+    Some(out)
+}
+
+// Highlights each <pre><code class="language-…"> block in html, as the Markdown and Org converters
+// and Asciidoctor write them; blocks in languages it does not know are left as they are.
+pub fn highlight_html(cfg: &Config, html: &str) -> String {
+    const OPEN: &str = "<code class=\"language-";
+    let mut out = String::with_capacity(html.len() * 2);
+    let mut rest = html;
+    while let Some(start) = rest.find(OPEN) {
+        let in_pre = rest[..start]
+            .rfind('<')
+            .is_some_and(|tag| rest[tag..start].starts_with("<pre"));
+        let block = rest[start..].find('>').and_then(|tag_end| {
+            let language = rest[start + OPEN.len()..].split(['"', ' ']).next()?;
+            let body_start = start + tag_end + 1;
+            let body_end = body_start + rest[body_start..].find("</code>")?;
+            let code = unescape_html(&rest[body_start..body_end]);
+            Some((
+                language,
+                body_start,
+                body_end,
+                highlight_code(cfg, language, &code)?,
+            ))
+        });
+        match block {
+            Some((_, body_start, body_end, highlighted)) if in_pre => {
+                out.push_str(&rest[..start]);
+                out.push_str("<code");
+                if cfg.highlight.stylesheet.is_none() {
+                    out.push_str(&format!(
+                        " style=\"display:block;background:{};color:{}\"",
+                        cfg.highlight.theme.background, cfg.highlight.theme.foreground
+                    ));
+                }
+                out.push_str(&rest[start + "<code".len()..body_start]);
+                out.push_str(&highlighted);
+                rest = &rest[body_end..];
+            }
+            _ => {
+                out.push_str(&rest[..start + OPEN.len()]);
+                rest = &rest[start + OPEN.len()..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Highlights a page Asciidoctor rendered with --html.
+pub fn highlight_file(cfg: &Config, path: &Path) -> io::Result<()> {
+    let html = fs::read_to_string(path)?;
+    let highlighted = highlight_html(cfg, &html);
+    if highlighted != html {
+        fs::write(path, highlighted)?;
+    }
+    Ok(())
+}
+
+fn stylesheet_css(cfg: &Config) -> String {
+    let theme = cfg.highlight.theme;
+    let mut css = format!(
+        "pre > code[class^=\"language-\"] {{ display: block; background: {}; color: {}; }}\n",
+        theme.background, theme.foreground
+    );
+    for class in CLASSES {
+        css.push_str(&format!(
+            ".hl-{} {{ {}; }}\n",
+            class.name(),
+            theme.style(class).replace(';', "; ").replace(':', ": ")
+        ));
+    }
+    css
+}
+
+// The stylesheet [highlight] stylesheet names, written under the output root.
+pub fn write_stylesheet(cfg: &Config) -> io::Result<Option<PathBuf>> {
+    let stylesheet = match &cfg.highlight.stylesheet {
+        Some(stylesheet) if cfg.highlight.enabled => stylesheet,
+        _ => return Ok(None),
+    };
+    let path = cfg.output_root_path.join(stylesheet);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, stylesheet_css(cfg))?;
+    Ok(Some(path))
+}
+
+// The <link> Asciidoctor pages rendered with --html get in their head.
+pub fn stylesheet_link(cfg: &Config, dir: &str) -> String {
+    match stylesheet_url(cfg, dir) {
+        Some(url) => format!("<link rel=\"stylesheet\" href=\"{}\">\n", escape_html(&url)),
+        None => String::new(),
+    }
+}
+
+// The stylesheet's URL from a page in dir, or None with inline styles.
+pub fn stylesheet_url(cfg: &Config, dir: &str) -> Option<String> {
+    let stylesheet = cfg.highlight.stylesheet.as_ref()?;
+    if !cfg.highlight.enabled {
+        return None;
+    }
+    Some(match base_path(dir) {
+        base if base.is_empty() => stylesheet.clone(),
+        base => format!("{}/{}", base, stylesheet),
+    })
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::config::Config;
+use crate::highlight;
+use crate::smartypants;
+use crate::tools::{is_fresh, spawn_err};
+
+fn is_asciidoc(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("adoc" | "asciidoc")
+    )
+}
+
+fn insert_head(path: &Path, head: &str) -> io::Result<()> {
+    let html = fs::read_to_string(path)?;
+    if let Some(at) = html.find("</head>") {
+        fs::write(path, format!("{}{}{}", &html[..at], head, &html[at..]))?;
+    }
+    Ok(())
+}
+
+// Renders an AsciiDoc fragment to embeddable HTML, without the document header and footer.
+pub fn render_fragment(cfg: &Config, source: &str) -> io::Result<String> {
+    let mut child = Command::new(&cfg.build.asciidoctor)
+        .args(["--embedded", "-o", "-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            spawn_err(
+                &cfg.build.asciidoctor,
+                "asciidoctor",
+                "[build] asciidoctor to its path",
+                err,
+            )
+        })?;
+
+    // Written from another thread, so a large page cannot fill both pipes at once.
+    // A failed write shows up as asciidoctor's exit status below.
+    let stdin = child.stdin.take();
+    let output = thread::scope(|scope| {
+        scope.spawn(move || match stdin {
+            Some(mut stdin) => stdin.write_all(source.as_bytes()),
+            None => Ok(()),
+        });
+        child.wait_with_output()
+    })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed with {}: {}",
+            cfg.build.asciidoctor,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(io::Error::other)
+}
+
+pub fn render_asciidoc(
+    cfg: &Config,
+    outputs: &[String],
+    heads: &BTreeMap<String, String>,
+    smartened: &BTreeSet<String>,
+) -> io::Result<Vec<PathBuf>> {
+    let root = &cfg.output_root_path;
+    let sources: Vec<PathBuf> = outputs
+        .iter()
+        .map(|output| root.join(output))
+        .filter(|path| is_asciidoc(path))
+        .collect();
+    let targets: Vec<PathBuf> = sources
+        .iter()
+        .map(|path| path.with_extension("html"))
+        .collect();
+
+    let stale: Vec<&PathBuf> = sources
+        .iter()
+        .zip(&targets)
+        .filter(|(source, target)| !is_fresh(source, target))
+        .map(|(source, _)| source)
+        .collect();
+
+    if !stale.is_empty() {
+        let status = Command::new(&cfg.build.asciidoctor)
+            .arg("--source-dir")
+            .arg(root)
+            .arg("--destination-dir")
+            .arg(root)
+            .args(&stale)
+            .status()
+            .map_err(|err| {
+                spawn_err(
+                    &cfg.build.asciidoctor,
+                    "asciidoctor",
+                    "[build] asciidoctor to its path",
+                    err,
+                )
+            })?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} failed with {}.",
+                cfg.build.asciidoctor, status
+            )));
+        }
+        let stale_sources: HashSet<&PathBuf> = stale.iter().copied().collect();
+        for output in outputs {
+            let source = root.join(output);
+            if !stale_sources.contains(&source) {
+                continue;
+            }
+            if let Some(head) = heads.get(output) {
+                insert_head(&source.with_extension("html"), head)?;
+            }
+            if cfg.highlight.enabled {
+                highlight::highlight_file(cfg, &source.with_extension("html"))?;
+            }
+            if smartened.contains(output) {
+                smartypants::smarten_file(&source.with_extension("html"))?;
+            }
+        }
+        eprintln!("rendered {} pages to html.", stale.len());
+    }
+
+    Ok(targets)
+}
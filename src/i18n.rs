@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::content::{ContentMeta, ContentUnit, Translation};
+
+pub fn is_language_tag(tag: &str) -> bool {
+    let (language, region) = match tag.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (tag, None),
+    };
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && region.is_none_or(|region| {
+            (2..=4).contains(&region.len()) && region.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+fn primary(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+// "post.de" names the German version of "post".
+pub fn split_language(file_stem: &str) -> (&str, Option<String>) {
+    match file_stem.rsplit_once('.') {
+        Some((stem, tag)) if !stem.is_empty() && is_language_tag(tag) => {
+            (stem, Some(tag.to_string()))
+        }
+        _ => (file_stem, None),
+    }
+}
+
+pub fn default_language(cfg: &Config) -> String {
+    match cfg.site.language.as_deref().map(primary) {
+        Some(language) if !language.is_empty() => language,
+        _ => String::from("en"),
+    }
+}
+
+pub fn is_default_language(meta: &ContentMeta, default_language: &str) -> bool {
+    meta.language
+        .as_deref()
+        .is_none_or(|language| primary(language) == default_language)
+}
+
+fn translation_key(meta: &ContentMeta) -> String {
+    format!("{}/{}", meta.path, meta.filesystem_friendly_name)
+}
+
+// Posts with the same date, category and file name are translations of each other.
+pub fn link_translations(
+    content_files_meta_data: &mut BTreeMap<PathBuf, ContentMeta>,
+    default_language: &str,
+) {
+    let mut groups: BTreeMap<String, Vec<Translation>> = BTreeMap::new();
+    for meta in content_files_meta_data.values() {
+        groups
+            .entry(translation_key(meta))
+            .or_default()
+            .push(Translation {
+                language: meta
+                    .language
+                    .clone()
+                    .unwrap_or_else(|| default_language.to_string()),
+                path: meta.output_path(),
+                format: meta.format,
+            });
+    }
+
+    for meta in content_files_meta_data.values_mut() {
+        let output_path = meta.output_path();
+        meta.translations = groups[&translation_key(meta)]
+            .iter()
+            .filter(|translation| translation.path != output_path)
+            .cloned()
+            .collect();
+    }
+}
+
+// Splits off the posts in other languages than the site's, grouped by language.
+pub fn split_translated(
+    content: Vec<ContentUnit>,
+    default_language: &str,
+) -> (Vec<ContentUnit>, BTreeMap<String, Vec<ContentUnit>>) {
+    let mut primary_content = Vec::with_capacity(content.len());
+    let mut translated: BTreeMap<String, Vec<ContentUnit>> = BTreeMap::new();
+    for unit in content {
+        match &unit.meta.language {
+            Some(language) if !is_default_language(&unit.meta, default_language) => {
+                translated.entry(language.clone()).or_default().push(unit);
+            }
+            _ => primary_content.push(unit),
+        }
+    }
+    (primary_content, translated)
+}
+
+// The index and feeds of one language live under <category>/<language>/.
+pub fn language_config(cfg: &Config, language: &str) -> Config {
+    let mut language_cfg = cfg.clone();
+    language_cfg.translation = Some(language.to_string());
+    language_cfg.site.language = Some(language.to_string());
+    language_cfg.site.locale = language.to_string();
+    for path in [
+        &mut language_cfg.feed.path,
+        &mut language_cfg.feed.atom_path,
+        &mut language_cfg.feed.json_path,
+    ] {
+        *path = format!("{}/{}", language, path);
+    }
+    language_cfg
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::discovery::glob_match;
+use crate::url;
+
+pub const IGNORE_FILE: &str = ".webweaverignore";
+
+struct Rule {
+    segments: Vec<String>,
+    negated: bool,
+    dir_only: bool,
+    // Patterns with a slash match from the ignore file's directory; others match any name below it.
+    anchored: bool,
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let anchored = pattern.contains('/');
+    let segments: Vec<String> = pattern
+        .trim_start_matches('/')
+        .split('/')
+        .map(String::from)
+        .collect();
+    if segments.iter().all(|segment| segment.is_empty()) {
+        return None;
+    }
+    Some(Rule {
+        segments,
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+// A "**" segment matches any number of directories; "*" and "?" stay within one.
+fn segments_match(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| segments_match(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => glob_match(first, name) && segments_match(rest, path),
+            None => false,
+        },
+    }
+}
+
+impl Rule {
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let path: Vec<&str> = relative.split('/').collect();
+        if self.anchored {
+            segments_match(&self.segments, &path)
+        } else {
+            path.last()
+                .is_some_and(|name| glob_match(&self.segments[0], name))
+        }
+    }
+}
+
+// The .webweaverignore files of the directories being walked, outermost first.
+#[derive(Default)]
+pub struct IgnoreFiles {
+    stack: Vec<(PathBuf, Vec<Rule>)>,
+}
+
+impl IgnoreFiles {
+    // Reads dir's ignore file, if any; pair with leave once dir is done.
+    pub fn enter(&mut self, dir: &Path) -> io::Result<()> {
+        let rules = match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(contents) => contents.lines().filter_map(parse_rule).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!("{}: {}", dir.join(IGNORE_FILE).display(), err),
+                ))
+            }
+        };
+        self.stack.push((dir.to_path_buf(), rules));
+        Ok(())
+    }
+
+    pub fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    // Enters the directories from the .content root down to dir's parent, so their rules apply
+    // to a walk that starts at dir.
+    pub fn enter_parents(&mut self, root: Option<&Path>, dir: &Path) -> io::Result<()> {
+        let mut dirs: Vec<&Path> = match root {
+            Some(root) if dir.starts_with(root) => dir
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| ancestor.starts_with(root))
+                .collect(),
+            _ => Vec::new(),
+        };
+        dirs.reverse();
+        for dir in dirs {
+            self.enter(dir)?;
+        }
+        Ok(())
+    }
+
+    // Like .gitignore, the last matching rule wins and "!" brings a path back.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for (base, rules) in &self.stack {
+            let relative = match path.strip_prefix(base) {
+                Ok(relative) => url::from_path(relative),
+                Err(_) => continue,
+            };
+            for rule in rules {
+                if rule.matches(&relative, is_dir) {
+                    ignored = !rule.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
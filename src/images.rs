@@ -0,0 +1,114 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::progress::Timings;
+use crate::tools::{is_fresh, spawn_err};
+
+pub fn is_raster(path: &str) -> bool {
+    let ext = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg")
+}
+
+fn variant_ext<'a>(cfg: &Config, ext: &'a str) -> &'a str {
+    if cfg.images.webp {
+        "webp"
+    } else {
+        ext
+    }
+}
+
+fn split_ext(path: &str) -> (&str, &str) {
+    path.rsplit_once('.').unwrap_or((path, ""))
+}
+
+pub fn optimized(cfg: &Config, path: &str) -> String {
+    let (stem, ext) = split_ext(path);
+    format!("{}.{}", stem, variant_ext(cfg, ext))
+}
+
+fn variants(cfg: &Config, path: &str) -> Vec<(Option<u32>, String)> {
+    let (stem, ext) = split_ext(path);
+    let ext = variant_ext(cfg, ext);
+
+    let mut variants: Vec<(Option<u32>, String)> = cfg
+        .images
+        .widths
+        .iter()
+        .map(|width| (Some(*width), format!("{}-{}w.{}", stem, width, ext)))
+        .collect();
+    if cfg.images.webp {
+        variants.push((None, format!("{}.webp", stem)));
+    }
+    variants
+}
+
+pub fn srcset(cfg: &Config, path: &str) -> String {
+    variants(cfg, path)
+        .into_iter()
+        .filter_map(|(width, variant)| width.map(|width| format!("{} {}w", variant, width)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn convert(cfg: &Config, source: &Path, target: &Path, width: Option<u32>) -> io::Result<()> {
+    let mut command = Command::new(&cfg.images.command);
+    command.arg(source);
+    if let Some(width) = width {
+        command.arg("-resize").arg(format!("{}x>", width));
+    }
+    command
+        .arg("-strip")
+        .arg("-quality")
+        .arg(cfg.images.quality.to_string())
+        .arg(target);
+
+    let status = command
+        .status()
+        .map_err(|err| spawn_err(&cfg.images.command, "ImageMagick", "[images] command", err))?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed on {} with {}.",
+            cfg.images.command,
+            source.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+pub fn write_variants(
+    cfg: &Config,
+    assets: &[PathBuf],
+    timings: &Timings,
+) -> io::Result<Vec<PathBuf>> {
+    let mut converted = 0;
+    let mut written = Vec::new();
+
+    for asset in assets {
+        let path = asset.to_string_lossy();
+        if !is_raster(&path) {
+            continue;
+        }
+        timings.file("assets", asset, || -> io::Result<()> {
+            for (width, variant) in variants(cfg, &path) {
+                let variant = PathBuf::from(variant);
+                if !is_fresh(asset, &variant) {
+                    convert(cfg, asset, &variant, width)?;
+                    converted += 1;
+                }
+                written.push(variant);
+            }
+            Ok(())
+        })?;
+    }
+
+    if converted > 0 {
+        eprintln!("wrote {} image variants.", converted);
+    }
+    Ok(written)
+}
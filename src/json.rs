@@ -0,0 +1,297 @@
+use std::io;
+
+use crate::value::{Table, Value};
+
+fn parse_err(pos: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("json parse error at byte {}: {}", pos, msg),
+    )
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.bytes.get(self.pos) {
+            if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> io::Result<()> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(parse_err(self.pos, &format!("expected {}", literal)))
+        }
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b't') => self.expect("true").map(|_| Value::Boolean(true)),
+            Some(b'f') => self.expect("false").map(|_| Value::Boolean(false)),
+            Some(b'n') => self.expect("null").map(|_| Value::Null),
+            Some(b) if *b == b'-' || b.is_ascii_digit() => self.number(),
+            Some(_) => Err(parse_err(self.pos, "unexpected character")),
+            None => Err(parse_err(self.pos, "unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> io::Result<Value> {
+        self.pos += 1;
+        let mut table = Table::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(parse_err(self.pos, "expected object key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            let value = self.value()?;
+            table.insert(key, value);
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Table(table));
+                }
+                _ => return Err(parse_err(self.pos, "expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> io::Result<Value> {
+        self.pos += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(parse_err(self.pos, "expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> io::Result<u32> {
+        let hex = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| parse_err(self.pos, "invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(hex)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        self.pos += 1;
+        let mut bytes: Vec<u8> = Vec::new();
+        loop {
+            let b = match self.bytes.get(self.pos) {
+                Some(b) => *b,
+                None => return Err(parse_err(self.pos, "unterminated string")),
+            };
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self
+                        .bytes
+                        .get(self.pos)
+                        .copied()
+                        .ok_or_else(|| parse_err(self.pos, "unterminated escape"))?;
+                    self.pos += 1;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                self.expect("\\u")?;
+                                let low = self.hex4()?;
+                                0x10000
+                                    + ((high - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF)
+                            } else {
+                                high
+                            };
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        _ => return Err(parse_err(self.pos, "invalid escape")),
+                    };
+                    let mut buf = [0u8; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => bytes.push(b),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| parse_err(self.pos, "invalid utf-8 in string"))
+    }
+
+    fn number(&mut self) -> io::Result<Value> {
+        let start = self.pos;
+        while let Some(b) = self.bytes.get(self.pos) {
+            if b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos])
+            .map_err(|_| parse_err(start, "invalid number"))?;
+        if let Ok(i) = text.parse::<i64>() {
+            return Ok(Value::Integer(i));
+        }
+        text.parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| parse_err(start, "invalid number"))
+    }
+}
+
+pub fn parse(input: &str) -> io::Result<Value> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(parse_err(parser.pos, "trailing characters"));
+    }
+    Ok(value)
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_value(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Integer(i) => out.push_str(&i.to_string()),
+        Value::Float(f) if f.is_finite() => out.push_str(&f.to_string()),
+        Value::Float(_) => out.push_str("null"),
+        Value::String(s) => out.push_str(&escape(s)),
+        Value::Array(values) if values.is_empty() => out.push_str("[]"),
+        Value::Array(values) => {
+            out.push_str("[\n");
+            for (i, value) in values.iter().enumerate() {
+                out.push_str(&pad);
+                write_value(value, indent + 1, out);
+                if i + 1 < values.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push(']');
+        }
+        Value::Table(table) if table.is_empty() => out.push_str("{}"),
+        Value::Table(table) => {
+            out.push_str("{\n");
+            for (i, (key, value)) in table.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&escape(key));
+                out.push_str(": ");
+                write_value(value, indent + 1, out);
+                if i + 1 < table.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push('}');
+        }
+    }
+}
+
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::with_capacity(1024);
+    write_value(value, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let value = parse(r#"{"a": [1, 2.5, true, null], "b": {"c": "d"}}"#).unwrap();
+        assert_eq!(
+            value.get("a").and_then(Value::as_array).unwrap(),
+            &[
+                Value::Integer(1),
+                Value::Float(2.5),
+                Value::Boolean(true),
+                Value::Null
+            ]
+        );
+        assert_eq!(
+            value.get("b").and_then(|b| b.get("c")),
+            Some(&Value::String(String::from("d")))
+        );
+    }
+
+    #[test]
+    fn escaped_quotes_round_trip() {
+        let value = parse(r#"{"q": "say \"hi\" \\ \u00e9\n"}"#).unwrap();
+        assert_eq!(
+            value.get("q").and_then(Value::as_str),
+            Some("say \"hi\" \\ \u{e9}\n")
+        );
+        assert_eq!(parse(&to_string(&value)).unwrap(), value);
+    }
+}
@@ -0,0 +1,73 @@
+pub mod activitypub;
+mod aes_gcm;
+pub mod archive;
+pub mod assets;
+pub mod attachment;
+pub mod authors;
+pub mod cache;
+pub mod checksums;
+pub mod citations;
+pub mod comments;
+pub mod compress;
+pub mod config;
+pub mod content;
+pub mod data;
+pub mod dates;
+pub mod deploy;
+pub mod diagrams;
+pub mod discovery;
+pub mod emoji;
+pub mod error;
+pub mod feed;
+pub mod footnotes;
+pub mod frontmatter;
+pub mod git;
+pub mod highlight;
+pub mod html;
+pub mod i18n;
+pub mod ignore;
+pub mod images;
+mod json;
+pub mod links;
+pub mod lock;
+pub mod manifest;
+pub mod markdown;
+pub mod math;
+pub mod minify;
+pub mod notebook;
+pub mod opengraph;
+pub mod org;
+pub mod parallel;
+pub mod profile;
+pub mod progress;
+pub mod protect;
+pub mod redirects;
+pub mod robots;
+pub mod scaffold;
+pub mod search;
+pub mod series;
+pub mod serve;
+mod sha256;
+pub mod shortcodes;
+pub mod site;
+pub mod sitemap;
+pub mod smartypants;
+pub mod staging;
+pub mod stats;
+pub mod stream;
+pub mod taxonomies;
+pub mod template;
+pub mod toc;
+mod toml;
+mod tools;
+pub mod url;
+pub mod validate;
+pub mod value;
+pub mod watch;
+pub mod webmention;
+mod yaml;
+
+pub use config::Config;
+pub use content::{ContentFormat, ContentMeta, ContentUnit};
+pub use error::WebWeaverError;
+pub use site::Site;
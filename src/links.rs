@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::discovery::ContentFile;
+use crate::tools::spawn_err;
+use crate::{assets, parallel, stream, url};
+
+const ASCIIDOC_MACROS: [&str; 5] = ["xref:", "link:", "image:", "video:", "audio:"];
+const HTML_ATTRIBUTES: [&str; 2] = ["href=\"", "src=\""];
+
+#[derive(Debug)]
+pub struct BrokenLink {
+    pub page: PathBuf,
+    pub target: String,
+}
+
+fn targets<'a>(text: &'a str, markers: &[&str], end: char) -> Vec<&'a str> {
+    let mut targets = Vec::new();
+
+    for marker in markers {
+        for (at, _) in text.match_indices(marker) {
+            let word_start = text[..at]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric());
+            if !word_start {
+                continue;
+            }
+            let rest = &text[at + marker.len()..];
+            let rest = if marker.ends_with(':') {
+                rest.strip_prefix(':').unwrap_or(rest)
+            } else {
+                rest
+            };
+            let target = &rest[..rest.find(end).unwrap_or(rest.len())];
+            if !target.chars().any(char::is_whitespace) {
+                targets.push(target);
+            }
+        }
+    }
+
+    targets
+}
+
+fn page_links(page: &Path) -> io::Result<Vec<String>> {
+    let ext = page
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !matches!(ext.as_str(), "adoc" | "asciidoc" | "html") {
+        return Ok(Vec::new());
+    }
+
+    // A line at a time, so a large page is never held whole.
+    let mut links = Vec::new();
+    for piece in stream::lines(page)? {
+        let piece = piece?;
+        let targets = if ext == "html" {
+            targets(&piece.text, &HTML_ATTRIBUTES, '"')
+        } else {
+            targets(&piece.text, &ASCIIDOC_MACROS, '[')
+        };
+        links.extend(
+            targets
+                .into_iter()
+                .filter(|target| assets::is_local(target))
+                .map(|target| {
+                    let end = target.find(['#', '?']).unwrap_or(target.len());
+                    target[..end].to_string()
+                })
+                .filter(|target| !target.is_empty()),
+        );
+    }
+    Ok(links)
+}
+
+pub fn check_links(cfg: &Config, outputs: &[String]) -> io::Result<Vec<BrokenLink>> {
+    let mut broken = Vec::new();
+
+    for output in outputs {
+        let page = cfg.output_root_path.join(output);
+        if !page.is_file() {
+            continue;
+        }
+        let dir = page.parent().unwrap_or(&cfg.output_root_path);
+        for target in page_links(&page)? {
+            if !dir.join(url::percent_decode(&target)).exists() {
+                broken.push(BrokenLink {
+                    page: page.clone(),
+                    target,
+                });
+            }
+        }
+    }
+
+    Ok(broken)
+}
+
+pub struct ExternalLink {
+    pub path: PathBuf,
+    pub line: usize,
+    pub url: String,
+}
+
+pub enum LinkStatus {
+    Ok,
+    Redirected(u16, String),
+    Dead(String),
+}
+
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32) -> RateLimiter {
+        RateLimiter {
+            interval: Duration::from_secs(1) / rate.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn wait(&self) {
+        let at = {
+            let mut next = self.next.lock().unwrap_or_else(|err| err.into_inner());
+            let at = (*next).max(Instant::now());
+            *next = at + self.interval;
+            at
+        };
+        thread::sleep(at.saturating_duration_since(Instant::now()));
+    }
+}
+
+pub fn line_urls(line: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    for scheme in ["https://", "http://"] {
+        for (at, _) in line.match_indices(scheme) {
+            let rest = &line[at..];
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "[]()<>\"'`\\".contains(c))
+                .unwrap_or(rest.len());
+            let url = rest[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if url.len() > scheme.len() {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+pub fn external_links(content_files: &[ContentFile]) -> io::Result<Vec<ExternalLink>> {
+    let mut links = Vec::new();
+    for content_file in content_files.iter().filter(|file| !file.attachment) {
+        let mut line = 0;
+        for piece in stream::lines(&content_file.path)? {
+            let piece = piece?;
+            if piece.line_start {
+                line += 1;
+            }
+            for url in line_urls(&piece.text) {
+                links.push(ExternalLink {
+                    path: content_file.path.clone(),
+                    line,
+                    url: url.to_string(),
+                });
+            }
+        }
+    }
+    Ok(links)
+}
+
+fn curl(cfg: &Config, url: &str, head: bool) -> io::Result<(u16, String)> {
+    let mut command = Command::new(&cfg.links.curl);
+    command
+        .arg("--silent")
+        .arg("--output")
+        .arg(if cfg!(windows) { "NUL" } else { "/dev/null" })
+        .arg("--write-out")
+        .arg("%{http_code} %{redirect_url}")
+        .arg("--max-time")
+        .arg(cfg.links.timeout_secs.to_string())
+        .arg("--user-agent")
+        .arg("WebWeaver link checker");
+    if head {
+        command.arg("--head");
+    }
+    let output = command
+        .arg(url)
+        .output()
+        .map_err(|err| spawn_err(&cfg.links.curl, "curl", "[links] curl", err))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (code, redirect) = stdout.split_once(' ').unwrap_or((&stdout, ""));
+    Ok((
+        code.trim().parse().unwrap_or(0),
+        redirect.trim().to_string(),
+    ))
+}
+
+fn check_url(cfg: &Config, limiter: &RateLimiter, url: &str) -> io::Result<LinkStatus> {
+    limiter.wait();
+    let (mut code, mut redirect) = curl(cfg, url, true)?;
+    // Some servers refuse HEAD; ask again with GET before calling the link dead.
+    if code == 0 || code == 405 || code == 403 || code == 501 {
+        limiter.wait();
+        (code, redirect) = curl(cfg, url, false)?;
+    }
+
+    Ok(match code {
+        0 => LinkStatus::Dead(String::from("no response")),
+        300..=399 => LinkStatus::Redirected(code, redirect),
+        400.. => LinkStatus::Dead(code.to_string()),
+        _ => LinkStatus::Ok,
+    })
+}
+
+pub fn check_external_links(cfg: &Config, links: &[ExternalLink]) -> io::Result<usize> {
+    let urls: Vec<&str> = links
+        .iter()
+        .map(|link| link.url.as_str())
+        .collect::<BTreeSet<&str>>()
+        .into_iter()
+        .collect();
+    eprintln!("checking {} external links.", urls.len());
+
+    let limiter = RateLimiter::new(cfg.links.rate);
+    let statuses: BTreeMap<&str, LinkStatus> = urls
+        .iter()
+        .copied()
+        .zip(parallel::map(cfg.build.jobs, &urls, |url| {
+            check_url(cfg, &limiter, url)
+        }))
+        .map(|(url, status)| status.map(|status| (url, status)))
+        .collect::<io::Result<BTreeMap<&str, LinkStatus>>>()?;
+
+    let mut dead = 0;
+    for link in links {
+        match &statuses[link.url.as_str()] {
+            LinkStatus::Ok => {}
+            LinkStatus::Redirected(code, target) => println!(
+                "{}:{}: {} {} -> {}",
+                link.path.display(),
+                link.line,
+                code,
+                link.url,
+                target
+            ),
+            LinkStatus::Dead(reason) => {
+                dead += 1;
+                println!(
+                    "{}:{}: {} {}",
+                    link.path.display(),
+                    link.line,
+                    reason,
+                    link.url
+                );
+            }
+        }
+    }
+
+    Ok(dead)
+}
@@ -0,0 +1,78 @@
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+pub const LOCK_FILE: &str = ".webweaver-lock";
+
+// Held for as long as the file stays open; the lock goes with the process, so a crashed build leaves none.
+pub struct OutputLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl Drop for OutputLock {
+    // Removed while still locked, so no build can take a lock on the file as it goes;
+    // where an open file cannot be removed it stays behind, which is harmless.
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Beside the output rather than in it, so an atomic build neither copies the lock nor renames it away.
+fn lock_path(output_root: &Path) -> PathBuf {
+    match output_root.file_name() {
+        Some(name) => {
+            output_root.with_file_name(format!(".{}{}", name.to_string_lossy(), LOCK_FILE))
+        }
+        None => output_root.join(LOCK_FILE),
+    }
+}
+
+// Takes the lock on output_root, or fails at once naming the process that holds it.
+pub fn lock_output(output_root: &Path) -> io::Result<OutputLock> {
+    let path = lock_path(output_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let pid = process::id().to_string();
+    loop {
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => {
+                file.set_len(0)?;
+                writeln!(file, "{}", pid)?;
+                // The build that held the lock removes the file as it ends, maybe after we opened it,
+                // so the lock only counts if the file still there is the one we wrote. Where a locked
+                // file cannot be read, it cannot be removed either.
+                match fs::read_to_string(&path) {
+                    Ok(holder) if holder.trim() != pid => {}
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    _ => return Ok(OutputLock { _file: file, path }),
+                }
+            }
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let holder = match file.read_to_string(&mut pid) {
+                    Ok(_) if !pid.trim().is_empty() => format!("process {}", pid.trim()),
+                    _ => String::from("another process"),
+                };
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!(
+                        "{} is building {}; wait for it to finish, or pass --force.",
+                        holder,
+                        output_root.display()
+                    ),
+                ));
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+use crate::cache::{file_hash, BuildCache};
+use crate::config::Config;
+use crate::content::ContentMeta;
+use crate::json;
+use crate::template::strings;
+use crate::url;
+use crate::value::{Table, Value};
+use crate::ContentUnit;
+
+pub const MANIFEST_FILE: &str = "build-manifest.json";
+const MANIFEST_VERSION: i64 = 1;
+
+fn file_entry(cfg: &Config, output: &str, hash: String) -> Table {
+    let mut entry = Table::new();
+    entry.insert(String::from("output"), Value::String(output.to_string()));
+    entry.insert(
+        String::from("url"),
+        Value::String(url::join(&cfg.site.base_url, output)),
+    );
+    entry.insert(String::from("hash"), Value::String(hash));
+    entry
+}
+
+// The last manifest's entries by output.
+fn previous_files(path: &Path) -> BTreeMap<String, Table> {
+    let manifest = match fs::read_to_string(path).map(|contents| json::parse(&contents)) {
+        Ok(Ok(manifest)) => manifest,
+        _ => return BTreeMap::new(),
+    };
+    manifest
+        .get("files")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|file| match file {
+            Value::Table(file) => {
+                let output = file.get("output")?.as_str()?.to_string();
+                Some((output, file.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Lists every file this build wrote or kept, so a deploy can diff two manifests by hash.
+// A build of one category keeps the last manifest's entries for the other categories.
+pub fn write_manifest(
+    cfg: &Config,
+    cache: &BuildCache,
+    content: &[ContentUnit],
+) -> io::Result<PathBuf> {
+    let metas: BTreeMap<String, &ContentMeta> = content
+        .iter()
+        .map(|unit| (unit.meta.output_path(), &unit.meta))
+        .collect();
+
+    let mut files: BTreeMap<String, Table> = BTreeMap::new();
+    for (source, entry) in cache.entries() {
+        // The cache hashes a page as rendered; minifying rewrites it afterwards.
+        let hash = if cfg.build.minify {
+            match file_hash(&cfg.output_root_path.join(&entry.output)) {
+                Ok(hash) => hash,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            }
+        } else {
+            entry.output_hash.clone()
+        };
+        let mut file = file_entry(cfg, &entry.output, hash);
+        file.insert(String::from("source"), Value::String(source.clone()));
+        if let Some(meta) = metas.get(&entry.output) {
+            file.insert(
+                String::from("date"),
+                Value::String(meta.date.format("%Y-%m-%d").to_string()),
+            );
+            file.insert(String::from("categories"), strings(&meta.categories));
+        }
+        files.insert(entry.output.clone(), file);
+    }
+    for output in cache.generated_outputs() {
+        if output == MANIFEST_FILE || files.contains_key(output) {
+            continue;
+        }
+        let hash = match file_hash(&cfg.output_root_path.join(output)) {
+            Ok(hash) => hash,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        files.insert(output.clone(), file_entry(cfg, output, hash));
+    }
+
+    let path = cfg.output_root_path.join(MANIFEST_FILE);
+    let mut previous = previous_files(&path);
+    for output in cache.other_outputs() {
+        if output == MANIFEST_FILE || files.contains_key(&output) {
+            continue;
+        }
+        if let Some(file) = previous.remove(&output) {
+            files.insert(output, file);
+            continue;
+        }
+        match file_hash(&cfg.output_root_path.join(&output)) {
+            Ok(hash) => {
+                files.insert(output.clone(), file_entry(cfg, &output, hash));
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let mut manifest = Table::new();
+    manifest.insert(String::from("version"), Value::Integer(MANIFEST_VERSION));
+    manifest.insert(
+        String::from("built"),
+        Value::String(Utc::now().to_rfc3339()),
+    );
+    manifest.insert(
+        String::from("base_url"),
+        Value::String(cfg.site.base_url.clone()),
+    );
+    manifest.insert(
+        String::from("files"),
+        Value::Array(files.into_values().map(Value::Table).collect()),
+    );
+
+    fs::write(&path, json::to_string(&Value::Table(manifest)))?;
+    Ok(path)
+}
@@ -0,0 +1,953 @@
+use std::collections::HashMap;
+
+struct LinkReference {
+    url: String,
+    title: Option<String>,
+}
+
+type References = HashMap<String, LinkReference>;
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const INLINE_TAGS: [&str; 9] = [
+    "a", "em", "strong", "code", "del", "span", "sup", "sub", "img",
+];
+
+pub fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut tag: Option<String> = None;
+    for c in html.chars() {
+        match (c, &mut tag) {
+            ('<', None) => tag = Some(String::new()),
+            ('>', Some(name)) => {
+                let name = name.trim_start_matches('/');
+                let name = name.split([' ', '/']).next().unwrap_or("");
+                // Block tags separate words; inline ones sit inside them.
+                if !INLINE_TAGS.contains(&name) {
+                    text.push(' ');
+                }
+                tag = None;
+            }
+            (c, Some(name)) => name.push(c),
+            (c, None) => text.push(c),
+        }
+    }
+    unescape_html(&text)
+}
+
+pub fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn indent_width(line: &str) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+    width
+}
+
+fn dedent(line: &str, width: usize) -> String {
+    let mut removed = 0;
+    let mut chars = line.chars().peekable();
+    while removed < width {
+        match chars.peek() {
+            Some(' ') => removed += 1,
+            Some('\t') => removed += 4 - removed % 4,
+            _ => break,
+        }
+        chars.next();
+    }
+    chars.collect()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn fence_open(line: &str) -> Option<(char, usize, String)> {
+    if indent_width(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next()?;
+    if fence_char != '`' && fence_char != '~' {
+        return None;
+    }
+    let len = trimmed.chars().take_while(|&c| c == fence_char).count();
+    if len < 3 {
+        return None;
+    }
+    let info = trimmed[len..].trim().to_string();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, len, info))
+}
+
+fn fence_close(line: &str, fence_char: char, len: usize) -> bool {
+    let trimmed = line.trim();
+    indent_width(line) <= 3
+        && trimmed.chars().take_while(|&c| c == fence_char).count() >= len
+        && trimmed.chars().all(|c| c == fence_char)
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    if indent_width(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+fn thematic_break(line: &str) -> bool {
+    if indent_width(line) > 3 {
+        return false;
+    }
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ["*", "-", "_"]
+            .iter()
+            .any(|marker| compact.chars().all(|c| c.to_string() == *marker))
+}
+
+fn setext_level(line: &str) -> Option<usize> {
+    if indent_width(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !trimmed.is_empty() && trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+struct ListMarker {
+    ordered: bool,
+    delimiter: char,
+    start: u64,
+    content_offset: usize,
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indent_width(line);
+    if indent > 3 {
+        return None;
+    }
+    let trimmed = line.trim_start();
+    let mut chars = trimmed.chars();
+    let first = chars.next()?;
+
+    let (ordered, delimiter, start, marker_len) = if first == '-' || first == '*' || first == '+' {
+        (false, first, 0, 1)
+    } else if first.is_ascii_digit() {
+        let digits: String = trimmed.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.len() > 9 {
+            return None;
+        }
+        let delimiter = trimmed[digits.len()..].chars().next()?;
+        if delimiter != '.' && delimiter != ')' {
+            return None;
+        }
+        (true, delimiter, digits.parse().ok()?, digits.len() + 1)
+    } else {
+        return None;
+    };
+
+    let after = &trimmed[marker_len..];
+    if !after.is_empty() && !after.starts_with(' ') && !after.starts_with('\t') {
+        return None;
+    }
+    let spaces = after.chars().take_while(|&c| c == ' ').count();
+    let spaces = if spaces == 0 || spaces > 4 || after.trim().is_empty() {
+        1
+    } else {
+        spaces
+    };
+
+    Some(ListMarker {
+        ordered,
+        delimiter,
+        start,
+        content_offset: indent + marker_len + spaces,
+    })
+}
+
+fn html_block_start(line: &str) -> bool {
+    if indent_width(line) > 3 {
+        return false;
+    }
+    let trimmed = line.trim_start();
+    let mut chars = trimmed.chars();
+    if chars.next() != Some('<') {
+        return false;
+    }
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?' => {}
+        _ => return false,
+    }
+    let tag: String = trimmed[1..]
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    tag.is_empty()
+        || matches!(
+            tag.as_str(),
+            "address"
+                | "article"
+                | "aside"
+                | "blockquote"
+                | "details"
+                | "div"
+                | "dl"
+                | "figure"
+                | "footer"
+                | "form"
+                | "h1"
+                | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "header"
+                | "hr"
+                | "iframe"
+                | "nav"
+                | "ol"
+                | "p"
+                | "pre"
+                | "section"
+                | "script"
+                | "style"
+                | "table"
+                | "ul"
+                | "video"
+                | "audio"
+                | "svg"
+        )
+}
+
+fn starts_block(line: &str) -> bool {
+    fence_open(line).is_some()
+        || atx_heading(line).is_some()
+        || thematic_break(line)
+        || line.trim_start().starts_with('>') && indent_width(line) <= 3
+        || html_block_start(line)
+        || list_marker(line)
+            .is_some_and(|marker| !line.trim().is_empty() && (!marker.ordered || marker.start == 1))
+}
+
+fn link_reference_definition(line: &str) -> Option<(String, LinkReference)> {
+    if indent_width(line) > 3 {
+        return None;
+    }
+    let trimmed = line.trim();
+    let rest = trimmed.strip_prefix('[')?;
+    let close = rest.find("]:")?;
+    let label = rest[..close].trim();
+    if label.is_empty() || label.starts_with('^') {
+        return None;
+    }
+    let rest = rest[close + 2..].trim();
+    let (url, title) = match rest.find(char::is_whitespace) {
+        Some(space) => {
+            let title = rest[space..].trim();
+            let title = title
+                .strip_prefix('"')
+                .and_then(|t| t.strip_suffix('"'))
+                .or_else(|| title.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+                .or_else(|| title.strip_prefix('(').and_then(|t| t.strip_suffix(')')))?;
+            (&rest[..space], Some(title.to_string()))
+        }
+        None => (rest, None),
+    };
+    if url.is_empty() {
+        return None;
+    }
+    let url = url
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_string();
+    Some((label.to_lowercase(), LinkReference { url, title }))
+}
+
+fn collect_references(input: &str) -> (Vec<String>, References) {
+    let mut lines = Vec::with_capacity(input.lines().count());
+    let mut references = References::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut previous_blank = true;
+
+    for line in input.lines() {
+        if let Some((fence_char, len)) = fence {
+            if fence_close(line, fence_char, len) {
+                fence = None;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        if let Some((fence_char, len, _)) = fence_open(line) {
+            fence = Some((fence_char, len));
+        } else if previous_blank {
+            if let Some((label, reference)) = link_reference_definition(line) {
+                references.entry(label).or_insert(reference);
+                continue;
+            }
+        }
+        previous_blank = is_blank(line);
+        lines.push(line.to_string());
+    }
+
+    (lines, references)
+}
+
+pub fn to_html(input: &str) -> String {
+    let (lines, references) = collect_references(input);
+    let mut out = String::with_capacity(input.len() * 2);
+    render_blocks(&lines, &references, false, &mut out);
+    out
+}
+
+fn render_blocks(lines: &[String], references: &References, tight: bool, out: &mut String) {
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = &lines[i];
+
+        if is_blank(line) {
+            i += 1;
+            continue;
+        }
+
+        if indent_width(line) >= 4 {
+            let mut code: Vec<String> = Vec::new();
+            while i < lines.len() && (is_blank(&lines[i]) || indent_width(&lines[i]) >= 4) {
+                code.push(dedent(&lines[i], 4));
+                i += 1;
+            }
+            while code.last().is_some_and(|l| is_blank(l)) {
+                code.pop();
+            }
+            out.push_str("<pre><code>");
+            for code_line in code {
+                out.push_str(&escape_html(&code_line));
+                out.push('\n');
+            }
+            out.push_str("</code></pre>\n");
+            continue;
+        }
+
+        if let Some((fence_char, len, info)) = fence_open(line) {
+            let fence_indent = indent_width(line);
+            i += 1;
+            let language = info.split_whitespace().next().unwrap_or("");
+            if language.is_empty() {
+                out.push_str("<pre><code>");
+            } else {
+                out.push_str(&format!(
+                    "<pre><code class=\"language-{}\">",
+                    escape_html(language)
+                ));
+            }
+            while i < lines.len() && !fence_close(&lines[i], fence_char, len) {
+                out.push_str(&escape_html(&dedent(&lines[i], fence_indent)));
+                out.push('\n');
+                i += 1;
+            }
+            i += 1;
+            out.push_str("</code></pre>\n");
+            continue;
+        }
+
+        if let Some((level, text)) = atx_heading(line) {
+            out.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                level,
+                inline(text, references),
+                level
+            ));
+            i += 1;
+            continue;
+        }
+
+        if thematic_break(line) {
+            out.push_str("<hr />\n");
+            i += 1;
+            continue;
+        }
+
+        if line.trim_start().starts_with('>') {
+            let mut quoted: Vec<String> = Vec::new();
+            while i < lines.len() && !is_blank(&lines[i]) {
+                let current = lines[i].trim_start();
+                if let Some(stripped) = current.strip_prefix('>') {
+                    quoted.push(stripped.strip_prefix(' ').unwrap_or(stripped).to_string());
+                } else if starts_block(&lines[i]) {
+                    break;
+                } else {
+                    quoted.push(lines[i].clone());
+                }
+                i += 1;
+            }
+            out.push_str("<blockquote>\n");
+            render_blocks(&quoted, references, false, out);
+            out.push_str("</blockquote>\n");
+            continue;
+        }
+
+        if let Some(marker) = list_marker(line) {
+            i = render_list(lines, i, marker, references, out);
+            continue;
+        }
+
+        if html_block_start(line) {
+            while i < lines.len() && !is_blank(&lines[i]) {
+                out.push_str(&lines[i]);
+                out.push('\n');
+                i += 1;
+            }
+            continue;
+        }
+
+        let mut paragraph: Vec<&str> = vec![line.trim_start()];
+        i += 1;
+        let mut heading_level = None;
+        while i < lines.len() && !is_blank(&lines[i]) {
+            if let Some(level) = setext_level(&lines[i]) {
+                heading_level = Some(level);
+                i += 1;
+                break;
+            }
+            if starts_block(&lines[i]) {
+                break;
+            }
+            paragraph.push(lines[i].trim_start());
+            i += 1;
+        }
+
+        let text = paragraph.join("\n");
+        let text = text.trim_end();
+        match heading_level {
+            Some(level) => out.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                level,
+                inline(text, references),
+                level
+            )),
+            None if tight => {
+                out.push_str(&inline(text, references));
+                out.push('\n');
+            }
+            None => out.push_str(&format!("<p>{}</p>\n", inline(text, references))),
+        }
+    }
+}
+
+fn render_list(
+    lines: &[String],
+    mut i: usize,
+    first: ListMarker,
+    references: &References,
+    out: &mut String,
+) -> usize {
+    let mut items: Vec<Vec<String>> = Vec::new();
+    let mut loose = false;
+    let mut marker = first;
+    let (ordered, delimiter, start) = (marker.ordered, marker.delimiter, marker.start);
+
+    loop {
+        let first_line: String = lines[i]
+            .replace('\t', "    ")
+            .chars()
+            .skip(marker.content_offset)
+            .collect();
+        let mut item: Vec<String> = vec![first_line];
+        i += 1;
+        let mut pending_blank = false;
+
+        while i < lines.len() {
+            let line = &lines[i];
+            if is_blank(line) {
+                pending_blank = true;
+                item.push(String::new());
+                i += 1;
+                continue;
+            }
+            if indent_width(line) >= marker.content_offset {
+                if pending_blank {
+                    loose = true;
+                }
+                pending_blank = false;
+                item.push(dedent(line, marker.content_offset));
+                i += 1;
+                continue;
+            }
+            let sibling = list_marker(line)
+                .is_some_and(|next| next.ordered == ordered && next.delimiter == delimiter);
+            if sibling || pending_blank || starts_block(line) {
+                break;
+            }
+            item.push(line.trim_start().to_string());
+            i += 1;
+        }
+
+        while item.last().is_some_and(|l| l.is_empty()) {
+            item.pop();
+        }
+        items.push(item);
+
+        let next = match lines.get(i).and_then(|line| list_marker(line)) {
+            Some(next) if next.ordered == ordered && next.delimiter == delimiter => next,
+            _ => break,
+        };
+        if pending_blank {
+            loose = true;
+        }
+        marker = next;
+    }
+
+    let tag = if ordered { "ol" } else { "ul" };
+    if ordered && start != 1 {
+        out.push_str(&format!("<ol start=\"{}\">\n", start));
+    } else {
+        out.push_str(&format!("<{}>\n", tag));
+    }
+    for item in items {
+        out.push_str("<li>");
+        let mut rendered = String::new();
+        render_blocks(&item, references, !loose, &mut rendered);
+        let rendered = rendered.trim_end_matches('\n');
+        if !loose && !rendered.contains('\n') {
+            out.push_str(rendered);
+        } else {
+            out.push('\n');
+            out.push_str(rendered);
+            out.push('\n');
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str(&format!("</{}>\n", tag));
+
+    i
+}
+
+fn find_closing_bracket(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut j = open;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 1,
+            '`' => {
+                let run = chars[j..].iter().take_while(|&&c| c == '`').count();
+                if let Some(end) = find_code_span_end(chars, j + run, run) {
+                    j = end + run - 1;
+                }
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    None
+}
+
+fn find_code_span_end(chars: &[char], from: usize, run: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == '`' {
+            let len = chars[j..].iter().take_while(|&&c| c == '`').count();
+            if len == run {
+                return Some(j);
+            }
+            j += len;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+fn link_destination(chars: &[char], open: usize) -> Option<(String, Option<String>, usize)> {
+    let mut depth = 0;
+    let mut j = open;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 1,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    if j >= chars.len() {
+        return None;
+    }
+
+    let inner: String = chars[open + 1..j].iter().collect();
+    let inner = inner.trim();
+    let (url, title) = match inner.find([' ', '\n']) {
+        Some(space) => {
+            let title = inner[space..].trim();
+            let title = title
+                .strip_prefix('"')
+                .and_then(|t| t.strip_suffix('"'))
+                .or_else(|| title.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))?;
+            (&inner[..space], Some(title.to_string()))
+        }
+        None => (inner, None),
+    };
+    let url = url
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_string();
+    Some((url, title, j))
+}
+
+fn flanking_open(chars: &[char], i: usize, run: usize) -> bool {
+    chars.get(i + run).is_some_and(|c| !c.is_whitespace())
+}
+
+fn find_emphasis_close(chars: &[char], from: usize, delimiter: char, run: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 2,
+            '`' => {
+                let len = chars[j..].iter().take_while(|&&c| c == '`').count();
+                j = match find_code_span_end(chars, j + len, len) {
+                    Some(end) => end + len,
+                    None => j + len,
+                };
+            }
+            c if c == delimiter => {
+                let len = chars[j..].iter().take_while(|&&c| c == delimiter).count();
+                let preceded_by_space = j == from || chars[j - 1].is_whitespace();
+                let intraword =
+                    delimiter == '_' && chars.get(j + len).is_some_and(|c| c.is_alphanumeric());
+                if len == run && !preceded_by_space && !intraword {
+                    return Some(j);
+                }
+                j += len;
+            }
+            _ => j += 1,
+        }
+    }
+    None
+}
+
+fn push_link(out: &mut String, url: &str, title: Option<&str>, text: &str) {
+    out.push_str(&format!("<a href=\"{}\"", escape_html(url)));
+    if let Some(title) = title {
+        out.push_str(&format!(" title=\"{}\"", escape_html(title)));
+    }
+    out.push_str(&format!(">{}</a>", text));
+}
+
+fn push_image(out: &mut String, url: &str, title: Option<&str>, alt: &str) {
+    out.push_str(&format!(
+        "<img src=\"{}\" alt=\"{}\"",
+        escape_html(url),
+        escape_html(alt)
+    ));
+    if let Some(title) = title {
+        out.push_str(&format!(" title=\"{}\"", escape_html(title)));
+    }
+    out.push_str(" />");
+}
+
+fn plain_text(chars: &[char]) -> String {
+    chars
+        .iter()
+        .filter(|c| !matches!(c, '*' | '_' | '`'))
+        .collect()
+}
+
+fn inline(text: &str, references: &References) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1) == Some(&'\n') => {
+                out.push_str("<br />\n");
+                i += 2;
+            }
+            '\\' if chars.get(i + 1).is_some_and(|c| c.is_ascii_punctuation()) => {
+                out.push_str(&escape_html(&chars[i + 1].to_string()));
+                i += 2;
+            }
+            '`' => {
+                let run = chars[i..].iter().take_while(|&&c| c == '`').count();
+                match find_code_span_end(&chars, i + run, run) {
+                    Some(end) => {
+                        let code: String = chars[i + run..end].iter().collect();
+                        let code = code.replace('\n', " ");
+                        let code = if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ')
+                        {
+                            &code[1..code.len() - 1]
+                        } else {
+                            &code
+                        };
+                        out.push_str(&format!("<code>{}</code>", escape_html(code)));
+                        i = end + run;
+                    }
+                    None => {
+                        out.push_str(&"`".repeat(run));
+                        i += run;
+                    }
+                }
+            }
+            '*' | '_' | '~' => {
+                let run = chars[i..].iter().take_while(|&&d| d == c).count();
+                let intraword_open = c == '_' && i > 0 && chars[i - 1].is_alphanumeric();
+                let usable = match c {
+                    '~' => run == 2,
+                    _ => run <= 3,
+                };
+                let close = if usable && !intraword_open && flanking_open(&chars, i, run) {
+                    find_emphasis_close(&chars, i + run, c, run)
+                } else {
+                    None
+                };
+                match close {
+                    Some(end) => {
+                        let inner: String = chars[i + run..end].iter().collect();
+                        let inner = inline(&inner, references);
+                        let (open, close) = match (c, run) {
+                            ('~', _) => ("<del>", "</del>"),
+                            (_, 1) => ("<em>", "</em>"),
+                            (_, 2) => ("<strong>", "</strong>"),
+                            _ => ("<em><strong>", "</strong></em>"),
+                        };
+                        out.push_str(open);
+                        out.push_str(&inner);
+                        out.push_str(close);
+                        i = end + run;
+                    }
+                    None => {
+                        for _ in 0..run {
+                            out.push(c);
+                        }
+                        i += run;
+                    }
+                }
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => match inline_link(&chars, i + 1, references) {
+                Some((url, title, label_end, end)) => {
+                    let alt = plain_text(&chars[i + 2..label_end]);
+                    push_image(&mut out, &url, title.as_deref(), &alt);
+                    i = end;
+                }
+                None => {
+                    out.push('!');
+                    i += 1;
+                }
+            },
+            '[' => match inline_link(&chars, i, references) {
+                Some((url, title, label_end, end)) => {
+                    let label: String = chars[i + 1..label_end].iter().collect();
+                    push_link(
+                        &mut out,
+                        &url,
+                        title.as_deref(),
+                        &inline(&label, references),
+                    );
+                    i = end;
+                }
+                None => {
+                    out.push('[');
+                    i += 1;
+                }
+            },
+            '<' => {
+                let rest: String = chars[i + 1..].iter().collect();
+                let end = rest.find('>');
+                let candidate = end.map(|end| &rest[..end]);
+                match candidate {
+                    Some(url)
+                        if (url.starts_with("http://")
+                            || url.starts_with("https://")
+                            || url.starts_with("mailto:"))
+                            && !url.contains(' ') =>
+                    {
+                        push_link(&mut out, url, None, &escape_html(url));
+                        i += url.chars().count() + 2;
+                    }
+                    Some(tag)
+                        if tag.starts_with(|c: char| {
+                            c.is_ascii_alphabetic() || c == '/' || c == '!'
+                        }) =>
+                    {
+                        out.push('<');
+                        out.push_str(tag);
+                        out.push('>');
+                        i += tag.chars().count() + 2;
+                    }
+                    _ => {
+                        out.push_str("&lt;");
+                        i += 1;
+                    }
+                }
+            }
+            '&' => {
+                let entity: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '#')
+                    .collect();
+                if !entity.is_empty() && chars.get(i + 1 + entity.len()) == Some(&';') {
+                    out.push('&');
+                } else {
+                    out.push_str("&amp;");
+                }
+                i += 1;
+            }
+            '>' => {
+                out.push_str("&gt;");
+                i += 1;
+            }
+            '"' => {
+                out.push_str("&quot;");
+                i += 1;
+            }
+            '\n' => {
+                if out.ends_with("  ") {
+                    let trimmed = out.trim_end_matches(' ').len();
+                    out.truncate(trimmed);
+                    out.push_str("<br />\n");
+                } else {
+                    let trimmed = out.trim_end_matches(' ').len();
+                    out.truncate(trimmed);
+                    out.push('\n');
+                }
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+fn inline_link(
+    chars: &[char],
+    open: usize,
+    references: &References,
+) -> Option<(String, Option<String>, usize, usize)> {
+    let label_end = find_closing_bracket(chars, open)?;
+    let label: String = chars[open + 1..label_end].iter().collect();
+
+    match chars.get(label_end + 1) {
+        Some('(') => {
+            let (url, title, end) = link_destination(chars, label_end + 1)?;
+            Some((url, title, label_end, end + 1))
+        }
+        Some('[') => {
+            let ref_end = find_closing_bracket(chars, label_end + 1)?;
+            let reference: String = chars[label_end + 2..ref_end].iter().collect();
+            let key = if reference.trim().is_empty() {
+                label.to_lowercase()
+            } else {
+                reference.to_lowercase()
+            };
+            let found = references.get(key.trim())?;
+            Some((
+                found.url.clone(),
+                found.title.clone(),
+                label_end,
+                ref_end + 1,
+            ))
+        }
+        _ => {
+            let found = references.get(label.trim().to_lowercase().as_str())?;
+            Some((
+                found.url.clone(),
+                found.title.clone(),
+                label_end,
+                label_end + 1,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_headings_emphasis_and_links() {
+        assert_eq!(
+            to_html("# Title\n\nSome *em* and **strong** with [a link](https://x.com).\n"),
+            "<h1>Title</h1>\n<p>Some <em>em</em> and <strong>strong</strong> \
+             with <a href=\"https://x.com\">a link</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn nests_lists_by_indent() {
+        assert_eq!(
+            to_html("- one\n  - nested\n  - again\n- two\n"),
+            "<ul>\n<li>\none\n<ul>\n<li>nested</li>\n<li>again</li>\n</ul>\n</li>\n<li>two</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_markers_in_fenced_code_stay_code() {
+        assert_eq!(
+            to_html("```yaml\n---\ntitle: x\n---\n```\n"),
+            "<pre><code class=\"language-yaml\">---\ntitle: x\n---\n</code></pre>\n"
+        );
+    }
+}
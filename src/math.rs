@@ -0,0 +1,187 @@
+use crate::config::Config;
+use crate::content::ContentMeta;
+use crate::markdown::escape_html;
+
+// Opening and closing delimiters, and whether the formula is set on its own line.
+const DELIMITERS: [(&str, &str, bool); 4] = [
+    ("$$", "$$", true),
+    ("\\[", "\\]", true),
+    ("\\(", "\\)", false),
+    ("$", "$", false),
+];
+
+// Stand in for formulas while the Markdown or Org converter runs, which would read _ and * as emphasis.
+const PLACEHOLDER_START: char = '\u{e000}';
+const PLACEHOLDER_END: char = '\u{e001}';
+
+// A post's math: front matter overrides [math] enabled, which _category.toml may set per category.
+pub fn wants_math(cfg: &Config, meta: &ContentMeta) -> bool {
+    meta.math.unwrap_or(cfg.math.enabled)
+}
+
+// A fence line's closing marker: ``` and ~~~ in Markdown, #+begin_ blocks in Org.
+fn fence_open(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    for fence_char in ['`', '~'] {
+        let run = trimmed.chars().take_while(|c| *c == fence_char).count();
+        if run >= 3 {
+            return Some(fence_char.to_string().repeat(run));
+        }
+    }
+    let lower = trimmed.to_ascii_lowercase();
+    lower
+        .strip_prefix("#+begin_")
+        .map(|kind| format!("#+end_{}", kind.split_whitespace().next().unwrap_or("")))
+}
+
+fn fence_close(line: &str, close: &str) -> bool {
+    let trimmed = line.trim().to_ascii_lowercase();
+    if close.starts_with("#+end_") {
+        return trimmed == close;
+    }
+    trimmed.starts_with(close) && trimmed.trim_start_matches(&close[..1]).is_empty()
+}
+
+// The length of the formula at the start of text, delimiters included.
+fn formula_len(text: &str, open: &str, close: &str) -> Option<usize> {
+    let inner = &text[open.len()..];
+    let end = if open == "$" {
+        // As Pandoc reads $: no space inside the dollars, no digit after them, one line.
+        // Only the next unescaped dollar may close it, so "$5 and $10" stays text.
+        let mut search = 0;
+        let at = loop {
+            let at = search + inner[search..].find('$')?;
+            if !inner[..at].ends_with('\\') {
+                break at;
+            }
+            search = at + 1;
+        };
+        if inner.starts_with(char::is_whitespace)
+            || inner[..at].ends_with(char::is_whitespace)
+            || inner[at + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            return None;
+        }
+        at
+    } else {
+        inner.find(close)?
+    };
+    let formula = &inner[..end];
+    if formula.trim().is_empty()
+        || formula.contains("\n\n")
+        || (open == "$" && formula.contains('\n'))
+    {
+        return None;
+    }
+    Some(open.len() + end + close.len())
+}
+
+// Replaces each formula outside code with a placeholder, returning the formulas in order.
+pub fn protect(text: &str) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(text.len());
+    let mut formulas = Vec::new();
+    let mut fence: Option<String> = None;
+    let mut rest = text;
+    let mut line_start = true;
+    while !rest.is_empty() {
+        if line_start {
+            let line = rest.split_inclusive('\n').next().unwrap_or(rest);
+            let code = match &fence {
+                Some(close) => {
+                    if fence_close(line, close) {
+                        fence = None;
+                    }
+                    true
+                }
+                None => {
+                    fence = fence_open(line);
+                    fence.is_some()
+                }
+            };
+            if code {
+                out.push_str(line);
+                rest = &rest[line.len()..];
+                continue;
+            }
+        }
+
+        if rest.starts_with('`') {
+            let run = rest.chars().take_while(|c| *c == '`').count();
+            let ticks = &rest[..run];
+            let end = rest[run..]
+                .find(ticks)
+                .filter(|end| !rest[run..run + end].contains("\n\n"))
+                .map_or(run, |end| run + end + run);
+            out.push_str(&rest[..end]);
+            line_start = rest[..end].ends_with('\n');
+            rest = &rest[end..];
+            continue;
+        }
+        let formula = DELIMITERS.iter().find_map(|(open, close, display)| {
+            if !rest.starts_with(open) {
+                return None;
+            }
+            let len = formula_len(rest, open, close)?;
+            let tex = &rest[open.len()..len - close.len()];
+            Some((len, tex, *display))
+        });
+        if let Some((len, tex, display)) = formula {
+            out.push(PLACEHOLDER_START);
+            out.push_str(&formulas.len().to_string());
+            out.push(PLACEHOLDER_END);
+            formulas.push(if display {
+                format!(
+                    "<span class=\"math display\">\\[{}\\]</span>",
+                    escape_html(tex.trim())
+                )
+            } else {
+                format!(
+                    "<span class=\"math inline\">\\({}\\)</span>",
+                    escape_html(tex)
+                )
+            });
+            line_start = false;
+            rest = &rest[len..];
+            continue;
+        }
+        // An escaped dollar is left for the converter to unescape.
+        let len = if rest.starts_with("\\$") {
+            2
+        } else {
+            rest.chars().next().map_or(1, char::len_utf8)
+        };
+        out.push_str(&rest[..len]);
+        line_start = rest[..len].ends_with('\n');
+        rest = &rest[len..];
+    }
+    (out, formulas)
+}
+
+// Puts the formulas back into the converted HTML, each in a math span KaTeX renders.
+pub fn restore(html: &str, formulas: &[String]) -> String {
+    if formulas.is_empty() {
+        return html.to_string();
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + PLACEHOLDER_START.len_utf8()..];
+        let formula = after.split_once(PLACEHOLDER_END).and_then(|(index, tail)| {
+            let formula = formulas.get(index.parse::<usize>().ok()?)?;
+            Some((formula, tail))
+        });
+        match formula {
+            Some((formula, tail)) => {
+                out.push_str(formula);
+                rest = tail;
+            }
+            None => {
+                out.push(PLACEHOLDER_START);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::io;
+
+use crate::config::Config;
+
+// Whitespace inside these elements is part of the content.
+const RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+// The length of the tag at the start of s, up to and including its '>', with quoted values skipped.
+fn tag_len(s: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+// Up to and including end, or the rest of s when it never comes.
+fn through(s: &str, end: &str) -> usize {
+    s.find(end).map_or(s.len(), |at| at + end.len())
+}
+
+fn raw_element(s: &str) -> Option<&'static str> {
+    let name: String = s[1..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    RAW_ELEMENTS.into_iter().find(|raw| *raw == name)
+}
+
+// Drops comments and collapses each run of whitespace between and around tags to one space or newline.
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("<!--[if") {
+            let len = through(rest, "-->");
+            out.push_str(&rest[..len]);
+            len
+        } else if rest.starts_with("<!--") {
+            through(rest, "-->")
+        } else if c == '<' {
+            let len = match raw_element(rest) {
+                Some(name) => {
+                    let open = tag_len(rest);
+                    let close = format!("</{}", name);
+                    let body = rest[open..].to_ascii_lowercase();
+                    match body.find(&close) {
+                        Some(at) => open + at + tag_len(&rest[open + at..]),
+                        None => rest.len(),
+                    }
+                }
+                None => tag_len(rest),
+            };
+            out.push_str(&rest[..len]);
+            len
+        } else if c.is_ascii_whitespace() {
+            let run = rest
+                .find(|c: char| !c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            // A dropped comment leaves the whitespace on both sides of it next to each other.
+            if !out.ends_with(|c: char| c.is_ascii_whitespace()) {
+                out.push(if rest[..run].contains('\n') {
+                    '\n'
+                } else {
+                    ' '
+                });
+            }
+            run
+        } else {
+            out.push(c);
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+
+    out
+}
+
+// Drops comments and the whitespace-only text between elements; text and CDATA stay as they are.
+pub fn minify_xml(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let len = if rest.starts_with("<![CDATA[") {
+            let len = through(rest, "]]>");
+            out.push_str(&rest[..len]);
+            len
+        } else if rest.starts_with("<!--") {
+            through(rest, "-->")
+        } else if rest.starts_with('<') {
+            let len = tag_len(rest);
+            out.push_str(&rest[..len]);
+            len
+        } else {
+            let len = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..len];
+            if !text.trim().is_empty() {
+                out.push_str(text);
+            }
+            len
+        };
+        rest = &rest[len..];
+    }
+
+    out
+}
+
+// Rewrites the HTML and XML outputs that shrink; returns how many files and bytes that saved.
+pub fn minify_outputs(cfg: &Config, outputs: &[String]) -> io::Result<(usize, usize)> {
+    let (mut files, mut saved) = (0, 0);
+    for output in outputs {
+        let minify: fn(&str) -> String = match output.rsplit_once('.') {
+            Some((_, "html" | "htm")) => minify_html,
+            Some((_, "xml")) => minify_xml,
+            _ => continue,
+        };
+        let path = cfg.output_root_path.join(output);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        let minified = minify(&text);
+        if minified.len() < text.len() {
+            fs::write(&path, &minified)?;
+            files += 1;
+            saved += text.len() - minified.len();
+        }
+    }
+    Ok((files, saved))
+}
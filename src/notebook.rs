@@ -0,0 +1,205 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::json;
+use crate::value::Value;
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+fn notebook_err(message: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("not a Jupyter notebook: {}", message.to_string()),
+    )
+}
+
+// Cell sources and outputs are a string or a list of lines.
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+// A fence longer than any backtick run that starts a line of the text.
+fn fenced(text: &str, info: &str) -> String {
+    let longest = text
+        .lines()
+        .map(|line| line.trim_start().chars().take_while(|c| *c == '`').count())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        info,
+        text.trim_end_matches('\n'),
+        fence
+    )
+}
+
+// Markdown ends an HTML block at a blank line, so the block has none.
+fn html_block(html: &str) -> String {
+    let lines: Vec<&str> = html
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    format!("<div class=\"output\">\n{}\n</div>\n\n", lines.join("\n"))
+}
+
+// Tracebacks come with terminal colours.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+// The richest form of a display or result output the page can show.
+fn display_data(data: &Value) -> String {
+    for mime_type in ["image/png", "image/jpeg", "image/gif"] {
+        if let Some(image) = data.get(mime_type) {
+            let base64: String = text(Some(image)).split_whitespace().collect();
+            return html_block(&format!(
+                "<img src=\"data:{};base64,{}\" alt=\"\">",
+                mime_type, base64
+            ));
+        }
+    }
+    if let Some(svg) = data.get("image/svg+xml") {
+        return html_block(&text(Some(svg)));
+    }
+    if let Some(html) = data.get("text/html") {
+        return html_block(&text(Some(html)));
+    }
+    if let Some(markdown) = data.get("text/markdown") {
+        return format!("{}\n\n", text(Some(markdown)).trim_end());
+    }
+    match data.get("text/plain") {
+        Some(plain) => fenced(&text(Some(plain)), "output"),
+        None => String::new(),
+    }
+}
+
+fn output(output: &Value) -> String {
+    match output.get("output_type").and_then(Value::as_str) {
+        Some("stream") => fenced(&text(output.get("text")), "output"),
+        Some("execute_result" | "display_data") => match output.get("data") {
+            Some(data) => display_data(data),
+            None => String::new(),
+        },
+        Some("error") => {
+            let traceback = match output.get("traceback").and_then(Value::as_array) {
+                Some(lines) => lines
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<&str>>()
+                    .join("\n"),
+                None => format!(
+                    "{}: {}",
+                    text(output.get("ename")),
+                    text(output.get("evalue"))
+                ),
+            };
+            fenced(&strip_ansi(&traceback), "output")
+        }
+        _ => String::new(),
+    }
+}
+
+// Images pasted into a Markdown cell are kept with it as attachment:name.png.
+fn with_attachments(source: String, cell: &Value) -> String {
+    let attachments = match cell.get("attachments") {
+        Some(Value::Table(attachments)) => attachments,
+        _ => return source,
+    };
+    let mut source = source;
+    for (name, bundle) in attachments {
+        let data = match bundle {
+            Value::Table(bundle) => bundle.iter().next(),
+            _ => None,
+        };
+        if let Some((mime_type, data)) = data {
+            let base64: String = text(Some(data)).split_whitespace().collect();
+            source = source.replace(
+                &format!("attachment:{}", name),
+                &format!("data:{};base64,{}", mime_type, base64),
+            );
+        }
+    }
+    source
+}
+
+// The notebook as Markdown: its Markdown and raw cells as they are, code cells fenced in the
+// kernel's language, and their outputs after them. Front matter goes in a first raw or Markdown cell.
+pub fn to_markdown(source: &str) -> io::Result<String> {
+    let notebook = json::parse(source).map_err(notebook_err)?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| notebook_err("it has no cells."))?;
+    let metadata = notebook.get("metadata");
+    let language = metadata
+        .and_then(|metadata| metadata.get("language_info"))
+        .and_then(|info| info.get("name"))
+        .or_else(|| {
+            metadata
+                .and_then(|metadata| metadata.get("kernelspec"))
+                .and_then(|kernel| kernel.get("language"))
+        })
+        .and_then(Value::as_str)
+        .unwrap_or("python");
+
+    let mut markdown = String::new();
+    for cell in cells {
+        let source = text(cell.get("source"));
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => {
+                markdown.push_str(with_attachments(source, cell).trim_end());
+                markdown.push_str("\n\n");
+            }
+            Some("code") => {
+                if !source.trim().is_empty() {
+                    markdown.push_str(&fenced(&source, language));
+                }
+                for output_value in cell
+                    .get("outputs")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    markdown.push_str(&output(output_value));
+                }
+            }
+            _ => {
+                markdown.push_str(source.trim_end());
+                markdown.push_str("\n\n");
+            }
+        }
+    }
+    Ok(markdown)
+}
+
+// A content file's text; a notebook's is its Markdown.
+pub fn read_source(path: &Path) -> io::Result<String> {
+    let source = fs::read_to_string(path)?;
+    if is_notebook(path) {
+        return to_markdown(&source);
+    }
+    Ok(source)
+}
@@ -0,0 +1,174 @@
+use crate::config::Config;
+use crate::content::{meta_context, ContentFormat, ContentMeta};
+use crate::feed::canonical_url;
+use crate::i18n::default_language;
+use crate::json;
+use crate::template::{context, context_value, optional, strings, Templates};
+use crate::url;
+use crate::value::Value;
+
+fn image_url(cfg: &Config, image: &str) -> String {
+    if url::is_absolute(image) {
+        image.to_string()
+    } else {
+        url::join(&cfg.site.base_url, image)
+    }
+}
+
+// The tags end up in HTML, so an AsciiDoc page is linked by its rendered .html.
+pub fn html_path(path: &str, format: ContentFormat) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, _)) if format == ContentFormat::AsciiDoc => format!("{}.html", stem),
+        _ => path.to_string(),
+    }
+}
+
+pub fn html_url(cfg: &Config, path: &str, format: ContentFormat) -> String {
+    url::join(&cfg.site.base_url, &html_path(path, format))
+}
+
+fn alternates(cfg: &Config, meta: &ContentMeta) -> Value {
+    if meta.translations.is_empty() {
+        return Value::Array(Vec::new());
+    }
+    let own = (
+        meta.language
+            .clone()
+            .unwrap_or_else(|| default_language(cfg)),
+        meta.output_path(),
+        meta.format,
+    );
+    let translations = meta.translations.iter().map(|translation| {
+        (
+            translation.language.clone(),
+            translation.path.clone(),
+            translation.format,
+        )
+    });
+    Value::Array(
+        std::iter::once(own)
+            .chain(translations)
+            .map(|(language, path, format)| {
+                context_value([
+                    ("language", Value::String(language)),
+                    ("url", Value::String(html_url(cfg, &path, format))),
+                ])
+            })
+            .collect(),
+    )
+}
+
+// schema.org BlogPosting data, or WebPage data without a publication date for a page,
+// with the post's jsonld front matter merged over it.
+fn jsonld(
+    cfg: &Config,
+    meta: &ContentMeta,
+    description: Option<&str>,
+    image: Option<&str>,
+) -> Option<String> {
+    let overrides = meta.jsonld.as_ref().filter(|_| cfg.site.jsonld)?;
+    let url = canonical_url(cfg, meta);
+    let date = meta.date.format("%Y-%m-%d").to_string();
+    let mut data = context([
+        (
+            "@context",
+            Value::String(String::from("https://schema.org")),
+        ),
+        (
+            "@type",
+            Value::String(String::from(if meta.page {
+                "WebPage"
+            } else {
+                "BlogPosting"
+            })),
+        ),
+        ("headline", Value::String(meta.name.clone())),
+        ("url", Value::String(url.clone())),
+        ("mainEntityOfPage", Value::String(url)),
+        ("wordCount", Value::Integer(meta.words as i64)),
+        (
+            "publisher",
+            context_value([
+                ("@type", Value::String(String::from("Organization"))),
+                ("name", Value::String(cfg.site.title.clone())),
+            ]),
+        ),
+    ]);
+    // A page's date comes from its file unless front matter sets one, so it claims none.
+    if !meta.page {
+        data.insert(String::from("datePublished"), Value::String(date.clone()));
+    }
+    if let Some(modified) = meta
+        .updated
+        .map(|updated| updated.format("%Y-%m-%d").to_string())
+    {
+        data.insert(String::from("dateModified"), Value::String(modified));
+    } else if !meta.page {
+        data.insert(String::from("dateModified"), Value::String(date));
+    }
+    if let Some(author) = meta.author.as_ref().or(cfg.author.as_ref()) {
+        data.insert(
+            String::from("author"),
+            context_value([
+                ("@type", Value::String(String::from("Person"))),
+                ("name", Value::String(author.clone())),
+            ]),
+        );
+    }
+    if !meta.tags.is_empty() {
+        data.insert(String::from("keywords"), strings(&meta.tags));
+    }
+    for (key, value) in [("description", description), ("image", image)] {
+        if let Some(value) = value {
+            data.insert(String::from(key), Value::String(value.to_string()));
+        }
+    }
+    if let Some(language) = meta.language.as_ref().or(cfg.site.language.as_ref()) {
+        data.insert(String::from("inLanguage"), Value::String(language.clone()));
+    }
+    data.extend(overrides.clone());
+    // "</" would end the script element early.
+    Some(json::to_string(&Value::Table(data)).replace("</", "<\\/"))
+}
+
+pub fn opengraph_tags(
+    cfg: &Config,
+    meta: &ContentMeta,
+    excerpt: &str,
+    templates: &Templates,
+) -> String {
+    let description = meta
+        .summary
+        .as_ref()
+        .map(|summary| summary.replace('\n', " "))
+        .or_else(|| Some(excerpt.to_string()).filter(|excerpt| !excerpt.is_empty()));
+    let image = meta
+        .image
+        .as_deref()
+        .or(cfg.site.image.as_deref())
+        .map(|image| image_url(cfg, image));
+
+    let mut context = meta_context(meta);
+    context.insert(
+        String::from("site_name"),
+        Value::String(cfg.site.title.clone()),
+    );
+    context.insert(String::from("url"), Value::String(canonical_url(cfg, meta)));
+    context.insert(String::from("page"), Value::Boolean(meta.page));
+    context.insert(String::from("alternates"), alternates(cfg, meta));
+    context.insert(
+        String::from("description"),
+        optional(description.as_deref()),
+    );
+    context.insert(String::from("image"), optional(image.as_deref()));
+    context.insert(
+        String::from("jsonld"),
+        optional(jsonld(cfg, meta, description.as_deref(), image.as_deref()).as_deref()),
+    );
+    context.insert(
+        String::from("twitter"),
+        optional(cfg.site.twitter.as_deref()),
+    );
+
+    templates.opengraph.render(&context)
+}
@@ -0,0 +1,429 @@
+use crate::markdown::escape_html;
+
+const IMAGE_EXTS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "avif"];
+const EMPHASIS: [(char, &str); 6] = [
+    ('*', "strong"),
+    ('/', "em"),
+    ('_', "u"),
+    ('+', "del"),
+    ('=', "code"),
+    ('~', "code"),
+];
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars().take_while(|c| *c == ' ').count()
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    if level == 0 || !line[level..].starts_with(' ') {
+        return None;
+    }
+    let text = line[level..].trim();
+    // Drop trailing :tag1:tag2: heading tags.
+    let text = match text.rsplit_once(' ') {
+        Some((title, tags)) if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') => {
+            title.trim_end()
+        }
+        _ => text,
+    };
+    Some((level, text))
+}
+
+fn block_begin(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim();
+    let rest = trimmed
+        .get(..8)
+        .filter(|start| start.eq_ignore_ascii_case("#+begin_"))?;
+    let rest = &trimmed[rest.len()..];
+    let (name, args) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((name.to_ascii_lowercase(), args.trim()))
+}
+
+fn is_block_end(line: &str, name: &str) -> bool {
+    line.trim()
+        .to_ascii_lowercase()
+        .strip_prefix("#+end_")
+        .is_some_and(|end| end == name)
+}
+
+fn list_marker(line: &str) -> Option<(bool, usize)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    if ["- ", "+ "]
+        .iter()
+        .any(|marker| trimmed.starts_with(marker))
+        || trimmed == "-"
+    {
+        return Some((false, indent + 2));
+    }
+    // A bullet star needs indentation, or it would be a heading.
+    if indent > 0 && trimmed.starts_with("* ") {
+        return Some((false, indent + 2));
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") ")) {
+        return Some((true, indent + digits + 2));
+    }
+    None
+}
+
+fn is_rule(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-')
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn is_keyword(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("#+") || trimmed == "#" || trimmed.starts_with("# ")
+}
+
+fn is_image(target: &str) -> bool {
+    target
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| IMAGE_EXTS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn link_target(target: &str) -> &str {
+    target.strip_prefix("file:").unwrap_or(target)
+}
+
+fn emphasis_close(chars: &[char], from: usize, marker: char) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == '\n' && chars.get(j + 1) == Some(&'\n') {
+            return None;
+        }
+        let followed = chars
+            .get(j + 1)
+            .is_none_or(|c| c.is_whitespace() || "-.,:!?;'\")}[".contains(*c));
+        if chars[j] == marker && j > from && !chars[j - 1].is_whitespace() && followed {
+            return Some(j);
+        }
+        j += 1;
+    }
+    None
+}
+
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '[' && chars.get(i + 1) == Some(&'[') {
+            let rest: String = chars[i + 2..].iter().collect();
+            if let Some(end) = rest.find("]]") {
+                let inner = &rest[..end];
+                let (target, description) = match inner.split_once("][") {
+                    Some((target, description)) => (link_target(target), Some(description)),
+                    None => (link_target(inner), None),
+                };
+                match description {
+                    None if is_image(target) => {
+                        out.push_str(&format!("<img src=\"{}\" alt=\"\" />", escape_html(target)))
+                    }
+                    Some(description) if is_image(link_target(description)) => {
+                        out.push_str(&format!(
+                            "<a href=\"{}\"><img src=\"{}\" alt=\"\" /></a>",
+                            escape_html(target),
+                            escape_html(link_target(description))
+                        ))
+                    }
+                    _ => out.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(target),
+                        inline(description.unwrap_or(target))
+                    )),
+                }
+                i += 2 + inner.chars().count() + 2;
+                continue;
+            }
+        }
+
+        if c == '\\'
+            && chars.get(i + 1) == Some(&'\\')
+            && chars.get(i + 2).is_none_or(|c| *c == '\n')
+        {
+            out.push_str("<br />");
+            i += 2;
+            continue;
+        }
+
+        let opens = i == 0 || chars[i - 1].is_whitespace() || "-({'\"".contains(chars[i - 1]);
+        if let Some((marker, tag)) = EMPHASIS.iter().find(|(marker, _)| *marker == c) {
+            let starts_word = chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+            if opens && starts_word {
+                if let Some(close) = emphasis_close(&chars, i + 1, *marker) {
+                    let inner: String = chars[i + 1..close].iter().collect();
+                    let inner = if *tag == "code" {
+                        escape_html(&inner)
+                    } else {
+                        inline(&inner)
+                    };
+                    out.push_str(&format!("<{}>{}</{}>", tag, inner, tag));
+                    i = close + 1;
+                    continue;
+                }
+            }
+        }
+
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+        i += 1;
+    }
+
+    out
+}
+
+fn table(rows: &[&str], out: &mut String) {
+    let cells = |row: &str| -> Vec<String> {
+        row.trim()
+            .trim_matches('|')
+            .split('|')
+            .map(|cell| inline(cell.trim()))
+            .collect()
+    };
+    let is_separator = |row: &str| row.trim_start().starts_with("|-");
+    let header = rows.iter().position(|row| is_separator(row)) == Some(1);
+
+    out.push_str("<table>\n");
+    for (i, row) in rows.iter().filter(|row| !is_separator(row)).enumerate() {
+        let tag = if header && i == 0 { "th" } else { "td" };
+        out.push_str("<tr>");
+        for cell in cells(row) {
+            out.push_str(&format!("<{}>{}</{}>", tag, cell, tag));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn list(lines: &[&str], ordered: bool, out: &mut String) {
+    let tag = if ordered { "ol" } else { "ul" };
+    let indent = indent_width(lines[0]);
+    out.push_str(&format!("<{}>\n", tag));
+
+    let mut i = 0;
+    while i < lines.len() {
+        let (_, width) = list_marker(lines[i]).unwrap_or((ordered, indent + 2));
+        let mut item = vec![lines[i].get(width..).unwrap_or("").to_string()];
+        i += 1;
+        while i < lines.len() && (is_blank(lines[i]) || indent_width(lines[i]) > indent) {
+            item.push(
+                lines[i]
+                    .get(width.min(indent_width(lines[i]))..)
+                    .unwrap_or("")
+                    .to_string(),
+            );
+            i += 1;
+        }
+        while item.last().is_some_and(|line| is_blank(line)) {
+            item.pop();
+        }
+
+        let item: Vec<&str> = item.iter().map(String::as_str).collect();
+        let mut body = String::new();
+        render_blocks(&item, &mut body);
+        // A single paragraph item reads better without the <p>.
+        let body = match body
+            .strip_prefix("<p>")
+            .and_then(|body| body.strip_suffix("</p>\n"))
+        {
+            Some(text) if !text.contains("<p>") => text.to_string(),
+            _ => body,
+        };
+        out.push_str(&format!("<li>{}</li>\n", body.trim_end()));
+    }
+
+    out.push_str(&format!("</{}>\n", tag));
+}
+
+fn render_blocks(lines: &[&str], out: &mut String) {
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if is_blank(line) {
+            i += 1;
+            continue;
+        }
+
+        if let Some((name, args)) = block_begin(line) {
+            let start = i + 1;
+            let mut end = start;
+            while end < lines.len() && !is_block_end(lines[end], &name) {
+                end += 1;
+            }
+            let body = &lines[start..end];
+            match name.as_str() {
+                "quote" => {
+                    out.push_str("<blockquote>\n");
+                    render_blocks(body, out);
+                    out.push_str("</blockquote>\n");
+                }
+                "verse" => {
+                    let verse: Vec<String> = body.iter().map(|line| inline(line.trim())).collect();
+                    out.push_str(&format!(
+                        "<p class=\"verse\">{}</p>\n",
+                        verse.join("<br />\n")
+                    ));
+                }
+                "center" => {
+                    out.push_str("<div class=\"center\">\n");
+                    render_blocks(body, out);
+                    out.push_str("</div>\n");
+                }
+                "comment" => {}
+                _ => {
+                    let language = args.split_whitespace().next().filter(|_| name == "src");
+                    match language {
+                        Some(language) => out.push_str(&format!(
+                            "<pre><code class=\"language-{}\">",
+                            escape_html(language)
+                        )),
+                        None => out.push_str("<pre><code>"),
+                    }
+                    for line in body {
+                        out.push_str(&escape_html(line));
+                        out.push('\n');
+                    }
+                    out.push_str("</code></pre>\n");
+                }
+            }
+            i = end + 1;
+            continue;
+        }
+
+        if is_keyword(line) {
+            i += 1;
+            continue;
+        }
+
+        if let Some((level, text)) = heading(line) {
+            // The page title is the h2, so top-level Org headings start at h3.
+            let level = (level + 2).min(6);
+            out.push_str(&format!("<h{}>{}</h{}>\n", level, inline(text), level));
+            i += 1;
+            continue;
+        }
+
+        if is_rule(line) {
+            out.push_str("<hr />\n");
+            i += 1;
+            continue;
+        }
+
+        if line.trim_start().starts_with(": ") || line.trim() == ":" {
+            out.push_str("<pre><code>");
+            while i < lines.len()
+                && (lines[i].trim_start().starts_with(": ") || lines[i].trim() == ":")
+            {
+                let text = lines[i].trim_start().trim_start_matches(':');
+                out.push_str(&escape_html(text.strip_prefix(' ').unwrap_or(text)));
+                out.push('\n');
+                i += 1;
+            }
+            out.push_str("</code></pre>\n");
+            continue;
+        }
+
+        if is_table_row(line) {
+            let start = i;
+            while i < lines.len() && is_table_row(lines[i]) {
+                i += 1;
+            }
+            table(&lines[start..i], out);
+            continue;
+        }
+
+        if let Some((ordered, _)) = list_marker(line) {
+            let indent = indent_width(line);
+            let start = i;
+            i += 1;
+            while i < lines.len() {
+                let next = lines[i];
+                let continues = !is_blank(next) && indent_width(next) > indent;
+                let sibling = indent_width(next) == indent
+                    && list_marker(next).is_some_and(|(next_ordered, _)| next_ordered == ordered);
+                let blank_inside = is_blank(next)
+                    && lines.get(i + 1).is_some_and(|after| {
+                        indent_width(after) > indent
+                            || (indent_width(after) == indent && list_marker(after).is_some())
+                    });
+                if !(continues || sibling || blank_inside) {
+                    break;
+                }
+                i += 1;
+            }
+            list(&lines[start..i], ordered, out);
+            continue;
+        }
+
+        let start = i;
+        while i < lines.len()
+            && !is_blank(lines[i])
+            && heading(lines[i]).is_none()
+            && block_begin(lines[i]).is_none()
+            && !is_keyword(lines[i])
+            && (i == start || list_marker(lines[i]).is_none())
+            && !is_table_row(lines[i])
+        {
+            i += 1;
+        }
+        let text: Vec<&str> = lines[start..i].iter().map(|line| line.trim()).collect();
+        out.push_str(&format!("<p>{}</p>\n", inline(&text.join("\n"))));
+    }
+}
+
+pub fn to_html(input: &str) -> String {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut out = String::with_capacity(input.len() * 2);
+    render_blocks(&lines, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_headings_and_emphasis() {
+        assert_eq!(
+            to_html("* Heading\nSome /em/ and *bold* text.\n"),
+            "<h3>Heading</h3>\n<p>Some <em>em</em> and <strong>bold</strong> text.</p>\n"
+        );
+    }
+
+    #[test]
+    fn nests_lists_by_indent() {
+        assert_eq!(
+            to_html("- one\n  - nested\n- two\n"),
+            "<ul>\n<li><p>one</p>\n<ul>\n<li>nested</li>\n</ul></li>\n<li>two</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_markers_in_source_blocks_stay_code() {
+        assert_eq!(
+            to_html("#+BEGIN_SRC yaml\n---\n#+END_SRC\n"),
+            "<pre><code class=\"language-yaml\">---\n</code></pre>\n"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::json;
+use crate::progress::{peak_memory, FileTiming, Timings};
+use crate::url;
+use crate::value::{Table, Value};
+
+pub const PROFILE_FILE: &str = ".webweaver-profile.json";
+pub const FOLDED_FILE: &str = ".webweaver-profile.folded";
+
+fn millis(duration: Duration) -> Value {
+    Value::Float((duration.as_secs_f64() * 1_000_000.0).round() / 1000.0)
+}
+
+fn optional_kb(kb: Option<u64>) -> Value {
+    match kb {
+        Some(kb) => Value::Integer(kb as i64),
+        None => Value::Null,
+    }
+}
+
+// Paths below the input, as written in the report.
+fn relative(cfg: &Config, path: &Path) -> String {
+    url::from_path(
+        path.strip_prefix(&cfg.input_content_root_path)
+            .or_else(|_| path.strip_prefix(&cfg.output_root_path))
+            .unwrap_or(path),
+    )
+}
+
+fn profile_json(cfg: &Config, timings: &Timings, files: &[FileTiming]) -> Value {
+    let stages = timings
+        .stages()
+        .iter()
+        .map(|(name, duration)| {
+            let mut stage = Table::new();
+            stage.insert(String::from("name"), Value::String(name.to_string()));
+            stage.insert(String::from("ms"), millis(*duration));
+            Value::Table(stage)
+        })
+        .collect();
+    let files = files
+        .iter()
+        .map(|file| {
+            let mut entry = Table::new();
+            entry.insert(
+                String::from("path"),
+                Value::String(relative(cfg, &file.path)),
+            );
+            entry.insert(String::from("stage"), Value::String(file.stage.to_string()));
+            entry.insert(String::from("ms"), millis(file.duration));
+            Value::Table(entry)
+        })
+        .collect();
+
+    let mut profile = Table::new();
+    profile.insert(String::from("ms"), millis(timings.elapsed()));
+    profile.insert(String::from("jobs"), Value::Integer(cfg.build.jobs as i64));
+    profile.insert(String::from("peak_memory_kb"), optional_kb(peak_memory()));
+    profile.insert(String::from("stages"), Value::Array(stages));
+    profile.insert(String::from("files"), Value::Array(files));
+    Value::Table(profile)
+}
+
+// One "build;stage;file microseconds" line per file, and per stage for the time outside its files,
+// as flamegraph.pl and inferno-flamegraph read them.
+fn folded(cfg: &Config, timings: &Timings, files: &[FileTiming]) -> String {
+    let mut lines = Vec::new();
+    for (name, duration) in timings.stages() {
+        let mut rest = duration.as_micros();
+        for file in files.iter().filter(|file| file.stage == *name) {
+            let micros = file.duration.as_micros();
+            rest = rest.saturating_sub(micros);
+            let path = relative(cfg, &file.path).replace([';', ' '], "_");
+            lines.push(format!("build;{};{} {}", name, path, micros));
+        }
+        lines.push(format!("build;{} {}", name, rest));
+    }
+    lines.join("\n") + "\n"
+}
+
+// Writes the --profile-build report next to the build cache and prints the slowest files;
+// output_root is where the report is published, as an atomic build writes it to staging first.
+pub fn write_profile(
+    cfg: &Config,
+    timings: &mut Timings,
+    output_root: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    let mut files = timings.take_files();
+    files.sort_by_key(|file| Reverse(file.duration));
+
+    let json_path = cfg.output_root_path.join(PROFILE_FILE);
+    fs::write(
+        &json_path,
+        json::to_string(&profile_json(cfg, timings, &files)),
+    )?;
+    let folded_path = cfg.output_root_path.join(FOLDED_FILE);
+    fs::write(&folded_path, folded(cfg, timings, &files))?;
+
+    eprintln!(
+        "wrote build profile to {}{}.",
+        output_root.join(PROFILE_FILE).display(),
+        match peak_memory() {
+            Some(kb) => format!("; peak memory {} MB", kb / 1024),
+            None => String::new(),
+        }
+    );
+    for file in files.iter().take(5) {
+        eprintln!(
+            "  {:.1?} {} {}",
+            file.duration,
+            file.stage,
+            relative(cfg, &file.path)
+        );
+    }
+    Ok(vec![json_path, folded_path])
+}
@@ -0,0 +1,157 @@
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+const BAR_WIDTH: usize = 30;
+
+// A progress bar on stderr, drawn only when stderr is a terminal; safe to tick from worker threads.
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: AtomicUsize,
+    shown: bool,
+}
+
+impl Progress {
+    pub fn new(cfg: &Config, label: &'static str, total: usize) -> Progress {
+        Progress {
+            label,
+            total,
+            done: AtomicUsize::new(0),
+            shown: cfg.build.progress && total > 0 && io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.shown || done > self.total {
+            return;
+        }
+        // Redrawn only when the bar grows, and at the end.
+        let filled = done * BAR_WIDTH / self.total;
+        if filled == (done - 1) * BAR_WIDTH / self.total && done < self.total {
+            return;
+        }
+        eprint!(
+            "\r{:<8} [{:<width$}] {}/{}",
+            self.label,
+            "#".repeat(filled),
+            done,
+            self.total,
+            width = BAR_WIDTH
+        );
+    }
+
+    pub fn finish(&self) {
+        if self.shown {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+pub struct FileTiming {
+    pub stage: &'static str,
+    pub path: PathBuf,
+    pub duration: Duration,
+}
+
+// The process's resident memory high-water mark, where /proc has it.
+pub fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+// Wall-clock time per build stage; a stage entered again, as with --all, adds to its total.
+// With --profile-build it also records the time each file took.
+pub struct Timings {
+    started: Instant,
+    current: Option<(&'static str, Instant)>,
+    stages: Vec<(&'static str, Duration)>,
+    profile: bool,
+    files: Mutex<Vec<FileTiming>>,
+}
+
+impl Timings {
+    pub fn new(profile: bool) -> Timings {
+        Timings {
+            started: Instant::now(),
+            current: None,
+            stages: Vec::new(),
+            profile,
+            files: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Runs work for one file, timing it when profiling; safe to call from worker threads.
+    pub fn file<T>(&self, stage: &'static str, path: &Path, work: impl FnOnce() -> T) -> T {
+        if !self.profile {
+            return work();
+        }
+        let started = Instant::now();
+        let result = work();
+        let timing = FileTiming {
+            stage,
+            path: path.to_path_buf(),
+            duration: started.elapsed(),
+        };
+        if let Ok(mut files) = self.files.lock() {
+            files.push(timing);
+        }
+        result
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn stages(&self) -> &[(&'static str, Duration)] {
+        &self.stages
+    }
+
+    pub fn take_files(&mut self) -> Vec<FileTiming> {
+        match self.files.get_mut() {
+            Ok(files) => std::mem::take(files),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn stage(&mut self, name: &'static str) {
+        self.end();
+        self.current = Some((name, Instant::now()));
+    }
+
+    fn end(&mut self) {
+        let (name, started) = match self.current.take() {
+            Some(current) => current,
+            None => return,
+        };
+        let elapsed = started.elapsed();
+        match self.stages.iter_mut().find(|(stage, _)| *stage == name) {
+            Some((_, total)) => *total += elapsed,
+            None => self.stages.push((name, elapsed)),
+        }
+    }
+
+    // "built in 1.2s: discover 10.1ms, render 1.1s, …".
+    pub fn summary(&mut self) -> String {
+        self.end();
+        let stages: Vec<String> = self
+            .stages
+            .iter()
+            .map(|(name, duration)| format!("{} {:.1?}", name, duration))
+            .collect();
+        format!(
+            "built in {:.1?}: {}.",
+            self.started.elapsed(),
+            stages.join(", ")
+        )
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::aes_gcm;
+use crate::assets;
+use crate::config::Config;
+use crate::content::{meta_context, ContentMeta};
+use crate::sha256::{self, Hmac};
+use crate::template::Templates;
+use crate::value::Value;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn password<'a>(cfg: &'a Config, meta: &'a ContentMeta) -> io::Result<&'a str> {
+    match meta.password.as_deref() {
+        Some(password) => Ok(password),
+        None if !cfg.protected.password.is_empty() => Ok(&cfg.protected.password),
+        None => Err(io::Error::other(format!(
+            "{} is protected but has no password; set [protected] password or a front matter password.",
+            meta.name
+        ))),
+    }
+}
+
+// Base64 of salt, nonce and ciphertext with its tag, as the page script reads it.
+// The salt comes from the output path and the nonce from the text, so an unchanged
+// post encrypts to the same page and the build cache still recognises it.
+// PBKDF2 gives 64 bytes: the first 32, which the page script derives too, are the AES key,
+// and the other 32 key the HMAC for the nonce.
+fn encrypt(cfg: &Config, meta: &ContentMeta, html: &str) -> io::Result<String> {
+    let password = password(cfg, meta)?;
+    let salt = sha256::digest(format!("webweaver:{}", meta.output_path()).as_bytes());
+    let keys = sha256::pbkdf2(
+        password.as_bytes(),
+        &salt[..16],
+        cfg.protected.iterations,
+        64,
+    );
+    let (key, nonce_key) = keys.split_at(32);
+    let key: [u8; 32] = key.try_into().map_err(io::Error::other)?;
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&Hmac::new(nonce_key).mac(html.as_bytes())[..12]);
+
+    let mut payload = Vec::with_capacity(28 + html.len() + 16);
+    payload.extend_from_slice(&salt[..16]);
+    payload.extend_from_slice(&nonce);
+    payload.extend(aes_gcm::encrypt(&key, &nonce, html.as_bytes()));
+    Ok(base64(&payload))
+}
+
+// The rendered body, encrypted into the protected.html block, and the assets it references,
+// which are rewritten before encryption since the ciphertext hides them from the page.
+pub fn protected_body(
+    cfg: &Config,
+    meta: &ContentMeta,
+    source: &Path,
+    html: &str,
+    templates: &Templates,
+) -> io::Result<(String, BTreeSet<PathBuf>)> {
+    let (html, assets) = assets::rewrite_references(cfg, meta, source, html, true);
+    let mut context = meta_context(meta);
+    context.insert(
+        String::from("payload"),
+        Value::String(encrypt(cfg, meta, &html)?),
+    );
+    context.insert(
+        String::from("iterations"),
+        Value::Integer(cfg.protected.iterations as i64),
+    );
+    Ok((templates.protected.render(&context), assets))
+}
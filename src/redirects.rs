@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::{Config, RedirectFormat};
+use crate::opengraph::html_url;
+use crate::template::{context, Templates};
+use crate::url;
+use crate::value::Value;
+use crate::ContentUnit;
+
+const NETLIFY_FILE: &str = "_redirects";
+const NGINX_FILE: &str = "redirects.map";
+
+// An alias is a path below the output root; a directory or extensionless path gets an index.html.
+fn alias_path(alias: &str) -> Option<String> {
+    let alias = alias.trim().trim_start_matches('/');
+    if alias.is_empty()
+        || alias
+            .split('/')
+            .any(|segment| segment == ".." || segment == ".")
+    {
+        return None;
+    }
+    let last = alias.rsplit('/').next().unwrap_or("");
+    if last.is_empty() {
+        Some(format!("{}index.html", alias))
+    } else if !last.contains('.') {
+        Some(format!("{}/index.html", alias))
+    } else {
+        Some(alias.to_string())
+    }
+}
+
+// The server-side maps want the path part of the URL, including any base_url path.
+fn url_path(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    match rest.find('/') {
+        Some(at) => rest[at..].to_string(),
+        None => String::from("/"),
+    }
+}
+
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub title: String,
+}
+
+// Keyed by the stub's path below the output root; `from` keeps the alias as it was written.
+pub fn redirects(cfg: &Config, content: &[ContentUnit]) -> BTreeMap<String, Redirect> {
+    let outputs: BTreeSet<String> = content.iter().map(|unit| unit.meta.output_path()).collect();
+
+    let mut redirects: BTreeMap<String, Redirect> = BTreeMap::new();
+    for unit in content {
+        let meta = &unit.meta;
+        let to = html_url(cfg, &meta.output_path(), meta.format);
+        for alias in &meta.aliases {
+            let path = match alias_path(alias) {
+                Some(path) => path,
+                None => {
+                    eprintln!(
+                        "warning: ignoring alias {} of {}; it must be a path below the output root.",
+                        alias,
+                        meta.output_path()
+                    );
+                    continue;
+                }
+            };
+            if outputs.contains(&path) {
+                eprintln!(
+                    "warning: ignoring alias {} of {}; a post is written there.",
+                    alias,
+                    meta.output_path()
+                );
+                continue;
+            }
+            let redirect = Redirect {
+                from: url_path(&url::join(&cfg.site.base_url, alias.trim())),
+                to: to.clone(),
+                title: meta.name.clone(),
+            };
+            if let Some(previous) = redirects.insert(path, redirect) {
+                eprintln!(
+                    "warning: alias {} is claimed by both {} and {}.",
+                    alias, previous.to, to
+                );
+            }
+        }
+    }
+    redirects
+}
+
+fn redirect_map(cfg: &Config, redirects: &BTreeMap<String, Redirect>) -> String {
+    let mut map = String::new();
+    if cfg.redirects.format == RedirectFormat::Nginx {
+        map.push_str("# map $uri $redirect_uri { include redirects.map; }\n");
+    }
+    for redirect in redirects.values() {
+        let to = url_path(&redirect.to);
+        match cfg.redirects.format {
+            RedirectFormat::Nginx => map.push_str(&format!("{} {};\n", redirect.from, to)),
+            _ => map.push_str(&format!("{} {} 301\n", redirect.from, to)),
+        }
+    }
+    map
+}
+
+pub fn write_redirects(
+    cfg: &Config,
+    content: &[ContentUnit],
+    templates: &Templates,
+) -> io::Result<Vec<PathBuf>> {
+    let redirects = redirects(cfg, content);
+    if redirects.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut written = Vec::with_capacity(redirects.len() + 1);
+    if cfg.redirects.stubs {
+        for (path, redirect) in &redirects {
+            // An .adoc alias gets an AsciiDoc page, which passes the refresh through to its HTML.
+            let template = if path.ends_with(".adoc") {
+                &templates.redirect_adoc
+            } else {
+                &templates.redirect
+            };
+            let stub = template.render(&context([
+                ("title", Value::String(redirect.title.clone())),
+                ("url", Value::String(redirect.to.clone())),
+            ]));
+            let path = cfg.output_root_path.join(path);
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, stub)?;
+            written.push(path);
+        }
+    }
+
+    let file = match cfg.redirects.format {
+        RedirectFormat::Netlify => NETLIFY_FILE,
+        RedirectFormat::Nginx => NGINX_FILE,
+        RedirectFormat::None => return Ok(written),
+    };
+    let path = cfg.output_root_path.join(file);
+    fs::write(&path, redirect_map(cfg, &redirects))?;
+    written.push(path);
+
+    Ok(written)
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::url;
+
+const ROBOTS_FILE: &str = "robots.txt";
+
+pub fn robots_txt(cfg: &Config, sitemap: Option<&str>) -> String {
+    let mut robots = format!("User-agent: {}\n", cfg.robots.user_agent);
+    for path in &cfg.robots.allow {
+        robots.push_str(&format!("Allow: {}\n", path));
+    }
+    for path in &cfg.robots.disallow {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    // An empty Disallow allows everything; a group needs at least one rule.
+    if cfg.robots.allow.is_empty() && cfg.robots.disallow.is_empty() {
+        robots.push_str("Disallow:\n");
+    }
+    if let Some(sitemap) = sitemap {
+        robots.push_str(&format!(
+            "\nSitemap: {}\n",
+            url::join(&cfg.site.base_url, sitemap)
+        ));
+    }
+    robots
+}
+
+// sitemap is the sitemap.xml or sitemap_index.xml the build wrote; a robots.txt in static/ wins.
+pub fn write_robots(cfg: &Config, sitemap: Option<&Path>) -> io::Result<Option<PathBuf>> {
+    let own = cfg
+        .static_root_path
+        .as_ref()
+        .is_some_and(|dir| dir.join(ROBOTS_FILE).is_file());
+    if own {
+        return Ok(None);
+    }
+
+    let sitemap = sitemap
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned());
+    let path = cfg.output_root_path.join(ROBOTS_FILE);
+    fs::create_dir_all(&cfg.output_root_path)?;
+    fs::write(&path, robots_txt(cfg, sitemap.as_deref()))?;
+    Ok(Some(path))
+}
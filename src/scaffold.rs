@@ -0,0 +1,61 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::template::{self, context, optional};
+use crate::value::Value;
+
+fn file_name_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+pub fn new_content(
+    cfg: &Config,
+    title: &str,
+    date: NaiveDate,
+    file_ext: &str,
+) -> io::Result<PathBuf> {
+    let name = file_name_title(title);
+    if name.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("title {:?} has no characters usable in a file name.", title),
+        ));
+    }
+
+    let path = cfg.input_content_root_path.join(format!(
+        "{}_{}.{}",
+        date.format("%Y-%m-%d"),
+        name,
+        file_ext
+    ));
+
+    let archetype =
+        template::archetype(cfg.archetypes_root_path.as_deref(), &cfg.category, file_ext)?;
+    let contents = archetype.render(&context([
+        ("title", Value::String(title.to_string())),
+        ("date", Value::String(date.format("%Y-%m-%d").to_string())),
+        ("category", Value::String(cfg.category.clone())),
+        ("author", optional(cfg.author.as_deref())),
+    ]));
+
+    fs::create_dir_all(&cfg.input_content_root_path)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    file.write_all(contents.as_bytes())?;
+
+    Ok(path)
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::content::{ContentFormat, ContentUnit};
+use crate::feed::content_url;
+use crate::json;
+use crate::markdown::strip_tags;
+use crate::template::strings;
+use crate::value::{Table, Value};
+
+const STOP_WORDS: [&str; 24] = [
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "of", "on",
+    "or", "that", "the", "this", "to", "was", "were", "with", "you",
+];
+
+const TITLE_WEIGHT: i64 = 5;
+const TAG_WEIGHT: i64 = 3;
+
+pub fn search_index_path(cfg: &Config) -> String {
+    cfg.category_path(&cfg.search.path)
+}
+
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+fn body_text(unit: &ContentUnit) -> String {
+    match unit.meta.format {
+        ContentFormat::Markdown | ContentFormat::Org => strip_tags(&unit.contents),
+        // Attribute entries, includes and comments come from the page template, not the post.
+        ContentFormat::AsciiDoc => unit
+            .contents
+            .lines()
+            .filter(|line| {
+                !line.starts_with(':') && !line.starts_with("include::") && !line.starts_with("//")
+            })
+            .collect::<Vec<&str>>()
+            .join("\n"),
+    }
+}
+
+fn document(cfg: &Config, unit: &ContentUnit) -> Value {
+    let meta = &unit.meta;
+    let mut document = Table::new();
+    document.insert(String::from("title"), Value::String(meta.name.clone()));
+    document.insert(String::from("url"), Value::String(meta.output_path()));
+    document.insert(
+        String::from("permalink"),
+        Value::String(content_url(cfg, meta)),
+    );
+    document.insert(
+        String::from("date"),
+        Value::String(meta.date.format("%Y-%m-%d").to_string()),
+    );
+    document.insert(String::from("categories"), strings(&meta.categories));
+    document.insert(String::from("tags"), strings(&meta.tags));
+    if let Some(summary) = &meta.summary {
+        document.insert(String::from("summary"), Value::String(summary.clone()));
+    }
+    Value::Table(document)
+}
+
+pub fn search_index(cfg: &Config, content: &[ContentUnit]) -> Table {
+    let mut index: BTreeMap<String, BTreeMap<usize, i64>> = BTreeMap::new();
+
+    for (id, unit) in content.iter().enumerate() {
+        let meta = &unit.meta;
+        let mut add = |text: &str, weight: i64| {
+            for term in terms(text) {
+                *index.entry(term).or_default().entry(id).or_default() += weight;
+            }
+        };
+        add(&meta.name, TITLE_WEIGHT);
+        for name in meta.tags.iter().chain(&meta.categories) {
+            add(name, TAG_WEIGHT);
+        }
+        add(&body_text(unit), 1);
+    }
+
+    let index: Table = index
+        .into_iter()
+        .map(|(term, postings)| {
+            let postings = postings
+                .into_iter()
+                .map(|(id, weight)| {
+                    Value::Array(vec![Value::Integer(id as i64), Value::Integer(weight)])
+                })
+                .collect();
+            (term, Value::Array(postings))
+        })
+        .collect();
+
+    let mut search = Table::new();
+    search.insert(String::from("version"), Value::Integer(1));
+    search.insert(
+        String::from("documents"),
+        Value::Array(content.iter().map(|unit| document(cfg, unit)).collect()),
+    );
+    search.insert(String::from("index"), Value::Table(index));
+    search
+}
+
+pub fn write_search_index(cfg: &Config, content: &[ContentUnit]) -> io::Result<PathBuf> {
+    let path = cfg.output_root_path.join(search_index_path(cfg));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(
+        &path,
+        json::to_string(&Value::Table(search_index(cfg, content))),
+    )?;
+    Ok(path)
+}
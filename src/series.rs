@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use crate::config::{Config, SERIES};
+use crate::content::{base_path, ContentFormat, ContentMeta};
+use crate::taxonomies::{self, term_index_path};
+use crate::template::context_value;
+use crate::value::Value;
+
+fn nav_link(prefix: &str, meta: &ContentMeta) -> Value {
+    let macro_name = match meta.format {
+        ContentFormat::AsciiDoc => "xref",
+        ContentFormat::Markdown | ContentFormat::Org => "link",
+    };
+    context_value([
+        ("title", Value::String(meta.name.clone())),
+        ("macro", Value::String(macro_name.to_string())),
+        (
+            "url",
+            Value::String(format!("{}{}", prefix, meta.output_path())),
+        ),
+    ])
+}
+
+// Each part's series, its place in it and its neighbours, by the part's output path.
+pub fn series_navigation<'a>(
+    cfg: &Config,
+    metas: impl IntoIterator<Item = &'a ContentMeta>,
+) -> BTreeMap<String, Value> {
+    let mut navigation = BTreeMap::new();
+    let taxonomy = match cfg
+        .taxonomies
+        .iter()
+        .find(|taxonomy| taxonomy.name == SERIES)
+    {
+        Some(taxonomy) => taxonomy,
+        None => return navigation,
+    };
+
+    for series in taxonomies::term_map(taxonomy, metas, |meta| meta).values() {
+        for (i, meta) in series.entries.iter().enumerate() {
+            let prefix = format!("{}/", base_path(&meta.path));
+            let neighbour = |at: Option<usize>| match at.and_then(|at| series.entries.get(at)) {
+                Some(neighbour) => nav_link(&prefix, neighbour),
+                None => Value::Null,
+            };
+            navigation.insert(
+                meta.output_path(),
+                context_value([
+                    ("name", Value::String(series.name.clone())),
+                    (
+                        "url",
+                        Value::String(format!(
+                            "{}{}",
+                            prefix,
+                            term_index_path(cfg, SERIES, &series.name)
+                        )),
+                    ),
+                    ("part", Value::Integer(i as i64 + 1)),
+                    ("parts", Value::Integer(series.entries.len() as i64)),
+                    ("prev", neighbour(i.checked_sub(1))),
+                    ("next", neighbour(Some(i + 1))),
+                ]),
+            );
+        }
+    }
+
+    navigation
+}
@@ -0,0 +1,195 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::url;
+
+const RELOAD_ENDPOINT: &str = "/__webweaver/reload";
+
+const RELOAD_SCRIPT: &str = "<script>
+(function () {
+  var version = null;
+  function poll() {
+    fetch('/__webweaver/reload').then(function (r) { return r.text(); }).then(function (v) {
+      if (version !== null && v !== version) { location.reload(); return; }
+      version = v;
+      setTimeout(poll, 1000);
+    }).catch(function () { setTimeout(poll, 2000); });
+  }
+  poll();
+})();
+</script>
+";
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml; charset=utf-8",
+        "txt" | "adoc" | "asciidoc" | "md" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+fn resolve(root: &Path, request_path: &str) -> Option<PathBuf> {
+    let request_path = request_path.split(['?', '#']).next().unwrap_or("");
+    let decoded = url::percent_decode(request_path);
+    let relative = Path::new(decoded.trim_start_matches('/'));
+
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+
+    let path = root.join(relative);
+    if path.is_dir() {
+        let index = path.join("index.html");
+        if index.is_file() {
+            return Some(index);
+        }
+        return None;
+    }
+    if path.is_file() {
+        return Some(path);
+    }
+    None
+}
+
+// A HEAD response carries the headers a GET would, Content-Length included, and no body.
+fn respond(
+    stream: &mut TcpStream,
+    head: bool,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+fn inject_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(at) => format!("{}{}{}", &html[..at], RELOAD_SCRIPT, &html[at..]),
+        None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+}
+
+fn handle(mut stream: TcpStream, root: &Path, version: &AtomicU64) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => {
+            return respond(
+                &mut stream,
+                false,
+                "400 Bad Request",
+                "text/plain",
+                b"bad request",
+            )
+        }
+    };
+    let head = method == "HEAD";
+
+    if method != "GET" && method != "HEAD" {
+        return respond(
+            &mut stream,
+            false,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"method not allowed",
+        );
+    }
+
+    if target.starts_with(RELOAD_ENDPOINT) {
+        let body = version.load(Ordering::SeqCst).to_string();
+        return respond(&mut stream, head, "200 OK", "text/plain", body.as_bytes());
+    }
+
+    let path = match resolve(root, target) {
+        Some(path) => path,
+        None => {
+            return respond(
+                &mut stream,
+                head,
+                "404 Not Found",
+                "text/plain",
+                b"not found",
+            )
+        }
+    };
+
+    let content_type = content_type(&path);
+    let body = fs::read(&path)?;
+    let body = if content_type.starts_with("text/html") {
+        inject_reload_script(&String::from_utf8_lossy(&body)).into_bytes()
+    } else {
+        body
+    };
+
+    eprintln!("{} {}", method, target);
+    respond(&mut stream, head, "200 OK", content_type, &body)
+}
+
+pub fn serve(root: &Path, address: &str, version: Arc<AtomicU64>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("serving {} at http://{}/", root.display(), address);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("connection failed: {}", err);
+                continue;
+            }
+        };
+        let root = root.to_path_buf();
+        let version = Arc::clone(&version);
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, &root, &version) {
+                eprintln!("request failed: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
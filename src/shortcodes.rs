@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::content::ContentFormat;
+use crate::emoji::BodyScan;
+use crate::template::Template;
+use crate::value::{Table, Value};
+
+const YOUTUBE_ADOC: &str = include_str!("templates/shortcodes/youtube.adoc");
+const YOUTUBE_HTML: &str = include_str!("templates/shortcodes/youtube.html");
+const FIGURE_ADOC: &str = include_str!("templates/shortcodes/figure.adoc");
+const FIGURE_HTML: &str = include_str!("templates/shortcodes/figure.html");
+
+// Under the templates directory; each file is a shortcode named by its stem.
+const SHORTCODES_DIR: &str = "shortcodes";
+
+// The names positional arguments take, so {{ figure cat.png "A cat" }} sets src and caption.
+const PARAMS: [(&str, &[&str]); 2] = [
+    ("youtube", &["id", "title"]),
+    ("figure", &["src", "caption"]),
+];
+
+// Stand in for HTML shortcodes while the Markdown or Org converter runs, as math::protect's placeholders do.
+const HTML_START: char = '\u{e006}';
+const HTML_END: char = '\u{e007}';
+
+// Shortcode templates by file name, like figure.html.
+pub type Shortcodes = BTreeMap<String, Template>;
+
+// The built-in shortcodes, and those in templates/shortcodes, which replace them by file name.
+pub fn load(dir: Option<&Path>, globals: &Arc<Table>) -> io::Result<Shortcodes> {
+    let mut shortcodes = Shortcodes::new();
+    for (name, source) in [
+        ("youtube.adoc", YOUTUBE_ADOC),
+        ("youtube.html", YOUTUBE_HTML),
+        ("figure.adoc", FIGURE_ADOC),
+        ("figure.html", FIGURE_HTML),
+    ] {
+        shortcodes.insert(
+            name.to_string(),
+            Template::parse(name, source)?.with_globals(globals),
+        );
+    }
+    let dir = match dir.map(|dir| dir.join(SHORTCODES_DIR)) {
+        Some(dir) if dir.is_dir() => dir,
+        _ => return Ok(shortcodes),
+    };
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if !path.is_file() || name.starts_with('.') {
+            continue;
+        }
+        let source = fs::read_to_string(&path)?;
+        let template = Template::parse(&path.to_string_lossy(), &source)?;
+        shortcodes.insert(name, template.with_globals(globals));
+    }
+    Ok(shortcodes)
+}
+
+// The template a post of the format uses for the shortcode: one in its own markup
+// (.adoc, .md or .org), else an .html one. The flag is true for HTML.
+fn template<'a>(
+    shortcodes: &'a Shortcodes,
+    format: ContentFormat,
+    name: &str,
+) -> Option<(&'a Template, bool)> {
+    let ext = match format {
+        ContentFormat::AsciiDoc => "adoc",
+        ContentFormat::Markdown => "md",
+        ContentFormat::Org => "org",
+    };
+    shortcodes
+        .get(&format!("{}.{}", name, ext))
+        .map(|template| (template, false))
+        .or_else(|| {
+            shortcodes
+                .get(&format!("{}.html", name))
+                .map(|template| (template, true))
+        })
+}
+
+// The words of a call, with "quoted words" kept whole.
+fn words(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => word.push(chars.next()?),
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// The call at the start of text, its length, name and the template context of its arguments:
+// key=value arguments by key, positional ones as arg1, arg2, ... and in args, and by their
+// names for the built-in shortcodes.
+fn call(text: &str) -> Option<(usize, String, Table)> {
+    let inner = text.strip_prefix("{{")?;
+    let end = inner.find("}}")?;
+    let mut words = words(&inner[..end])?.into_iter();
+    let name = words.next()?;
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    let params = PARAMS
+        .iter()
+        .find(|(shortcode, _)| *shortcode == name)
+        .map_or(&[][..], |(_, params)| *params);
+
+    let mut context = Table::new();
+    let mut args = Vec::new();
+    for word in words {
+        match word.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains('/') => {
+                context.insert(key.to_string(), Value::String(value.to_string()));
+            }
+            _ => {
+                context.insert(
+                    format!("arg{}", args.len() + 1),
+                    Value::String(word.clone()),
+                );
+                if let Some(param) = params.get(args.len()) {
+                    context
+                        .entry(param.to_string())
+                        .or_insert_with(|| Value::String(word.clone()));
+                }
+                args.push(Value::String(word));
+            }
+        }
+    }
+    context.insert(String::from("args"), Value::Array(args));
+    Some((end + 4, name, context))
+}
+
+// Expands each {{ name args }} call outside code whose name has a template; other calls are
+// left as written, and \{{ writes {{. HTML shortcodes in Markdown and Org posts are returned
+// apart, for restore to put into the converted page; AsciiDoc posts pass them through.
+pub fn expand(shortcodes: &Shortcodes, format: ContentFormat, text: &str) -> (String, Vec<String>) {
+    let mut html_pieces = Vec::new();
+    if !text.contains("{{") {
+        return (text.to_string(), html_pieces);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut scan = BodyScan::default();
+    for line in text.split_inclusive('\n') {
+        if scan.is_code(format, line) {
+            out.push_str(line);
+            continue;
+        }
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                let run = rest.chars().take_while(|c| *c == '`').count();
+                let end = rest[run..]
+                    .find(&rest[..run])
+                    .map_or(run, |end| run + end + run);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            if rest.starts_with("\\{{") {
+                out.push_str("{{");
+                rest = &rest[3..];
+                continue;
+            }
+            let expanded = call(rest).and_then(|(len, name, context)| {
+                let (template, html) = template(shortcodes, format, &name)?;
+                Some((len, template.render(&context), html))
+            });
+            let (len, expanded, html) = match expanded {
+                Some(expanded) => expanded,
+                None => {
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                    continue;
+                }
+            };
+            // A call on a line of its own is a block.
+            let block = line.trim() == rest[..len].trim() && line.trim_start().starts_with("{{");
+            match (html, format) {
+                (false, _) => out.push_str(&expanded),
+                (true, ContentFormat::AsciiDoc) if block => {
+                    out.push_str(&format!("++++\n{}\n++++", expanded));
+                }
+                (true, ContentFormat::AsciiDoc) => {
+                    out.push_str(&format!("pass:[{}]", expanded.replace(']', "\\]")));
+                }
+                (true, _) => {
+                    out.push(HTML_START);
+                    out.push_str(&html_pieces.len().to_string());
+                    out.push(HTML_END);
+                    html_pieces.push(expanded);
+                }
+            }
+            rest = &rest[len..];
+        }
+    }
+    (out, html_pieces)
+}
+
+// The text without shortcode calls, for excerpts.
+pub fn strip(text: &str) -> String {
+    if !text.contains("{{") {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("\\{{") {
+            out.push_str("{{");
+            rest = &rest[3..];
+            continue;
+        }
+        match call(rest) {
+            Some((len, _, _)) => rest = &rest[len..],
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+// Puts the HTML shortcodes into the converted page, in place of the paragraph
+// a shortcode on a line of its own was wrapped in.
+pub fn restore(html: &str, html_pieces: &[String]) -> String {
+    if html_pieces.is_empty() {
+        return html.to_string();
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(HTML_START) {
+        let after = &rest[start + HTML_START.len_utf8()..];
+        let piece = after.split_once(HTML_END).and_then(|(index, tail)| {
+            let piece = html_pieces.get(index.parse::<usize>().ok()?)?;
+            Some((piece, tail))
+        });
+        match piece {
+            Some((piece, tail)) => {
+                let (before, tail) =
+                    match (rest[..start].strip_suffix("<p>"), tail.strip_prefix("</p>")) {
+                        (Some(before), Some(tail)) => (before, tail),
+                        _ => (&rest[..start], tail),
+                    };
+                out.push_str(before);
+                out.push_str(piece);
+                rest = tail;
+            }
+            None => {
+                out.push_str(&rest[..start]);
+                out.push(HTML_START);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
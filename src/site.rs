@@ -0,0 +1,1334 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{Datelike, Local};
+
+use crate::archive::{self, Archive};
+use crate::cache::{self, BuildCache, Hasher, Render};
+use crate::config::{Config, IndexGrouping, LinkCheck, SlugCollisions, TAGS};
+use crate::content::{
+    content_excerpt, content_file_metadata, content_unit_contents, feed_description, file_modified,
+    html_page, html_unit_contents, meta_context, newest_first, partials, reading_time, sort_posts,
+    title_case, ContentFormat, ContentMeta, ContentUnit,
+};
+use crate::dates::{format_date, month_name};
+use crate::discovery::{content_files, ContentFile};
+use crate::error::WebWeaverError;
+use crate::lock::{self, OutputLock};
+use crate::opengraph::{html_path, opengraph_tags};
+use crate::progress::{Progress, Timings};
+use crate::template::{context, context_value, optional, strings, Templates};
+use crate::value::Value;
+use crate::{
+    activitypub, assets, attachment, authors, checksums, compress, data, emoji, feed, git,
+    highlight, html, i18n, images, links, manifest, minify, parallel, profile, redirects, robots,
+    search, series, sitemap, smartypants, staging, stream, taxonomies,
+};
+
+pub struct Site {
+    pub config: Config,
+}
+
+pub struct Build {
+    pub index: String,
+    pub errors: Vec<WebWeaverError>,
+}
+
+fn files_map(
+    cfg: &Config,
+    content_files: Vec<ContentFile>,
+    errors: &mut Vec<WebWeaverError>,
+    progress: &Progress,
+) -> Result<BTreeMap<PathBuf, ContentMeta>, WebWeaverError> {
+    let mut content_files_meta_data: BTreeMap<PathBuf, ContentMeta> = BTreeMap::new();
+
+    let metas = parallel::map(cfg.build.jobs, &content_files, |content_file| {
+        let meta = content_file_metadata(cfg, content_file);
+        progress.tick();
+        meta
+    });
+    progress.finish();
+
+    for (content_file, meta) in content_files.into_iter().zip(metas) {
+        let mut meta = match meta {
+            Ok(meta) => meta,
+            Err(err) if cfg.build.keep_going => {
+                errors.push(err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if meta.author.is_none() {
+            meta.author = cfg.author.clone();
+        }
+
+        if content_files_meta_data
+            .insert(content_file.path, meta)
+            .is_some()
+        {
+            return Err(WebWeaverError::Io(io::Error::other(
+                "unexpected duplicate content file.",
+            )));
+        }
+    }
+
+    Ok(content_files_meta_data)
+}
+
+fn resolve_slug_collisions(
+    content_files_meta_data: &mut BTreeMap<PathBuf, ContentMeta>,
+    resolution: SlugCollisions,
+) -> Result<(), WebWeaverError> {
+    let mut outputs: BTreeMap<String, PathBuf> = BTreeMap::new();
+
+    for (path, meta) in content_files_meta_data.iter_mut() {
+        let output_path = meta.output_path();
+        let first = match outputs.get(&output_path) {
+            Some(first) => first,
+            None => {
+                outputs.insert(output_path, path.clone());
+                continue;
+            }
+        };
+
+        // A permalink names its URL outright, so it is never renamed.
+        if meta.permalink.is_some() {
+            return Err(WebWeaverError::Metadata {
+                path: path.clone(),
+                message: format!(
+                    "permalink output {} is already written by {}.",
+                    output_path,
+                    first.display()
+                ),
+            });
+        }
+        if resolution == SlugCollisions::Error {
+            return Err(WebWeaverError::Metadata {
+                path: path.clone(),
+                message: format!(
+                    "output {} is already written by {}; rename one or set [build] slug_collisions = \"suffix\".",
+                    output_path,
+                    first.display()
+                ),
+            });
+        }
+
+        let slug = meta.filesystem_friendly_name.clone();
+        let mut n = 2;
+        while outputs.contains_key(&meta.output_path()) {
+            meta.filesystem_friendly_name = format!("{}_{}", slug, n);
+            n += 1;
+        }
+        eprintln!(
+            "warning: {} collides with {}; writing it to {}.",
+            path.display(),
+            outputs[&output_path].display(),
+            meta.output_path()
+        );
+        outputs.insert(meta.output_path(), path.clone());
+    }
+
+    Ok(())
+}
+
+fn entries_map(metas: impl IntoIterator<Item = ContentMeta>) -> Archive {
+    let mut map: Archive = BTreeMap::new();
+
+    for meta in metas {
+        let units = map
+            .entry(meta.date.year_ce().1)
+            .or_default()
+            .entry(meta.date.month())
+            .or_insert(Vec::with_capacity(8));
+        units.push(meta);
+    }
+
+    for units in map.values_mut().flat_map(|months| months.values_mut()) {
+        units.sort_by(newest_first);
+    }
+
+    map
+}
+
+// With --all every category keeps its index in its own directory.
+fn index_page_path(cfg: &Config, page: usize) -> String {
+    let path = match page {
+        1 => String::from("index.adoc"),
+        page => format!("page/{}/index.adoc", page),
+    };
+    let path = match &cfg.translation {
+        Some(language) => format!("{}/{}", language, path),
+        None => path,
+    };
+    if cfg.build.all || cfg.translation.is_some() {
+        cfg.category_path(&path)
+    } else {
+        path
+    }
+}
+
+fn index_page_prefix(cfg: &Config, page: usize) -> String {
+    "../".repeat(index_page_path(cfg, page).matches('/').count())
+}
+
+fn index_page_link(cfg: &Config, from: usize, to: usize) -> String {
+    format!(
+        "{}{}",
+        index_page_prefix(cfg, from),
+        index_page_path(cfg, to)
+    )
+}
+
+struct IndexLinks<'a> {
+    tags: bool,
+    authors: bool,
+    categories: &'a [CategoryBuild],
+    languages: &'a [(String, String)],
+    pages: &'a [ContentMeta],
+    // Every post the index lists, over all its pages, for the taxonomy sections.
+    posts: &'a [&'a ContentMeta],
+}
+
+// The section heading an index entry falls under, and the page the heading links to.
+fn index_group(cfg: &Config, unit: &ContentMeta) -> Option<(String, Option<String>)> {
+    let (year, month) = (unit.date.year_ce().1, unit.date.month());
+    match cfg.index.group_by {
+        IndexGrouping::Year => Some((year.to_string(), Some(archive::year_index_path(cfg, year)))),
+        IndexGrouping::Month => Some((
+            format!("{} {}", month_name(&cfg.site.locale, month), year),
+            Some(archive::month_index_path(cfg, year, month)),
+        )),
+        IndexGrouping::Category => Some((title_case(&unit.categories.join("/")), None)),
+        IndexGrouping::None => None,
+    }
+}
+
+fn index_page(
+    cfg: &Config,
+    templates: &Templates,
+    units: &[&ContentMeta],
+    page: usize,
+    pages: usize,
+    links: &IndexLinks,
+    excerpts: &BTreeMap<String, String>,
+) -> String {
+    let prefix = index_page_prefix(cfg, page);
+
+    let feeds: Vec<Value> = feed::feed_links(cfg)
+        .into_iter()
+        .map(|(label, path)| {
+            context_value([
+                ("label", Value::String(label.to_string())),
+                ("url", Value::String(format!("{}{}", prefix, path))),
+            ])
+        })
+        .collect();
+
+    let mut previous_group = None;
+    let entries: Vec<Value> = units
+        .iter()
+        .map(|unit| {
+            let group = index_group(cfg, unit);
+            let (group_heading, group_url) = match &group {
+                Some((heading, url)) if previous_group.as_ref() != Some(heading) => (
+                    Value::String(heading.clone()),
+                    match url {
+                        Some(url) => Value::String(format!("{}{}", prefix, url)),
+                        None => Value::Null,
+                    },
+                ),
+                _ => (Value::Null, Value::Null),
+            };
+            previous_group = group.map(|(heading, _)| heading);
+            // Templates written before group_by head their sections with year and year_url.
+            let (year_heading, year_url) =
+                if cfg.index.group_by == IndexGrouping::Year && group_heading != Value::Null {
+                    (
+                        Value::Integer(i64::from(unit.date.year_ce().1)),
+                        group_url.clone(),
+                    )
+                } else {
+                    (Value::Null, Value::Null)
+                };
+
+            let macro_name = match unit.format {
+                ContentFormat::AsciiDoc => "xref",
+                ContentFormat::Markdown | ContentFormat::Org => "link",
+            };
+            let tags: Vec<Value> = unit
+                .tags
+                .iter()
+                .filter(|tag| !taxonomies::term_slug(tag).is_empty())
+                .map(|tag| {
+                    context_value([
+                        ("name", Value::String(tag.clone())),
+                        (
+                            "url",
+                            Value::String(format!(
+                                "{}{}",
+                                prefix,
+                                taxonomies::term_index_path(cfg, TAGS, tag)
+                            )),
+                        ),
+                    ])
+                })
+                .collect();
+
+            let mut entry = meta_context(unit);
+            entry.insert(String::from("group"), group_heading);
+            entry.insert(String::from("group_url"), group_url);
+            entry.insert(String::from("year"), year_heading);
+            entry.insert(String::from("year_url"), year_url);
+            entry.insert(String::from("macro"), Value::String(macro_name.to_string()));
+            entry.insert(
+                String::from("url"),
+                Value::String(format!("{}{}", prefix, unit.output_path())),
+            );
+            entry.insert(
+                String::from("html_url"),
+                Value::String(format!(
+                    "{}{}",
+                    prefix,
+                    html_path(&unit.output_path(), unit.format)
+                )),
+            );
+            entry.insert(
+                String::from("datetime"),
+                Value::String(unit.date.format("%Y-%m-%d").to_string()),
+            );
+            entry.insert(
+                String::from("updated_datetime"),
+                optional(
+                    unit.updated
+                        .map(|updated| updated.format("%Y-%m-%d").to_string())
+                        .as_deref(),
+                ),
+            );
+            entry.insert(
+                String::from("date"),
+                Value::String(format_date(cfg, unit.date)),
+            );
+            entry.insert(
+                String::from("updated"),
+                match unit.updated {
+                    Some(updated) => Value::String(format_date(cfg, updated)),
+                    None => Value::Null,
+                },
+            );
+            entry.insert(String::from("tags"), Value::Array(tags));
+            entry.insert(
+                String::from("excerpt"),
+                optional(excerpts.get(&unit.output_path()).map(String::as_str)),
+            );
+            entry.insert(
+                String::from("author_url"),
+                match &unit.author {
+                    Some(author) if links.authors => Value::String(format!(
+                        "{}{}",
+                        prefix,
+                        authors::author_index_path(cfg, author)
+                    )),
+                    _ => Value::Null,
+                },
+            );
+            Value::Table(entry)
+        })
+        .collect();
+
+    let languages: Vec<Value> = links
+        .languages
+        .iter()
+        .map(|(language, path)| {
+            context_value([
+                ("language", Value::String(language.clone())),
+                ("url", Value::String(format!("{}{}", prefix, path))),
+            ])
+        })
+        .collect();
+
+    let static_pages: Vec<Value> = links
+        .pages
+        .iter()
+        .map(|meta| {
+            let macro_name = match meta.format {
+                ContentFormat::AsciiDoc => "xref",
+                ContentFormat::Markdown | ContentFormat::Org => "link",
+            };
+            context_value([
+                ("title", Value::String(meta.name.clone())),
+                ("macro", Value::String(macro_name.to_string())),
+                (
+                    "url",
+                    Value::String(format!("{}{}", prefix, meta.output_path())),
+                ),
+            ])
+        })
+        .collect();
+
+    let mut attributes = feed::docinfo_attributes(cfg, prefix.trim_end_matches('/'));
+    // Asciidoctor puts the docrole on <body>, which makes the page the h-feed.
+    if cfg.index.microformats {
+        attributes.push(String::from(":docrole: h-feed"));
+    }
+
+    let page_link = |to: usize| Value::String(index_page_link(cfg, page, to));
+    let title = match &cfg.category_config.title {
+        None if cfg.category.is_empty() => cfg.site.title.clone(),
+        _ => category_title(cfg),
+    };
+    let context = context([
+        (
+            "title",
+            Value::String(match &cfg.translation {
+                Some(language) => format!("{} ({})", title, language),
+                None => title,
+            }),
+        ),
+        (
+            "description",
+            optional(cfg.category_config.description.as_deref()),
+        ),
+        ("attributes", strings(&attributes)),
+        ("microformats", Value::Boolean(cfg.index.microformats)),
+        ("languages", Value::Array(languages)),
+        ("static_pages", Value::Array(static_pages)),
+        (
+            "categories",
+            Value::Array(category_values(links.categories, &prefix)),
+        ),
+        ("feeds", Value::Array(feeds)),
+        (
+            "tags_url",
+            if links.tags {
+                Value::String(format!(
+                    "{}{}",
+                    prefix,
+                    taxonomies::taxonomy_index_path(cfg, TAGS)
+                ))
+            } else {
+                Value::Null
+            },
+        ),
+        (
+            "taxonomies",
+            Value::Array(taxonomies::index_values(cfg, links.posts, &prefix)),
+        ),
+        ("entries", Value::Array(entries)),
+        ("paginated", Value::Boolean(pages > 1)),
+        ("page", Value::Integer(page as i64)),
+        ("pages", Value::Integer(pages as i64)),
+        (
+            "newer",
+            if page > 1 {
+                page_link(page - 1)
+            } else {
+                Value::Null
+            },
+        ),
+        (
+            "older",
+            if page < pages {
+                page_link(page + 1)
+            } else {
+                Value::Null
+            },
+        ),
+    ]);
+
+    templates.index.render(&context)
+}
+
+fn index_contents(
+    cfg: &Config,
+    templates: &Templates,
+    archive: &Archive,
+    excerpts: &BTreeMap<String, String>,
+    categories: &[CategoryBuild],
+    languages: &[(String, String)],
+    pages: &[ContentMeta],
+) -> Vec<String> {
+    let mut units: Vec<&ContentMeta> = archive
+        .values()
+        .rev()
+        .flat_map(|months| months.values().rev().flatten())
+        .collect();
+    sort_posts(cfg, &mut units, |unit| unit);
+    if cfg.index.group_by == IndexGrouping::Category {
+        units.sort_by(|a, b| a.categories.cmp(&b.categories));
+    }
+    let links = IndexLinks {
+        tags: units.iter().any(|unit| {
+            unit.tags
+                .iter()
+                .any(|tag| !taxonomies::term_slug(tag).is_empty())
+        }),
+        authors: authors::has_author_pages(cfg, units.iter().copied()),
+        categories,
+        languages,
+        pages,
+        posts: &units,
+    };
+
+    let page_size = match cfg.index.page_size {
+        Some(page_size) if page_size > 0 => page_size,
+        _ => units.len().max(1),
+    };
+    let chunks: Vec<&[&ContentMeta]> = if units.is_empty() {
+        vec![&[]]
+    } else {
+        units.chunks(page_size).collect()
+    };
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| index_page(cfg, templates, chunk, i + 1, chunks.len(), &links, excerpts))
+        .collect()
+}
+
+fn write_index_pages(cfg: &Config, pages: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::with_capacity(pages.len());
+    for (i, page) in pages.iter().enumerate() {
+        let path = cfg.output_root_path.join(index_page_path(cfg, i + 1));
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, page)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn prune_stale_outputs(cfg: &Config, cache: &BuildCache) -> io::Result<usize> {
+    let mut pruned = 0;
+
+    for output in cache.stale_outputs() {
+        let relative = Path::new(&output);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            continue;
+        }
+        let path = cfg.output_root_path.join(relative);
+        if !path.is_file() {
+            continue;
+        }
+        fs::remove_file(&path)?;
+        pruned += 1;
+
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if current == cfg.output_root_path || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    Ok(pruned)
+}
+
+struct RenderedUnit {
+    unit: ContentUnit,
+    output_path: String,
+    page_hash: String,
+    written: bool,
+    assets: BTreeSet<PathBuf>,
+    render: Render,
+}
+
+// fingerprint covers the config and templates; a source unchanged since the last build
+// under the same fingerprint, metadata and series is not rendered again.
+fn render_content_unit(
+    cfg: &Config,
+    templates: &Templates,
+    cache: &BuildCache,
+    input_content_file_path: &Path,
+    meta: &ContentMeta,
+    series: &Value,
+    fingerprint: &str,
+) -> io::Result<RenderedUnit> {
+    let output_root_path = &cfg.output_root_path;
+    let content_file_output_path = meta.output_path();
+    let path = output_root_path.join(&content_file_output_path);
+    let source_mtime = cache::file_mtime(input_content_file_path)?;
+    let mut hasher = Hasher::default();
+    hasher.update(fingerprint.as_bytes());
+    hasher.update(
+        format!(
+            "{:?}{:?}{:?}",
+            meta,
+            series,
+            partials(cfg, meta, input_content_file_path)?
+        )
+        .as_bytes(),
+    );
+    let render_hash = hasher.finish();
+
+    if cfg.build.incremental {
+        if let Some(entry) =
+            cache.unchanged(input_content_file_path, source_mtime, &render_hash, &path)
+        {
+            let render = Render {
+                source_mtime,
+                ..entry.render.clone()
+            };
+            return Ok(RenderedUnit {
+                unit: ContentUnit {
+                    meta: meta.clone(),
+                    contents: render.contents.clone(),
+                    description: render.description.clone(),
+                    excerpt: render.excerpt.clone(),
+                },
+                output_path: content_file_output_path,
+                page_hash: entry.output_hash.clone(),
+                written: false,
+                assets: render.assets.iter().map(PathBuf::from).collect(),
+                render,
+            });
+        }
+    }
+
+    let excerpt = content_excerpt(meta, input_content_file_path)?;
+    let excerpt = if cfg.build.emoji {
+        emoji::expand(&excerpt)
+    } else {
+        excerpt
+    };
+    let excerpt = if smartypants::wants_smartypants(cfg, meta) {
+        smartypants::smarten(&excerpt)
+    } else {
+        excerpt
+    };
+    let (page, contents, mut assets) = match meta.format {
+        // The page holds stream::BODY until write_page copies the body over it.
+        ContentFormat::AsciiDoc if meta.streamed => {
+            let (contents, _) = content_unit_contents(
+                cfg,
+                meta,
+                input_content_file_path,
+                &excerpt,
+                series,
+                templates,
+            )?;
+            let (page, assets) =
+                assets::rewrite_references(cfg, meta, input_content_file_path, &contents, false);
+            (page, String::new(), assets)
+        }
+        ContentFormat::AsciiDoc => {
+            let (contents, protected_assets) = content_unit_contents(
+                cfg,
+                meta,
+                input_content_file_path,
+                &excerpt,
+                series,
+                templates,
+            )?;
+            let (contents, mut assets) =
+                assets::rewrite_references(cfg, meta, input_content_file_path, &contents, false);
+            assets.extend(protected_assets);
+            (contents.clone(), contents, assets)
+        }
+        ContentFormat::Markdown | ContentFormat::Org => {
+            let (contents, protected_assets) =
+                html_unit_contents(cfg, meta, input_content_file_path, templates)?;
+            let (contents, mut assets) =
+                assets::rewrite_references(cfg, meta, input_content_file_path, &contents, true);
+            assets.extend(protected_assets);
+            let page = html_page(
+                cfg,
+                meta,
+                &contents,
+                &excerpt,
+                series,
+                &format!(
+                    "{}{}",
+                    opengraph_tags(cfg, meta, &excerpt, templates),
+                    feed::head_links(cfg)
+                ),
+                templates,
+            );
+            (page, contents, assets)
+        }
+    };
+    // An attachment is copied like any asset its wrapper page links to.
+    if meta.attachment.is_some() {
+        assets.insert(assets::normalize(input_content_file_path));
+    }
+    let dir = output_root_path.join(&meta.path);
+    let fresh = |page_hash: &str| {
+        cfg.build.incremental
+            && cache.is_fresh(
+                input_content_file_path,
+                &content_file_output_path,
+                page_hash,
+                &path,
+            )
+    };
+
+    let (page_hash, written) = if meta.streamed && meta.format == ContentFormat::AsciiDoc {
+        let (page_hash, body_assets, written) =
+            stream::write_page(cfg, meta, input_content_file_path, &page, &path, fresh)?;
+        assets.extend(body_assets);
+        (page_hash, written)
+    } else {
+        let page_hash = cache::content_hash(page.as_bytes());
+        let written = !fresh(&page_hash);
+        if written {
+            fs::create_dir_all(dir)?;
+            fs::write(&path, &page)?;
+        }
+        (page_hash, written)
+    };
+
+    let unit = ContentUnit {
+        meta: meta.clone(),
+        // Feeds and search get nothing of a protected body, not even its ciphertext,
+        // nor a large one, which would otherwise be held until they are written.
+        contents: if meta.protected || meta.streamed {
+            String::new()
+        } else {
+            contents
+        },
+        description: feed_description(meta, &excerpt, templates),
+        excerpt,
+    };
+    let render = Render {
+        source_hash: cache::file_hash(input_content_file_path)?,
+        source_mtime,
+        render_hash,
+        excerpt: unit.excerpt.clone(),
+        description: unit.description.clone(),
+        contents: unit.contents.clone(),
+        assets: assets
+            .iter()
+            .map(|asset| asset.to_string_lossy().into_owned())
+            .collect(),
+    };
+    Ok(RenderedUnit {
+        unit,
+        output_path: content_file_output_path,
+        page_hash,
+        written,
+        assets,
+        render,
+    })
+}
+
+fn construct_content_filesystem(
+    cfg: &Config,
+    templates: &Templates,
+    cache: &mut BuildCache,
+    content_files_meta_data: &BTreeMap<PathBuf, ContentMeta>,
+    series_navigation: &BTreeMap<String, Value>,
+    errors: &mut Vec<WebWeaverError>,
+    timings: &Timings,
+) -> io::Result<(Vec<ContentUnit>, BTreeSet<PathBuf>)> {
+    let (mut written, mut unchanged) = (0, 0);
+    let mut content: Vec<ContentUnit> = Vec::with_capacity(content_files_meta_data.len());
+    let mut assets = BTreeSet::new();
+
+    let entries: Vec<(&PathBuf, &ContentMeta)> = content_files_meta_data.iter().collect();
+    let fingerprint = cache::config_fingerprint(cfg) + &templates.fingerprint();
+    let progress = Progress::new(cfg, "render", entries.len());
+    let rendered = parallel::map(cfg.build.jobs, &entries, |(path, meta)| {
+        let series = series_navigation
+            .get(&meta.output_path())
+            .unwrap_or(&Value::Null);
+        let rendered = timings.file("render", path, || {
+            render_content_unit(cfg, templates, cache, path, meta, series, &fingerprint)
+        });
+        progress.tick();
+        rendered
+    });
+    progress.finish();
+
+    for ((input_content_file_path, _), rendered) in entries.iter().zip(rendered) {
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(err) if cfg.build.keep_going => {
+                cache.keep(input_content_file_path);
+                errors.push(WebWeaverError::Io(io::Error::new(
+                    err.kind(),
+                    format!("{}: {}", input_content_file_path.display(), err),
+                )));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if rendered.written {
+            written += 1;
+        } else {
+            unchanged += 1;
+        }
+        cache.record(
+            input_content_file_path,
+            &rendered.output_path,
+            rendered.page_hash,
+            rendered.render,
+        );
+        assets.extend(rendered.assets);
+        content.push(rendered.unit);
+    }
+
+    eprintln!("wrote {} content files, {} unchanged.", written, unchanged);
+    Ok((content, assets))
+}
+
+struct CategoryBuild {
+    title: String,
+    description: Option<String>,
+    index_path: String,
+    posts: usize,
+    feeds: Vec<(&'static str, String)>,
+    content: Vec<ContentUnit>,
+    pages: Vec<ContentUnit>,
+    index: String,
+}
+
+// The posts a build would publish, with drafts and future posts left out as configured.
+pub fn content_metadata(
+    cfg: &Config,
+    errors: &mut Vec<WebWeaverError>,
+) -> Result<BTreeMap<PathBuf, ContentMeta>, WebWeaverError> {
+    let mut content_files = content_files(
+        &cfg.input_content_root_path,
+        &cfg.output_content_root_path,
+        &cfg.discovery,
+    )?;
+    if cfg.build.git_dates {
+        git::set_git_dates(cfg, &cfg.input_content_root_path, &mut content_files)?;
+    }
+    let progress = Progress::new(cfg, "read", content_files.len());
+    let mut content_files_meta_data: BTreeMap<PathBuf, ContentMeta> =
+        files_map(cfg, content_files, errors, &progress)?;
+    for (path, meta) in content_files_meta_data.iter_mut() {
+        meta.reading_time = reading_time(meta.words, cfg.site.words_per_minute);
+        if cfg.build.emoji {
+            meta.name = emoji::expand(&meta.name);
+            meta.summary = meta.summary.as_deref().map(emoji::expand);
+        }
+        if smartypants::wants_smartypants(cfg, meta) {
+            meta.name = smartypants::smarten(&meta.name);
+            meta.summary = meta.summary.as_deref().map(smartypants::smarten);
+        }
+        meta.streamed = stream::is_streamed(cfg, meta, path);
+        attachment::resolve(cfg, meta, path);
+    }
+    if cfg.build.mtime_updated {
+        for (path, meta) in content_files_meta_data.iter_mut() {
+            if meta.updated.is_none() {
+                meta.updated = file_modified(path).filter(|modified| *modified > meta.date);
+            }
+        }
+    }
+    if !cfg.build.future {
+        let today = Local::now().date_naive();
+        let total = content_files_meta_data.len();
+        content_files_meta_data.retain(|_, meta| meta.date <= today);
+        let scheduled = total - content_files_meta_data.len();
+        if scheduled > 0 {
+            eprintln!(
+                "skipped {} posts dated after today; pass --include-future to include them.",
+                scheduled
+            );
+        }
+    }
+    if !cfg.build.drafts {
+        let total = content_files_meta_data.len();
+        content_files_meta_data.retain(|_, meta| !meta.draft);
+        let drafts = total - content_files_meta_data.len();
+        if drafts > 0 {
+            eprintln!("skipped {} drafts; pass --drafts to include them.", drafts);
+        }
+    }
+    Ok(content_files_meta_data)
+}
+
+fn build_category(
+    cfg: &Config,
+    templates: &Templates,
+    cache: &mut BuildCache,
+    errors: &mut Vec<WebWeaverError>,
+    timings: &mut Timings,
+) -> Result<CategoryBuild, WebWeaverError> {
+    timings.stage("discover");
+    cache.enter(&cfg.output_content_root_path);
+    let first_error = errors.len();
+    let mut content_files_meta_data = content_metadata(cfg, errors)?;
+    resolve_slug_collisions(&mut content_files_meta_data, cfg.build.slug_collisions)?;
+    let default_language = i18n::default_language(cfg);
+    i18n::link_translations(&mut content_files_meta_data, &default_language);
+    let series_navigation = series::series_navigation(
+        cfg,
+        content_files_meta_data.values().filter(|meta| !meta.page),
+    );
+    // A skipped file keeps its last good output instead of having it pruned.
+    for err in &errors[first_error..] {
+        if let WebWeaverError::Metadata { path, .. } = err {
+            cache.keep(path);
+        }
+    }
+    timings.stage("render");
+    let (content, assets) = construct_content_filesystem(
+        cfg,
+        templates,
+        cache,
+        &content_files_meta_data,
+        &series_navigation,
+        errors,
+        timings,
+    )?;
+    // Pages are rendered like posts but stay out of the index, feeds and archives.
+    let (pages, content): (Vec<ContentUnit>, Vec<ContentUnit>) =
+        content.into_iter().partition(|unit| unit.meta.page);
+    let page_metas = navigation_pages(&pages, &default_language);
+
+    timings.stage("assets");
+    let copied = assets::copy_assets(cfg, &assets)?;
+    if cfg.images.enabled {
+        for path in images::write_variants(cfg, &copied, timings)? {
+            cache.generated(&path);
+        }
+    }
+    for path in copied {
+        cache.generated(&path);
+    }
+
+    timings.stage("index");
+    // Posts in other languages get their own index and feeds instead of the category's.
+    let (mut content, translated) = i18n::split_translated(content, &default_language);
+    let mut languages = Vec::new();
+    if !translated.is_empty() {
+        languages.push((default_language.clone(), index_page_path(cfg, 1)));
+        for language in translated.keys() {
+            let language_cfg = i18n::language_config(cfg, language);
+            languages.push((language.clone(), index_page_path(&language_cfg, 1)));
+        }
+    }
+
+    write_feeds(cfg, cache, &content)?;
+
+    for (language, units) in translated {
+        let language_cfg = i18n::language_config(cfg, &language);
+        write_feeds(&language_cfg, cache, &units)?;
+        let archive = entries_map(units.iter().map(|unit| unit.meta.clone()));
+        let pages = index_contents(
+            &language_cfg,
+            templates,
+            &archive,
+            &excerpts(&units),
+            &[],
+            &languages,
+            &page_metas,
+        );
+        for path in write_index_pages(&language_cfg, &pages)? {
+            cache.generated(&path);
+        }
+        content.extend(units);
+    }
+
+    if cfg.search.enabled {
+        cache.generated(&search::write_search_index(cfg, &content)?);
+    }
+
+    for path in authors::write_author_pages(cfg, &content)? {
+        cache.generated(&path);
+    }
+
+    for path in taxonomies::write_taxonomy_pages(cfg, &content)? {
+        cache.generated(&path);
+    }
+
+    let metas: Vec<ContentMeta> = content_files_meta_data
+        .into_values()
+        .filter(|meta| !meta.page)
+        .collect();
+    let archive = entries_map(metas.iter().cloned());
+    for path in archive::write_archive_pages(cfg, &archive)? {
+        cache.generated(&path);
+    }
+
+    let index_archive = if languages.is_empty() {
+        archive
+    } else {
+        entries_map(
+            metas
+                .into_iter()
+                .filter(|meta| i18n::is_default_language(meta, &default_language)),
+        )
+    };
+    let mut index_pages = index_contents(
+        cfg,
+        templates,
+        &index_archive,
+        &excerpts(&content),
+        &[],
+        &languages,
+        &page_metas,
+    );
+    if cfg.index.page_size.is_some() || cfg.build.all || !languages.is_empty() {
+        for path in write_index_pages(cfg, &index_pages)? {
+            cache.generated(&path);
+        }
+    }
+
+    Ok(CategoryBuild {
+        title: category_title(cfg),
+        description: cfg.category_config.description.clone(),
+        index_path: index_page_path(cfg, 1),
+        posts: content.len(),
+        feeds: feed::feed_links(cfg),
+        content,
+        pages,
+        index: index_pages.swap_remove(0),
+    })
+}
+
+fn category_title(cfg: &Config) -> String {
+    match &cfg.category_config.title {
+        Some(title) => title.clone(),
+        None => title_case(&cfg.category),
+    }
+}
+
+// Default language pages by title, for the index's navigation.
+fn navigation_pages(pages: &[ContentUnit], default_language: &str) -> Vec<ContentMeta> {
+    let mut metas: Vec<ContentMeta> = pages
+        .iter()
+        .filter(|unit| i18n::is_default_language(&unit.meta, default_language))
+        .map(|unit| unit.meta.clone())
+        .collect();
+    metas.sort_by(|a, b| a.name.cmp(&b.name));
+    metas
+}
+
+fn category_values(categories: &[CategoryBuild], prefix: &str) -> Vec<Value> {
+    categories
+        .iter()
+        .map(|category| {
+            let feeds: Vec<Value> = category
+                .feeds
+                .iter()
+                .map(|(label, path)| {
+                    context_value([
+                        ("label", Value::String(label.to_string())),
+                        ("url", Value::String(format!("{}{}", prefix, path))),
+                    ])
+                })
+                .collect();
+            context_value([
+                ("title", Value::String(category.title.clone())),
+                ("description", optional(category.description.as_deref())),
+                (
+                    "url",
+                    Value::String(format!("{}{}", prefix, category.index_path)),
+                ),
+                ("posts", Value::Integer(category.posts as i64)),
+                ("feeds", Value::Array(feeds)),
+            ])
+        })
+        .collect()
+}
+
+fn excerpts(content: &[ContentUnit]) -> BTreeMap<String, String> {
+    content
+        .iter()
+        .filter(|unit| !unit.excerpt.is_empty())
+        .map(|unit| (unit.meta.output_path(), unit.excerpt.clone()))
+        .collect()
+}
+
+fn write_feeds(
+    cfg: &Config,
+    cache: &mut BuildCache,
+    content: &[ContentUnit],
+) -> Result<(), WebWeaverError> {
+    if cfg.feed.rss {
+        cache.generated(&feed::write_rss_feed(cfg, content)?);
+    }
+
+    if cfg.feed.atom {
+        cache.generated(&feed::write_atom_feed(cfg, content)?);
+    }
+
+    if cfg.feed.json {
+        cache.generated(&feed::write_json_feed(cfg, content)?);
+    }
+
+    if !feed::head_links(cfg).is_empty() {
+        cache.generated(&feed::write_docinfo(cfg)?);
+    }
+
+    Ok(())
+}
+
+fn build_combined(
+    cfg: &Config,
+    templates: &Templates,
+    cache: &mut BuildCache,
+    categories: &[CategoryBuild],
+    content: &[ContentUnit],
+    pages: &[ContentUnit],
+) -> Result<String, WebWeaverError> {
+    write_feeds(cfg, cache, content)?;
+
+    for path in authors::write_author_pages(cfg, content)? {
+        cache.generated(&path);
+    }
+
+    for path in taxonomies::write_taxonomy_pages(cfg, content)? {
+        cache.generated(&path);
+    }
+
+    let archive = entries_map(content.iter().map(|unit| unit.meta.clone()));
+    for path in archive::write_archive_pages(cfg, &archive)? {
+        cache.generated(&path);
+    }
+
+    let mut index_pages = index_contents(
+        cfg,
+        templates,
+        &archive,
+        &excerpts(content),
+        categories,
+        &[],
+        &navigation_pages(pages, &i18n::default_language(cfg)),
+    );
+    for path in write_index_pages(cfg, &index_pages)? {
+        cache.generated(&path);
+    }
+
+    Ok(index_pages.swap_remove(0))
+}
+
+// output_root is where the output is published, which an atomic build stages elsewhere first.
+fn build_site(cfg: &Config, output_root: &Path) -> Result<Build, WebWeaverError> {
+    let mut errors = Vec::new();
+    fs::create_dir_all(&cfg.output_root_path)?;
+
+    let data = data::load(cfg.data_root_path.as_deref())?;
+    let templates = Templates::load(cfg.templates_root_path.as_deref(), data)
+        .map_err(|err| WebWeaverError::Template(err.to_string()))?;
+    let scope = (!cfg.build.all).then_some(cfg.output_content_root_path.as_path());
+    let mut cache = BuildCache::load(&cfg.output_root_path, scope);
+    let mut timings = Timings::new(cfg.build.profile);
+
+    let (index, mut content, pages) = if cfg.build.all {
+        let mut categories = Vec::new();
+        for category_cfg in cfg.category_configs()? {
+            eprintln!("building {}.", category_cfg.category);
+            categories.push(build_category(
+                &category_cfg,
+                &templates,
+                &mut cache,
+                &mut errors,
+                &mut timings,
+            )?);
+        }
+        let content: Vec<ContentUnit> = categories
+            .iter_mut()
+            .flat_map(|category| category.content.drain(..))
+            .collect();
+        let pages: Vec<ContentUnit> = categories
+            .iter_mut()
+            .flat_map(|category| category.pages.drain(..))
+            .collect();
+        cache.enter(&cfg.output_content_root_path);
+        timings.stage("index");
+        let index = build_combined(cfg, &templates, &mut cache, &categories, &content, &pages)?;
+        (index, content, pages)
+    } else {
+        let category = build_category(cfg, &templates, &mut cache, &mut errors, &mut timings)?;
+        (category.index, category.content, category.pages)
+    };
+
+    timings.stage("write");
+    for path in assets::copy_static(cfg)? {
+        cache.generated(&path);
+    }
+
+    if cfg.activitypub.enabled {
+        for path in activitypub::write_activitypub(cfg, &content)? {
+            cache.generated(&path);
+        }
+    }
+    content.extend(pages);
+
+    let mut sitemap_path = None;
+    if cfg.sitemap.enabled {
+        for path in sitemap::write_sitemap(cfg, &content)? {
+            cache.generated(&path);
+            sitemap_path = Some(path);
+        }
+    }
+    if cfg.robots.enabled {
+        if let Some(path) = robots::write_robots(cfg, sitemap_path.as_deref())? {
+            cache.generated(&path);
+        }
+    }
+
+    for path in redirects::write_redirects(cfg, &content, &templates)? {
+        cache.generated(&path);
+    }
+    if let Some(path) = highlight::write_stylesheet(cfg)? {
+        cache.generated(&path);
+    }
+
+    if cfg.build.html {
+        timings.stage("html");
+        let heads: BTreeMap<String, String> = content
+            .iter()
+            .filter(|unit| unit.meta.format == ContentFormat::AsciiDoc)
+            .map(|unit| {
+                (
+                    unit.meta.output_path(),
+                    opengraph_tags(cfg, &unit.meta, &unit.excerpt, &templates)
+                        + &highlight::stylesheet_link(cfg, &unit.meta.path),
+                )
+            })
+            .collect();
+        let smartened: BTreeSet<String> = content
+            .iter()
+            .filter(|unit| smartypants::wants_smartypants(cfg, &unit.meta))
+            .map(|unit| unit.meta.output_path())
+            .collect();
+        for path in html::render_asciidoc(cfg, &cache.outputs(), &heads, &smartened)? {
+            cache.generated(&path);
+        }
+    }
+
+    timings.stage("optimize");
+    if cfg.build.minify {
+        let (files, saved) = minify::minify_outputs(cfg, &cache.outputs())?;
+        if files > 0 {
+            eprintln!("minified {} files, saving {} bytes.", files, saved);
+        }
+    }
+
+    if cfg.compress.gzip || cfg.compress.brotli {
+        for path in compress::write_compressed(cfg, &cache.outputs())? {
+            cache.generated(&path);
+        }
+    }
+
+    // Written before the manifest, which lists it so that deploy uploads it.
+    if cfg.build.checksums {
+        let path = checksums::write_checksums(cfg, &cache.outputs())?;
+        cache.generated(&path);
+    }
+
+    if cfg.build.manifest {
+        let path = manifest::write_manifest(cfg, &cache, &content)?;
+        cache.generated(&path);
+    }
+
+    timings.stage("finish");
+    if cfg.build.prune {
+        let pruned = prune_stale_outputs(cfg, &cache)?;
+        if pruned > 0 {
+            eprintln!("pruned {} stale output files.", pruned);
+        }
+    }
+    cache.save()?;
+
+    if cfg.build.check_links != LinkCheck::Off {
+        timings.stage("links");
+        let mut broken = links::check_links(cfg, &cache.outputs())?;
+        for link in &mut broken {
+            if let Ok(output) = link.page.strip_prefix(&cfg.output_root_path) {
+                link.page = output_root.join(output);
+            }
+        }
+        if cfg.build.check_links == LinkCheck::Error && !broken.is_empty() {
+            return Err(WebWeaverError::BrokenLinks(broken));
+        }
+        for link in &broken {
+            eprintln!(
+                "warning: broken link in {}: {}",
+                link.page.display(),
+                link.target
+            );
+        }
+    }
+
+    if cfg.build.progress || cfg.build.profile {
+        eprintln!("{}", timings.summary());
+    }
+    if cfg.build.profile {
+        profile::write_profile(cfg, &mut timings, output_root)?;
+    }
+    Ok(Build { index, errors })
+}
+
+impl Site {
+    pub fn new(config: Config) -> Site {
+        Site { config }
+    }
+
+    fn lock(&self) -> io::Result<Option<OutputLock>> {
+        if self.config.build.force {
+            return Ok(None);
+        }
+        lock::lock_output(&self.config.output_root_path).map(Some)
+    }
+
+    pub fn build(&self) -> Result<Build, WebWeaverError> {
+        let cfg = &self.config;
+        let _lock = self.lock()?;
+        if !cfg.build.atomic {
+            return build_site(cfg, &cfg.output_root_path);
+        }
+
+        // The build goes to a staging copy of the output, which replaces it only once the build succeeds.
+        self.check_replaceable()?;
+        let staging = staging::stage(&cfg.output_root_path, cfg.build.clean)?;
+        let mut staged = cfg.clone();
+        staged.output_root_path = staging.clone();
+        let build = build_site(&staged, &cfg.output_root_path).and_then(|build| {
+            staging::swap(&staging, &cfg.output_root_path)?;
+            Ok(build)
+        });
+        if build.is_err() {
+            staging::discard(&staging);
+        }
+        build
+    }
+
+    // Refuses to remove or replace an output directory that holds the input or working directory.
+    fn check_replaceable(&self) -> io::Result<()> {
+        let output_root_path = &self.config.output_root_path;
+        if !output_root_path.exists() {
+            return Ok(());
+        }
+
+        let output = fs::canonicalize(output_root_path)?;
+        let input = fs::canonicalize(&self.config.input_content_root_path)?;
+        let working_dir = fs::canonicalize(env::current_dir()?)?;
+
+        if input.starts_with(&output) || working_dir.starts_with(&output) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "refusing to remove {}: it contains the input or working directory.",
+                    output_root_path.display()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn clean(&self) -> io::Result<()> {
+        self.check_replaceable()?;
+        let output_root_path = &self.config.output_root_path;
+        if !output_root_path.exists() {
+            return Ok(());
+        }
+        let _lock = self.lock()?;
+        fs::remove_dir_all(output_root_path)
+    }
+}
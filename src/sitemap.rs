@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::feed::canonical_url;
+use crate::url;
+use crate::ContentUnit;
+
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn urlset(entries: &[(String, NaiveDate)]) -> String {
+    let mut xml = String::with_capacity(128 + entries.len() * 128);
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<urlset xmlns=\"{}\">\n", SITEMAP_NS));
+    for (loc, lastmod) in entries {
+        xml.push_str(&format!(
+            "  <url>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </url>\n",
+            escape_xml(loc),
+            lastmod.format("%Y-%m-%d")
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(&xml[start..end])
+}
+
+// The <url> entries of the sitemap, or of each sitemap its index lists, an earlier build wrote.
+fn previous_entries(cfg: &Config) -> Vec<(String, NaiveDate)> {
+    let index = cfg.output_root_path.join("sitemap_index.xml");
+    let files: Vec<PathBuf> = match fs::read_to_string(&index) {
+        Ok(xml) => xml
+            .split("<sitemap>")
+            .skip(1)
+            .filter_map(|sitemap| element(sitemap, "loc"))
+            .filter_map(|loc| loc.rsplit('/').next())
+            .map(|file_name| cfg.output_root_path.join(file_name))
+            .collect(),
+        Err(_) => vec![cfg.output_root_path.join("sitemap.xml")],
+    };
+
+    let mut entries = Vec::new();
+    for file in files {
+        let xml = match fs::read_to_string(&file) {
+            Ok(xml) => xml,
+            Err(_) => continue,
+        };
+        for url in xml.split("<url>").skip(1) {
+            let lastmod = element(url, "lastmod")
+                .and_then(|lastmod| NaiveDate::parse_from_str(lastmod, "%Y-%m-%d").ok());
+            if let (Some(loc), Some(lastmod)) = (element(url, "loc"), lastmod) {
+                entries.push((unescape_xml(loc), lastmod));
+            }
+        }
+    }
+    entries
+}
+
+fn sitemap_index(sitemaps: &[(String, NaiveDate)]) -> String {
+    let mut xml = String::with_capacity(128 + sitemaps.len() * 128);
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<sitemapindex xmlns=\"{}\">\n", SITEMAP_NS));
+    for (loc, lastmod) in sitemaps {
+        xml.push_str(&format!(
+            "  <sitemap>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </sitemap>\n",
+            escape_xml(loc),
+            lastmod.format("%Y-%m-%d")
+        ));
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+pub fn write_sitemap(cfg: &Config, content: &[ContentUnit]) -> io::Result<Vec<PathBuf>> {
+    // A post whose canonical copy lives on another site is left to that site's sitemap.
+    let own = format!("{}/", cfg.site.canonical_base_url.trim_end_matches('/'));
+    let mut entries: Vec<(String, NaiveDate)> = content
+        .iter()
+        .map(|unit| (canonical_url(cfg, &unit.meta), unit.meta.modified()))
+        .filter(|(url, _)| url.starts_with(&own))
+        .collect();
+    // A build of one category keeps what the last sitemap listed outside it.
+    if !cfg.build.all {
+        let category = url::join(&cfg.site.canonical_base_url, &cfg.category_path(""));
+        entries.extend(
+            previous_entries(cfg)
+                .into_iter()
+                .filter(|(url, _)| url.starts_with(&own) && !url.starts_with(&category)),
+        );
+    }
+    entries.sort();
+    entries.dedup_by(|a, b| a.0 == b.0);
+
+    fs::create_dir_all(&cfg.output_root_path)?;
+
+    let max_urls = cfg.sitemap.max_urls.max(1);
+    if entries.len() <= max_urls {
+        let path = cfg.output_root_path.join("sitemap.xml");
+        fs::write(&path, urlset(&entries))?;
+        return Ok(vec![path]);
+    }
+
+    let mut sitemaps: Vec<(String, NaiveDate)> = Vec::with_capacity(entries.len() / max_urls + 1);
+    let mut written: Vec<PathBuf> = Vec::with_capacity(entries.len() / max_urls + 2);
+    for (i, chunk) in entries.chunks(max_urls).enumerate() {
+        let file_name = format!("sitemap-{}.xml", i + 1);
+        let path = cfg.output_root_path.join(&file_name);
+        fs::write(&path, urlset(chunk))?;
+        written.push(path);
+
+        let lastmod = chunk
+            .iter()
+            .map(|(_, lastmod)| *lastmod)
+            .max()
+            .expect("chunks are never empty");
+        sitemaps.push((url::join(&cfg.site.base_url, &file_name), lastmod));
+    }
+
+    let path = cfg.output_root_path.join("sitemap_index.xml");
+    fs::write(&path, sitemap_index(&sitemaps))?;
+    written.push(path);
+
+    Ok(written)
+}
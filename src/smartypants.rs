@@ -0,0 +1,133 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::content::ContentMeta;
+
+// Elements whose text is code or markup, left as written.
+const VERBATIM: [&str; 7] = ["pre", "code", "kbd", "samp", "script", "style", "textarea"];
+
+// A post's typography: front matter overrides [smartypants] enabled, which _category.toml may set per category.
+pub fn wants_smartypants(cfg: &Config, meta: &ContentMeta) -> bool {
+    meta.smartypants.unwrap_or(cfg.smartypants.enabled)
+}
+
+// Whether a quote after this character opens rather than closes.
+fn opens_after(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "([{<‘“—–/-".contains(c))
+}
+
+// Curly quotes, dashes and ellipses: --- is an em dash and -- an en dash, as in Pandoc.
+// previous carries the character before text, so quotes around inline markup pair up.
+fn smarten_text(text: &str, previous: &mut Option<char>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (replacement, len) = if rest.starts_with("---") {
+            ('—', 3)
+        } else if rest.starts_with("--") {
+            ('–', 2)
+        } else if rest.starts_with("...") {
+            ('…', 3)
+        } else if rest.starts_with(". . .") {
+            ('…', 5)
+        } else if rest.starts_with('"') || rest.starts_with("&quot;") {
+            let len = if c == '"' { 1 } else { 6 };
+            (if opens_after(*previous) { '“' } else { '”' }, len)
+        } else if rest.starts_with('\'') || rest.starts_with("&#39;") || rest.starts_with("&#x27;")
+        {
+            let len = rest.find(['\'', ';']).map_or(1, |end| end + 1);
+            let next = rest[len..].chars().next();
+            // '90s takes an apostrophe, not an opening quote.
+            let opening = opens_after(*previous) && !next.is_some_and(|c| c.is_ascii_digit());
+            (if opening { '‘' } else { '’' }, len)
+        } else {
+            (c, c.len_utf8())
+        };
+        out.push(replacement);
+        *previous = Some(replacement);
+        rest = &rest[len..];
+    }
+    out
+}
+
+// For titles, summaries and excerpts, which are plain text.
+pub fn smarten(text: &str) -> String {
+    smarten_text(text, &mut None)
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches(['<', '/'])
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+// Smartens the text of an HTML fragment or page, passing over tags, comments,
+// verbatim elements and the math spans KaTeX renders.
+pub fn smarten_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut previous = None;
+    // The element being passed over, and how deeply it is nested in itself.
+    let mut verbatim: Option<(String, usize)> = None;
+    let mut rest = html;
+    while !rest.is_empty() {
+        let text_len = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..text_len];
+        if verbatim.is_some() {
+            out.push_str(text);
+        } else {
+            out.push_str(&smarten_text(text, &mut previous));
+        }
+        rest = &rest[text_len..];
+        if rest.is_empty() {
+            break;
+        }
+
+        let tag_len = if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |end| end + 3)
+        } else {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        };
+        let tag = &rest[..tag_len];
+        out.push_str(tag);
+        rest = &rest[tag_len..];
+        if tag.starts_with("<!") || tag.starts_with("<?") || tag.ends_with("/>") {
+            continue;
+        }
+        let name = tag_name(tag);
+        let closing = tag.starts_with("</");
+        match &mut verbatim {
+            Some((open, depth)) if *open == name => {
+                if !closing {
+                    *depth += 1;
+                } else if *depth == 1 {
+                    verbatim = None;
+                } else {
+                    *depth -= 1;
+                }
+            }
+            Some(_) => {}
+            None if closing => {}
+            None => {
+                let math = name == "span" && tag.contains("class=\"math ");
+                if math || VERBATIM.contains(&name.as_str()) {
+                    verbatim = Some((name, 1));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Smartens a page Asciidoctor rendered with --html.
+pub fn smarten_file(path: &Path) -> io::Result<()> {
+    let html = fs::read_to_string(path)?;
+    let smartened = smarten_html(&html);
+    if smartened != html {
+        fs::write(path, smartened)?;
+    }
+    Ok(())
+}
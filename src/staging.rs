@@ -0,0 +1,87 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// A hidden sibling of the output directory, so the final renames stay on one filesystem.
+fn sibling(output: &Path, suffix: &str) -> io::Result<PathBuf> {
+    match output.file_name() {
+        Some(name) => Ok(output.with_file_name(format!(".{}.{}", name.to_string_lossy(), suffix))),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "[build] atomic needs a named output directory, not {}.",
+                output.display()
+            ),
+        )),
+    }
+}
+
+fn remove_leftover(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+// Copies keep their modification times, so the freshness checks of the next build still hold.
+fn copy_tree(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for dir_entry in fs::read_dir(source)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        let target = target.join(dir_entry.file_name());
+        if path.is_dir() {
+            copy_tree(&path, &target)?;
+            continue;
+        }
+        fs::copy(&path, &target)?;
+        let modified = fs::metadata(&path)?.modified()?;
+        // Windows only sets times through a handle open for writing; Unix takes a read-only one
+        // too, which a file copied without write permission needs.
+        let file = match File::options().write(true).open(&target) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => File::open(&target)?,
+            Err(err) => return Err(err),
+        };
+        file.set_modified(modified)?;
+    }
+    Ok(())
+}
+
+// A fresh staging directory holding a copy of the current output, or nothing with clean.
+pub fn stage(output: &Path, clean: bool) -> io::Result<PathBuf> {
+    let staging = sibling(output, "webweaver-staging")?;
+    // An interrupted build leaves its staging directory behind.
+    remove_leftover(&staging)?;
+    if clean || !output.exists() {
+        fs::create_dir_all(&staging)?;
+    } else {
+        copy_tree(output, &staging)?;
+    }
+    Ok(staging)
+}
+
+// Moves the finished staging directory into place; the old output is only removed once it is.
+pub fn swap(staging: &Path, output: &Path) -> io::Result<()> {
+    if !output.exists() {
+        return fs::rename(staging, output);
+    }
+    let old = sibling(output, "webweaver-old")?;
+    remove_leftover(&old)?;
+    fs::rename(output, &old)?;
+    if let Err(err) = fs::rename(staging, output) {
+        fs::rename(&old, output)?;
+        return Err(err);
+    }
+    fs::remove_dir_all(&old)
+}
+
+pub fn discard(staging: &Path) {
+    if let Err(err) = remove_leftover(staging) {
+        eprintln!(
+            "warning: could not remove staging directory {}: {}",
+            staging.display(),
+            err
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::config::Config;
+use crate::content::{reading_time, ContentMeta};
+use crate::dates::month_name;
+
+const TOP_TAGS: usize = 10;
+const LONGEST_GAPS: usize = 5;
+
+fn abbreviated_month(cfg: &Config, month: u32) -> String {
+    month_name(&cfg.site.locale, month)
+        .chars()
+        .take(3)
+        .collect()
+}
+
+fn posts_per_month(cfg: &Config, metas: &[ContentMeta], report: &mut String) {
+    let mut months: BTreeMap<i32, BTreeMap<u32, usize>> = BTreeMap::new();
+    for meta in metas {
+        *months
+            .entry(meta.date.year())
+            .or_default()
+            .entry(meta.date.month())
+            .or_default() += 1;
+    }
+
+    report.push_str("\nposts per month\n");
+    for (year, months) in months.iter().rev() {
+        let total: usize = months.values().sum();
+        let months: Vec<String> = months
+            .iter()
+            .map(|(month, posts)| format!("{} {}", abbreviated_month(cfg, *month), posts))
+            .collect();
+        report.push_str(&format!(
+            "  {}  {:>4}  {}\n",
+            year,
+            total,
+            months.join(" · ")
+        ));
+    }
+}
+
+fn top_tags(metas: &[ContentMeta], report: &mut String) {
+    let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in metas.iter().flat_map(|meta| &meta.tags) {
+        *tags.entry(tag.as_str()).or_default() += 1;
+    }
+    if tags.is_empty() {
+        return;
+    }
+    let mut tags: Vec<(&str, usize)> = tags.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let width = tags
+        .iter()
+        .take(TOP_TAGS)
+        .map(|(tag, _)| tag.chars().count())
+        .max()
+        .unwrap_or(0);
+    report.push_str("\nmost used tags\n");
+    for (tag, posts) in tags.iter().take(TOP_TAGS) {
+        report.push_str(&format!("  {:<width$}  {:>4}\n", tag, posts, width = width));
+    }
+}
+
+fn longest_gaps(metas: &[ContentMeta], report: &mut String) {
+    let mut dates: Vec<NaiveDate> = metas.iter().map(|meta| meta.date).collect();
+    dates.sort();
+    dates.dedup();
+    let mut gaps: Vec<(i64, NaiveDate, NaiveDate)> = dates
+        .windows(2)
+        .map(|pair| ((pair[1] - pair[0]).num_days(), pair[0], pair[1]))
+        .collect();
+    if gaps.is_empty() {
+        return;
+    }
+    gaps.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    report.push_str("\nlongest gaps between posts\n");
+    for (days, from, to) in gaps.iter().take(LONGEST_GAPS) {
+        report.push_str(&format!(
+            "  {:>5} days  {} → {}\n",
+            days,
+            from.format("%Y-%m-%d"),
+            to.format("%Y-%m-%d")
+        ));
+    }
+}
+
+pub fn report(cfg: &Config, metas: &[ContentMeta]) -> String {
+    let mut report = String::with_capacity(1024);
+    let (first, latest) = match (
+        metas.iter().map(|meta| meta.date).min(),
+        metas.iter().map(|meta| meta.date).max(),
+    ) {
+        (Some(first), Some(latest)) => (first, latest),
+        _ => return String::from("no posts.\n"),
+    };
+
+    let words: usize = metas.iter().map(|meta| meta.words).sum();
+    let average = words / metas.len();
+    report.push_str(&format!("posts        {}\n", metas.len()));
+    report.push_str(&format!("words        {}\n", words));
+    report.push_str(&format!("average      {} words", average));
+    match reading_time(average, cfg.site.words_per_minute) {
+        0 => report.push('\n'),
+        minutes => report.push_str(&format!(", {} min read\n", minutes)),
+    }
+    let longest = metas
+        .iter()
+        .max_by_key(|meta| meta.words)
+        .expect("metas is not empty");
+    report.push_str(&format!(
+        "longest      {} ({} words)\n",
+        longest.name, longest.words
+    ));
+    report.push_str(&format!(
+        "first post   {}\nlatest post  {}\n",
+        first.format("%Y-%m-%d"),
+        latest.format("%Y-%m-%d")
+    ));
+
+    posts_per_month(cfg, metas, &mut report);
+    top_tags(metas, &mut report);
+    longest_gaps(metas, &mut report);
+    report
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::assets;
+use crate::cache::Hasher;
+use crate::config::Config;
+use crate::content::{ContentFormat, ContentMeta, TitleScan};
+use crate::emoji::BodyScan;
+use crate::frontmatter::FrontMatter;
+use crate::notebook;
+use crate::toc::HeadingCount;
+
+// The most of a line read at once; longer lines, like embedded data, come in several pieces.
+const PIECE: usize = 64 * 1024;
+// How far the front matter may run before the file is given up on.
+const HEAD_LIMIT: usize = 1024 * 1024;
+
+// Stands in for the body in a streamed page's template until the body is copied over it.
+pub const BODY: &str = "\u{0}webweaver-body\u{0}";
+
+pub struct Piece {
+    pub text: String,
+    pub line_start: bool,
+    pub line_end: bool,
+}
+
+// A file's lines, each in one piece or, past PIECE bytes, several.
+pub struct Pieces<R> {
+    reader: R,
+    line_start: bool,
+    // The first bytes of a character the last piece was cut through.
+    carry: Vec<u8>,
+}
+
+impl<R: BufRead> Pieces<R> {
+    fn new(reader: R) -> Pieces<R> {
+        Pieces {
+            reader,
+            line_start: true,
+            carry: Vec::new(),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<Option<Piece>> {
+        let mut bytes = std::mem::take(&mut self.carry);
+        let line_end = loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                if bytes.is_empty() {
+                    return Ok(None);
+                }
+                break true;
+            }
+            let room = available.len().min(PIECE - bytes.len());
+            match available[..room].iter().position(|byte| *byte == b'\n') {
+                Some(end) => {
+                    bytes.extend_from_slice(&available[..=end]);
+                    self.reader.consume(end + 1);
+                    break true;
+                }
+                None => {
+                    bytes.extend_from_slice(&available[..room]);
+                    self.reader.consume(room);
+                    if bytes.len() == PIECE {
+                        break false;
+                    }
+                }
+            }
+        };
+        if !line_end {
+            if let Err(err) = std::str::from_utf8(&bytes) {
+                if err.error_len().is_none() {
+                    self.carry = bytes.split_off(err.valid_up_to());
+                }
+            }
+        }
+        let text = String::from_utf8(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let line_start = self.line_start;
+        self.line_start = line_end;
+        Ok(Some(Piece {
+            text,
+            line_start,
+            line_end,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for Pieces<R> {
+    type Item = io::Result<Piece>;
+
+    fn next(&mut self) -> Option<io::Result<Piece>> {
+        self.read().transpose()
+    }
+}
+
+// Any text file in pieces, for scans that look at a line at a time.
+pub fn lines(path: &Path) -> io::Result<Pieces<BufReader<File>>> {
+    Ok(Pieces::new(BufReader::new(File::open(path)?)))
+}
+
+// The front matter and the blank lines after it, up to and including the first body line;
+// without front matter, just the first line.
+fn read_head(pieces: &mut Pieces<impl BufRead>, format: ContentFormat) -> io::Result<String> {
+    let mut head = String::new();
+    let mut fence: Option<&str> = None;
+    let mut closed = false;
+    for piece in pieces {
+        let piece = piece?;
+        head.push_str(&piece.text);
+        let open = fence.is_some() && !closed;
+        if head.len() > HEAD_LIMIT {
+            if open {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "front matter is not closed within its first 1 MiB.",
+                ));
+            }
+            break;
+        }
+        if !piece.line_start {
+            if open {
+                continue;
+            }
+            break;
+        }
+
+        let line = piece.text.trim_start_matches('\u{feff}').trim_end();
+        if head.len() == piece.text.len() {
+            fence = ["---", "+++"].into_iter().find(|fence| line == *fence);
+            if fence.is_some() {
+                continue;
+            }
+        }
+        if open {
+            closed = fence == Some(line);
+            continue;
+        }
+        // Blank lines after the front matter, and Org keywords, still belong to the head.
+        let keyword = format == ContentFormat::Org && fence.is_none() && line.starts_with("#+");
+        if !piece.line_end || !(line.is_empty() || keyword) {
+            break;
+        }
+    }
+    Ok(head)
+}
+
+// A content body read from a file or, for a notebook, from memory.
+pub type Body = Pieces<Box<dyn BufRead>>;
+
+// A content file's front matter, and its body as split_front_matter leaves it, in pieces.
+// A notebook is converted whole and read from memory.
+pub fn open(path: &Path, format: ContentFormat) -> io::Result<(Option<FrontMatter>, Body)> {
+    if notebook::is_notebook(path) {
+        let source = notebook::read_source(path)?;
+        let (front_matter, body) = format.split_front_matter(&source)?;
+        let offset = (source.len() - body.len()) as u64;
+        let mut reader = Cursor::new(source.into_bytes());
+        reader.set_position(offset);
+        return Ok((front_matter, Pieces::new(Box::new(reader))));
+    }
+    let mut pieces = Pieces::new(BufReader::new(File::open(path)?));
+    let head = read_head(&mut pieces, format)?;
+    let (front_matter, body) = format.split_front_matter(&head)?;
+    let mut reader = pieces.reader;
+    reader.seek(SeekFrom::Start((head.len() - body.len()) as u64))?;
+    Ok((front_matter, Pieces::new(Box::new(reader))))
+}
+
+// Posts over [build] stream_threshold_kb; AsciiDoc ones have their pages copied together a line at a time,
+// while Markdown and Org ones are still converted whole.
+pub fn is_streamed(cfg: &Config, meta: &ContentMeta, source: &Path) -> bool {
+    cfg.build.stream_threshold > 0
+        && !meta.protected
+        && meta.attachment.is_none()
+        && fs::metadata(source).is_ok_and(|metadata| metadata.len() >= cfg.build.stream_threshold)
+}
+
+pub fn adoc_headings(meta: &ContentMeta, source: &Path) -> io::Result<usize> {
+    let (_, body) = open(source, meta.format)?;
+    let mut count = HeadingCount::default();
+    for piece in body {
+        let piece = piece?;
+        if piece.line_start {
+            count.line(&piece.text);
+        }
+    }
+    Ok(count.headings)
+}
+
+struct Output {
+    file: BufWriter<File>,
+    hasher: Hasher,
+}
+
+impl Output {
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.hasher.update(text.as_bytes());
+        self.file.write_all(text.as_bytes())
+    }
+}
+
+// The body as post_body gives it, with asset references rewritten line by line;
+// pieces of overlong lines are copied as they are.
+fn copy_body(
+    cfg: &Config,
+    meta: &ContentMeta,
+    source: &Path,
+    output: &mut Output,
+    assets: &mut BTreeSet<PathBuf>,
+) -> io::Result<()> {
+    let (_, body) = open(source, meta.format)?;
+    let mut title = meta.title_heading.then(|| TitleScan::new(meta.format));
+    let (mut in_heading, mut after_heading) = (false, false);
+    let mut emoji = cfg.build.emoji.then(BodyScan::default);
+    for piece in body {
+        let piece = piece?;
+        if piece.line_start
+            && title
+                .as_mut()
+                .is_some_and(|scan| scan.line(&piece.text).is_some())
+        {
+            title = None;
+            in_heading = true;
+        }
+        if in_heading {
+            if piece.line_end {
+                in_heading = false;
+                after_heading = true;
+            }
+            continue;
+        }
+        if after_heading && piece.line_end && piece.text.trim_start_matches(['\r', '\n']).is_empty()
+        {
+            continue;
+        }
+        after_heading = false;
+
+        if piece.line_start && piece.line_end {
+            let text = match emoji.as_mut() {
+                Some(scan) => scan.line(meta.format, &piece.text),
+                None => piece.text,
+            };
+            let (line, line_assets) = assets::rewrite_references(cfg, meta, source, &text, false);
+            assets.extend(line_assets);
+            output.write(&line)?;
+        } else {
+            output.write(&piece.text)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_partial(
+    cfg: &Config,
+    meta: &ContentMeta,
+    source: &Path,
+    page: &str,
+    partial: &Path,
+    assets: &mut BTreeSet<PathBuf>,
+) -> io::Result<String> {
+    let mut output = Output {
+        file: BufWriter::new(File::create(partial)?),
+        hasher: Hasher::default(),
+    };
+    match page.split_once(BODY) {
+        Some((before, after)) => {
+            output.write(before)?;
+            copy_body(cfg, meta, source, &mut output, assets)?;
+            output.write(after)?;
+        }
+        None => output.write(page)?,
+    }
+    output.file.flush()?;
+    Ok(output.hasher.finish())
+}
+
+// Writes a page rendered with BODY for its body, copying the body from source as it goes,
+// and moves it into place unless fresh says the page on disk already has its hash.
+// Returns the hash, the assets the body references and whether the page was written.
+pub fn write_page(
+    cfg: &Config,
+    meta: &ContentMeta,
+    source: &Path,
+    page: &str,
+    path: &Path,
+    fresh: impl FnOnce(&str) -> bool,
+) -> io::Result<(String, BTreeSet<PathBuf>, bool)> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let partial = path.with_file_name(format!(".{}.webweaver-partial", name));
+    let mut assets = BTreeSet::new();
+    let hash = match write_partial(cfg, meta, source, page, &partial, &mut assets) {
+        Ok(hash) => hash,
+        Err(err) => {
+            let _ = fs::remove_file(&partial);
+            return Err(err);
+        }
+    };
+    if fresh(&hash) {
+        fs::remove_file(&partial)?;
+        return Ok((hash, assets, false));
+    }
+    fs::rename(&partial, path)?;
+    Ok((hash, assets, true))
+}
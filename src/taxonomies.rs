@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{Config, TaxonomyConfig, SERIES, TAGS};
+use crate::content::{
+    adoc_prologue, base_path, friendly_filename, index_entry, newest_first, same_day_order,
+    ContentMeta, ContentUnit,
+};
+use crate::error::WebWeaverError;
+use crate::feed;
+use crate::template::context_value;
+use crate::url;
+use crate::value::Value;
+
+const MAX_WEIGHT: usize = 5;
+
+pub struct Term<'a, T> {
+    pub name: String,
+    pub entries: Vec<&'a T>,
+}
+
+fn taxonomy_dir(cfg: &Config, taxonomy: &str) -> String {
+    cfg.category_path(taxonomy)
+}
+
+pub fn term_slug(term: &str) -> String {
+    friendly_filename(term)
+}
+
+fn term_dir(cfg: &Config, taxonomy: &str, term: &str) -> String {
+    format!("{}/{}", taxonomy_dir(cfg, taxonomy), term_slug(term))
+}
+
+pub fn term_index_path(cfg: &Config, taxonomy: &str, term: &str) -> String {
+    format!("{}/index.adoc", term_dir(cfg, taxonomy, term))
+}
+
+pub fn taxonomy_index_path(cfg: &Config, taxonomy: &str) -> String {
+    format!("{}/index.adoc", taxonomy_dir(cfg, taxonomy))
+}
+
+// The post's terms; tags and series keep fields of their own, the rest come by name from the front matter.
+pub fn post_terms<'a>(taxonomy: &TaxonomyConfig, meta: &'a ContentMeta) -> &'a [String] {
+    match taxonomy.name.as_str() {
+        TAGS => &meta.tags,
+        SERIES => meta.series.as_slice(),
+        name => meta.taxonomies.get(name).map_or(&[], Vec::as_slice),
+    }
+}
+
+// The taxonomy's terms by slug, each with its posts, newest first or, when ordered, oldest first.
+pub fn term_map<'a, T: 'a>(
+    taxonomy: &TaxonomyConfig,
+    posts: impl IntoIterator<Item = &'a T>,
+    meta: impl Fn(&T) -> &ContentMeta,
+) -> BTreeMap<String, Term<'a, T>> {
+    let mut terms: BTreeMap<String, Term<'a, T>> = BTreeMap::new();
+
+    for post in posts {
+        for name in post_terms(taxonomy, meta(post)) {
+            let slug = term_slug(name);
+            if slug.is_empty() {
+                continue;
+            }
+            let term = terms.entry(slug).or_insert_with(|| Term {
+                name: name.clone(),
+                entries: Vec::new(),
+            });
+            if !term.entries.iter().any(|entry| std::ptr::eq(*entry, post)) {
+                term.entries.push(post);
+            }
+        }
+    }
+
+    for term in terms.values_mut() {
+        if taxonomy.ordered {
+            term.entries.sort_by(|a, b| {
+                let (a, b) = (meta(a), meta(b));
+                a.date.cmp(&b.date).then_with(|| same_day_order(a, b))
+            });
+        } else {
+            term.entries.sort_by(|a, b| newest_first(meta(a), meta(b)));
+        }
+    }
+
+    terms
+}
+
+fn heading(taxonomy: &TaxonomyConfig, name: &str) -> String {
+    match &taxonomy.icon {
+        Some(icon) => format!("{} {}", icon, name),
+        None => format!("{}: {}", taxonomy.title, name),
+    }
+}
+
+fn term_page(cfg: &Config, taxonomy: &TaxonomyConfig, term: &Term<ContentUnit>) -> String {
+    let base_path = base_path(&term_dir(cfg, &taxonomy.name, &term.name));
+
+    let mut page = format!(
+        "{}== {}\n\n",
+        adoc_prologue(cfg, &base_path),
+        heading(taxonomy, &term.name)
+    );
+    page.push_str(&format!(
+        "xref:../index.adoc[All {}]\n\n",
+        taxonomy.title.to_lowercase()
+    ));
+
+    if taxonomy.feeds {
+        page.push_str(&feed::listing_feed_links(cfg));
+    }
+
+    for (i, unit) in term.entries.iter().enumerate() {
+        let entry = index_entry(cfg, &unit.meta, &format!("{}/", base_path));
+        if taxonomy.ordered {
+            page.push_str(&format!("==== {}. {}\n\n", i + 1, entry));
+        } else {
+            page.push_str(&format!("==== {}\n\n", entry));
+        }
+    }
+
+    page
+}
+
+// Links to each term, with a tag-weight-1 … tag-weight-5 role by its post count.
+fn term_cloud(terms: &BTreeMap<String, Term<ContentUnit>>) -> String {
+    let max = terms
+        .values()
+        .map(|term| term.entries.len())
+        .max()
+        .unwrap_or(1);
+
+    let links: Vec<String> = terms
+        .iter()
+        .map(|(slug, term)| {
+            let count = term.entries.len();
+            let weight = if max > 1 {
+                1 + (count - 1) * (MAX_WEIGHT - 1) / (max - 1)
+            } else {
+                1
+            };
+            format!(
+                "[.tag-weight-{}]#xref:{}/index.adoc[{} ({})]#",
+                weight, slug, term.name, count
+            )
+        })
+        .collect();
+    links.join(" ") + "\n"
+}
+
+fn taxonomy_page(
+    cfg: &Config,
+    taxonomy: &TaxonomyConfig,
+    terms: &BTreeMap<String, Term<ContentUnit>>,
+) -> String {
+    let base_path = base_path(&taxonomy_dir(cfg, &taxonomy.name));
+    let title = match &taxonomy.icon {
+        Some(icon) => format!("{} {}", icon, taxonomy.title),
+        None => taxonomy.title.clone(),
+    };
+
+    let mut page = format!("{}== {}\n\n", adoc_prologue(cfg, &base_path), title);
+
+    if taxonomy.cloud {
+        page.push_str(&term_cloud(terms));
+        return page;
+    }
+
+    for (slug, term) in terms {
+        page.push_str(&format!(
+            "* xref:{}/index.adoc[{}] ({})\n",
+            slug,
+            term.name,
+            term.entries.len()
+        ));
+    }
+
+    page
+}
+
+// A page per term of every taxonomy, tags and series included, with its feeds,
+// and a page listing each taxonomy's terms.
+pub fn write_taxonomy_pages(
+    cfg: &Config,
+    content: &[ContentUnit],
+) -> Result<Vec<PathBuf>, WebWeaverError> {
+    let mut written = Vec::new();
+    for taxonomy in &cfg.taxonomies {
+        let terms = term_map(taxonomy, content, |unit| &unit.meta);
+        if terms.is_empty() {
+            continue;
+        }
+
+        for term in terms.values() {
+            let path = cfg
+                .output_root_path
+                .join(term_index_path(cfg, &taxonomy.name, &term.name));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, term_page(cfg, taxonomy, term))?;
+            written.push(path);
+
+            if taxonomy.feeds {
+                let title = format!(
+                    "{} \u{2014} {}: {}",
+                    cfg.site.title, taxonomy.title, term.name
+                );
+                let page_url = url::join(
+                    &cfg.site.base_url,
+                    &term_index_path(cfg, &taxonomy.name, &term.name),
+                );
+                written.extend(feed::write_listing_feeds(
+                    cfg,
+                    &term.entries,
+                    &format!("{}/{}", taxonomy.name, term_slug(&term.name)),
+                    &title,
+                    &page_url,
+                )?);
+            }
+        }
+
+        let path = cfg
+            .output_root_path
+            .join(taxonomy_index_path(cfg, &taxonomy.name));
+        fs::write(&path, taxonomy_page(cfg, taxonomy, &terms))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+// Each taxonomy with terms among the posts, for the index: its name, title and url,
+// and its terms with name, url and the number of posts. Tags and series have links of their own.
+pub fn index_values(cfg: &Config, posts: &[&ContentMeta], prefix: &str) -> Vec<Value> {
+    cfg.taxonomies
+        .iter()
+        .filter(|taxonomy| taxonomy.name != TAGS && taxonomy.name != SERIES)
+        .filter_map(|taxonomy| {
+            let terms = term_map(taxonomy, posts, |post| *post);
+            if terms.is_empty() {
+                return None;
+            }
+            let terms = terms
+                .values()
+                .map(|term| {
+                    context_value([
+                        ("name", Value::String(term.name.clone())),
+                        (
+                            "url",
+                            Value::String(format!(
+                                "{}{}",
+                                prefix,
+                                term_index_path(cfg, &taxonomy.name, &term.name)
+                            )),
+                        ),
+                        ("posts", Value::Integer(term.entries.len() as i64)),
+                    ])
+                })
+                .collect();
+            Some(context_value([
+                ("name", Value::String(taxonomy.name.clone())),
+                ("title", Value::String(taxonomy.title.clone())),
+                (
+                    "url",
+                    Value::String(format!(
+                        "{}{}",
+                        prefix,
+                        taxonomy_index_path(cfg, &taxonomy.name)
+                    )),
+                ),
+                ("terms", Value::Array(terms)),
+            ]))
+        })
+        .collect()
+}
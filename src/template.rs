@@ -0,0 +1,522 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::cache::Hasher;
+use crate::json;
+use crate::markdown::escape_html;
+use crate::shortcodes::{self, Shortcodes};
+use crate::value::{Table, Value};
+
+const CONTENT_ADOC: &str = include_str!("templates/content.adoc");
+const CONTENT_HTML: &str = include_str!("templates/content.html");
+const INDEX_ADOC: &str = include_str!("templates/index.adoc");
+const FEED_DESCRIPTION: &str = include_str!("templates/feed_description.txt");
+const OPENGRAPH_HTML: &str = include_str!("templates/opengraph.html");
+const REDIRECT_HTML: &str = include_str!("templates/redirect.html");
+const REDIRECT_ADOC: &str = include_str!("templates/redirect.adoc");
+const COMMENTS_HTML: &str = include_str!("templates/comments.html");
+const PROTECTED_HTML: &str = include_str!("templates/protected.html");
+const ATTACHMENT_ADOC: &str = include_str!("templates/attachment.adoc");
+const ARCHETYPE_ADOC: &str = include_str!("templates/archetype.adoc");
+const ARCHETYPE_MD: &str = include_str!("templates/archetype.md");
+const ARCHETYPE_ORG: &str = include_str!("templates/archetype.org");
+
+#[derive(Debug)]
+enum Filter {
+    Escape,
+    Trim,
+    Upper,
+    Lower,
+    Quote,
+    Join(String),
+}
+
+#[derive(Debug)]
+struct Expr {
+    path: Vec<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Expr(Expr),
+    If {
+        negate: bool,
+        condition: Vec<String>,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    For {
+        name: String,
+        iterable: Vec<String>,
+        body: Vec<Node>,
+    },
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Expr(&'a str),
+    Tag(&'a str),
+}
+
+pub struct Template {
+    nodes: Vec<Node>,
+    // Variables every render sees unless its context has its own: data, the site's data files.
+    globals: Arc<Table>,
+}
+
+pub struct Templates {
+    pub content_adoc: Template,
+    pub content_html: Template,
+    pub index: Template,
+    pub feed_description: Template,
+    pub opengraph: Template,
+    pub redirect: Template,
+    pub redirect_adoc: Template,
+    pub comments: Template,
+    pub protected: Template,
+    pub attachment: Template,
+    pub shortcodes: Shortcodes,
+}
+
+fn template_err(name: &str, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("template {}: {}", name, msg),
+    )
+}
+
+fn tokens<'a>(name: &str, source: &'a str) -> io::Result<Vec<Token<'a>>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    while !rest.is_empty() {
+        let next = [rest.find("{{"), rest.find("{%")]
+            .into_iter()
+            .flatten()
+            .min();
+        let start = match next {
+            Some(start) => start,
+            None => {
+                tokens.push(Token::Text(rest));
+                break;
+            }
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+
+        let is_expr = rest[start..].starts_with("{{");
+        let close = if is_expr { "}}" } else { "%}" };
+        let inner_start = start + 2;
+        let end = match rest[inner_start..].find(close) {
+            Some(end) => inner_start + end,
+            None => {
+                return Err(template_err(
+                    name,
+                    &format!("unclosed {}", &rest[start..start + 2]),
+                ))
+            }
+        };
+
+        let inner = rest[inner_start..end].trim();
+        tokens.push(if is_expr {
+            Token::Expr(inner)
+        } else {
+            Token::Tag(inner)
+        });
+        rest = &rest[end + 2..];
+    }
+
+    Ok(tokens)
+}
+
+fn path(name: &str, text: &str) -> io::Result<Vec<String>> {
+    let path: Vec<String> = text.trim().split('.').map(String::from).collect();
+    let valid = path.iter().all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+    if !valid {
+        return Err(template_err(name, &format!("invalid variable {:?}", text)));
+    }
+    Ok(path)
+}
+
+fn filter(name: &str, text: &str) -> io::Result<Filter> {
+    let text = text.trim();
+    let (filter_name, arg) = match text.find('(') {
+        Some(open) => {
+            let arg = text[open + 1..]
+                .strip_suffix(')')
+                .map(str::trim)
+                .and_then(|arg| {
+                    arg.strip_prefix('"')
+                        .and_then(|arg| arg.strip_suffix('"'))
+                        .or_else(|| {
+                            arg.strip_prefix('\'')
+                                .and_then(|arg| arg.strip_suffix('\''))
+                        })
+                })
+                .ok_or_else(|| {
+                    template_err(name, &format!("invalid filter argument in {}", text))
+                })?;
+            (text[..open].trim(), Some(arg))
+        }
+        None => (text, None),
+    };
+
+    match (filter_name, arg) {
+        ("escape", None) => Ok(Filter::Escape),
+        ("trim", None) => Ok(Filter::Trim),
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        ("quote", None) => Ok(Filter::Quote),
+        ("join", Some(separator)) => Ok(Filter::Join(separator.to_string())),
+        _ => Err(template_err(name, &format!("unknown filter {}", text))),
+    }
+}
+
+fn expr(name: &str, text: &str) -> io::Result<Expr> {
+    let mut parts = text.split('|');
+    let path = path(name, parts.next().unwrap_or(""))?;
+    let filters = parts
+        .map(|part| filter(name, part))
+        .collect::<io::Result<Vec<Filter>>>()?;
+    Ok(Expr { path, filters })
+}
+
+fn parse_nodes<'a, I>(name: &str, tokens: &mut I, ends: &[&str]) -> io::Result<(Vec<Node>, String)>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text.to_string())),
+            Token::Expr(text) => nodes.push(Node::Expr(expr(name, text)?)),
+            Token::Tag(tag) if ends.contains(&tag) => return Ok((nodes, tag.to_string())),
+            Token::Tag(tag) => {
+                let (keyword, rest) = tag.split_once(' ').unwrap_or((tag, ""));
+                match keyword {
+                    "if" => {
+                        let (negate, condition) = match rest.trim().strip_prefix("not ") {
+                            Some(condition) => (true, condition),
+                            None => (false, rest),
+                        };
+                        let condition = path(name, condition)?;
+                        let (then, end) = parse_nodes(name, tokens, &["else", "endif"])?;
+                        let otherwise = if end == "else" {
+                            parse_nodes(name, tokens, &["endif"])?.0
+                        } else {
+                            Vec::new()
+                        };
+                        nodes.push(Node::If {
+                            negate,
+                            condition,
+                            then,
+                            otherwise,
+                        });
+                    }
+                    "for" => {
+                        let (variable, iterable) = rest.split_once(" in ").ok_or_else(|| {
+                            template_err(name, &format!("invalid tag {{% {} %}}", tag))
+                        })?;
+                        let variable = variable.trim();
+                        if path(name, variable)?.len() != 1 || variable == "loop" {
+                            return Err(template_err(
+                                name,
+                                &format!("invalid loop variable {}", variable),
+                            ));
+                        }
+                        let iterable = path(name, iterable)?;
+                        let (body, _) = parse_nodes(name, tokens, &["endfor"])?;
+                        nodes.push(Node::For {
+                            name: variable.to_string(),
+                            iterable,
+                            body,
+                        });
+                    }
+                    _ => {
+                        return Err(template_err(
+                            name,
+                            &format!("unexpected tag {{% {} %}}", tag),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+
+    match ends.last() {
+        Some(end) => Err(template_err(name, &format!("missing {{% {} %}}", end))),
+        None => Ok((nodes, String::new())),
+    }
+}
+
+fn lookup<'a>(
+    scopes: &'a [(String, Value)],
+    context: &'a Table,
+    globals: &'a Table,
+    path: &[String],
+) -> &'a Value {
+    const NULL: &Value = &Value::Null;
+
+    let root = scopes
+        .iter()
+        .rev()
+        .find(|(name, _)| *name == path[0])
+        .map(|(_, value)| value)
+        .or_else(|| context.get(&path[0]))
+        .or_else(|| globals.get(&path[0]));
+
+    path[1..]
+        .iter()
+        .try_fold(root.unwrap_or(NULL), |value, key| value.get(key))
+        .unwrap_or(NULL)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Boolean(b) => *b,
+        Value::String(s) => !s.is_empty(),
+        Value::Integer(i) => *i != 0,
+        Value::Float(f) => *f != 0.0,
+        Value::Array(values) => !values.is_empty(),
+        Value::Table(table) => !table.is_empty(),
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null | Value::Table(_) => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Array(values) => values
+            .iter()
+            .map(to_text)
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+fn apply(value: &Value, filters: &[Filter]) -> String {
+    let mut text = match (value, filters.first()) {
+        (Value::Array(values), Some(Filter::Join(separator))) => values
+            .iter()
+            .map(to_text)
+            .collect::<Vec<String>>()
+            .join(separator),
+        _ => to_text(value),
+    };
+
+    for filter in filters {
+        text = match filter {
+            Filter::Escape => escape_html(&text),
+            Filter::Trim => text.trim().to_string(),
+            Filter::Upper => text.to_uppercase(),
+            Filter::Lower => text.to_lowercase(),
+            Filter::Quote => json::escape(&text),
+            Filter::Join(_) => text,
+        };
+    }
+
+    text
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    context: &Table,
+    globals: &Table,
+    scopes: &mut Vec<(String, Value)>,
+    out: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Expr(expr) => out.push_str(&apply(
+                lookup(scopes, context, globals, &expr.path),
+                &expr.filters,
+            )),
+            Node::If {
+                negate,
+                condition,
+                then,
+                otherwise,
+            } => {
+                let truthy = is_truthy(lookup(scopes, context, globals, condition)) != *negate;
+                render_nodes(
+                    if truthy { then } else { otherwise },
+                    context,
+                    globals,
+                    scopes,
+                    out,
+                );
+            }
+            Node::For {
+                name,
+                iterable,
+                body,
+            } => {
+                let values = match lookup(scopes, context, globals, iterable) {
+                    Value::Array(values) => values.clone(),
+                    _ => Vec::new(),
+                };
+                for (i, value) in values.iter().enumerate() {
+                    let loop_value = context_value([
+                        ("index", Value::Integer(i as i64 + 1)),
+                        ("first", Value::Boolean(i == 0)),
+                        ("last", Value::Boolean(i + 1 == values.len())),
+                    ]);
+                    scopes.push((String::from("loop"), loop_value));
+                    scopes.push((name.clone(), value.clone()));
+                    render_nodes(body, context, globals, scopes, out);
+                    scopes.truncate(scopes.len() - 2);
+                }
+            }
+        }
+    }
+}
+
+pub fn context<const N: usize>(pairs: [(&str, Value); N]) -> Table {
+    pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
+
+pub fn context_value<const N: usize>(pairs: [(&str, Value); N]) -> Value {
+    Value::Table(context(pairs))
+}
+
+pub fn optional(value: Option<&str>) -> Value {
+    match value {
+        Some(s) => Value::String(s.to_string()),
+        None => Value::Null,
+    }
+}
+
+pub fn strings(values: &[String]) -> Value {
+    Value::Array(values.iter().cloned().map(Value::String).collect())
+}
+
+impl Template {
+    pub fn parse(name: &str, source: &str) -> io::Result<Template> {
+        // Like Jinja, drop the single newline that ends the template file.
+        let source = source
+            .strip_suffix("\r\n")
+            .or_else(|| source.strip_suffix('\n'))
+            .unwrap_or(source);
+        let mut tokens = tokens(name, source)?.into_iter();
+        let (nodes, _) = parse_nodes(name, &mut tokens, &[])?;
+        Ok(Template {
+            nodes,
+            globals: Arc::default(),
+        })
+    }
+
+    pub fn render(&self, context: &Table) -> String {
+        let mut out = String::with_capacity(4096);
+        render_nodes(
+            &self.nodes,
+            context,
+            &self.globals,
+            &mut Vec::new(),
+            &mut out,
+        );
+        out
+    }
+
+    pub fn with_globals(self, globals: &Arc<Table>) -> Template {
+        Template {
+            globals: Arc::clone(globals),
+            ..self
+        }
+    }
+}
+
+fn load(dir: Option<&Path>, name: &str, default: &str) -> io::Result<Template> {
+    if let Some(path) = dir.map(|dir| dir.join(name)).filter(|path| path.exists()) {
+        let source = fs::read_to_string(&path)?;
+        return Template::parse(&path.to_string_lossy(), &source);
+    }
+    Template::parse(name, default)
+}
+
+// archetypes/<category>.<ext> for the category's posts, else archetypes/default.<ext>.
+pub fn archetype(dir: Option<&Path>, category: &str, file_ext: &str) -> io::Result<Template> {
+    let default = match file_ext {
+        "md" => ARCHETYPE_MD,
+        "org" => ARCHETYPE_ORG,
+        _ => ARCHETYPE_ADOC,
+    };
+    let name = format!("{}.{}", category, file_ext);
+    if let Some(path) = dir
+        .filter(|_| !category.is_empty())
+        .map(|dir| dir.join(&name))
+        .filter(|path| path.is_file())
+    {
+        let source = fs::read_to_string(&path)?;
+        return Template::parse(&path.to_string_lossy(), &source);
+    }
+    load(dir, &format!("default.{}", file_ext), default)
+}
+
+impl Templates {
+    // data, the site's data files, is a variable of every template and shortcode.
+    pub fn load(dir: Option<&Path>, data: Table) -> io::Result<Templates> {
+        let globals = Arc::new(context([("data", Value::Table(data))]));
+        let template = |name: &str, default: &str| {
+            load(dir, name, default).map(|template| template.with_globals(&globals))
+        };
+        Ok(Templates {
+            content_adoc: template("content.adoc", CONTENT_ADOC)?,
+            content_html: template("content.html", CONTENT_HTML)?,
+            index: template("index.adoc", INDEX_ADOC)?,
+            feed_description: template("feed_description.txt", FEED_DESCRIPTION)?,
+            opengraph: template("opengraph.html", OPENGRAPH_HTML)?,
+            redirect: template("redirect.html", REDIRECT_HTML)?,
+            redirect_adoc: template("redirect.adoc", REDIRECT_ADOC)?,
+            comments: template("comments.html", COMMENTS_HTML)?,
+            protected: template("protected.html", PROTECTED_HTML)?,
+            attachment: template("attachment.adoc", ATTACHMENT_ADOC)?,
+            shortcodes: shortcodes::load(dir, &globals)?,
+        })
+    }
+
+    // Changes with any template, shortcode or data file, for the build cache.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Hasher::default();
+        for template in [
+            &self.content_adoc,
+            &self.content_html,
+            &self.index,
+            &self.feed_description,
+            &self.opengraph,
+            &self.redirect,
+            &self.redirect_adoc,
+            &self.comments,
+            &self.protected,
+            &self.attachment,
+        ]
+        .into_iter()
+        .chain(self.shortcodes.values())
+        {
+            hasher.update(format!("{:?}", template.nodes).as_bytes());
+        }
+        for name in self.shortcodes.keys() {
+            hasher.update(name.as_bytes());
+        }
+        hasher.update(format!("{:?}", self.content_adoc.globals).as_bytes());
+        hasher.finish()
+    }
+}
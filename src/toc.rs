@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+
+use crate::config::Config;
+use crate::content::{friendly_filename, ContentMeta};
+
+struct Heading {
+    level: usize,
+    id: String,
+    html: String,
+}
+
+// Front matter toc: true or false decides; otherwise posts with enough headings get one.
+pub fn wants_toc(cfg: &Config, meta: &ContentMeta, headings: usize) -> bool {
+    meta.toc
+        .unwrap_or(cfg.toc.enabled && headings >= cfg.toc.min_headings.max(1))
+}
+
+// Section titles (== to ======) outside listing, literal and fenced blocks, counted a line at a time.
+#[derive(Default)]
+pub struct HeadingCount {
+    fence: Option<String>,
+    pub headings: usize,
+}
+
+impl HeadingCount {
+    pub fn line(&mut self, line: &str) {
+        let line = line.trim_end();
+        if let Some(open) = &self.fence {
+            if line == open {
+                self.fence = None;
+            }
+            return;
+        }
+        if matches!(line, "----" | "...." | "```" | "////" | "++++") {
+            self.fence = Some(line.to_string());
+            return;
+        }
+        let marks = line.chars().take_while(|c| *c == '=').count();
+        if (2..=6).contains(&marks) && line[marks..].starts_with(' ') {
+            self.headings += 1;
+        }
+    }
+}
+
+pub fn adoc_headings(body: &str) -> usize {
+    let mut count = HeadingCount::default();
+    for line in body.lines() {
+        count.line(line);
+    }
+    count.headings
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn heading_id(html: &str, ids: &mut BTreeSet<String>) -> String {
+    let text = strip_tags(html);
+    let text: String = text
+        .split('&')
+        .enumerate()
+        .map(|(i, part)| match part.split_once(';') {
+            Some((_, rest)) if i > 0 => rest,
+            _ => part,
+        })
+        .collect();
+    let mut base = friendly_filename(text.trim());
+    if base.is_empty() {
+        base = String::from("section");
+    }
+    let mut id = base.clone();
+    let mut n = 2;
+    while ids.contains(&id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    ids.insert(id.clone());
+    id
+}
+
+// Gives every <hN> in the rendered body an id and collects them in order.
+fn anchor_headings(html: &str) -> (String, Vec<Heading>) {
+    let mut out = String::with_capacity(html.len() + 256);
+    let mut headings = Vec::new();
+    let mut ids = BTreeSet::new();
+    let mut rest = html;
+    while let Some(at) = rest.find("<h") {
+        let after = &rest[at + 2..];
+        let level = after.chars().next().and_then(|c| c.to_digit(10));
+        let (level, close) = match level {
+            Some(level @ 1..=6) if after[1..].starts_with('>') => {
+                (level as usize, format!("</h{}>", level))
+            }
+            _ => {
+                out.push_str(&rest[..at + 2]);
+                rest = after;
+                continue;
+            }
+        };
+        let inner_start = at + 4;
+        let inner_end = match rest[inner_start..].find(&close) {
+            Some(end) => inner_start + end,
+            None => break,
+        };
+        let inner = &rest[inner_start..inner_end];
+        let id = heading_id(inner, &mut ids);
+        out.push_str(&rest[..at]);
+        out.push_str(&format!("<h{} id=\"{}\">{}", level, id, inner));
+        headings.push(Heading {
+            level,
+            id,
+            html: strip_tags(inner),
+        });
+        rest = &rest[inner_end..];
+    }
+    out.push_str(rest);
+    (out, headings)
+}
+
+fn toc_nav(headings: &[Heading], levels: usize) -> String {
+    let top = match headings.iter().map(|heading| heading.level).min() {
+        Some(top) => top,
+        None => return String::new(),
+    };
+    // A nested list opens inside the previous item; skipped levels nest only one deeper.
+    let mut nav = String::from("<nav class=\"toc\">\n<ul>\n");
+    let mut depth = 0;
+    let mut first = true;
+    for heading in headings
+        .iter()
+        .filter(|heading| heading.level < top + levels)
+    {
+        let level = heading.level - top;
+        if first {
+            first = false;
+        } else if level > depth {
+            nav.push_str("\n<ul>\n");
+            depth += 1;
+        } else {
+            nav.push_str("</li>\n");
+            while depth > level {
+                nav.push_str("</ul>\n</li>\n");
+                depth -= 1;
+            }
+        }
+        nav.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            heading.id, heading.html
+        ));
+    }
+    nav.push_str("</li>\n");
+    while depth > 0 {
+        nav.push_str("</ul>\n</li>\n");
+        depth -= 1;
+    }
+    nav.push_str("</ul>\n</nav>\n");
+    nav
+}
+
+// Puts the nav above the body and anchors the headings it links to; posts without one are left alone.
+pub fn with_html_toc(cfg: &Config, meta: &ContentMeta, body: String) -> String {
+    let (anchored, headings) = anchor_headings(&body);
+    if !wants_toc(cfg, meta, headings.len()) {
+        return body;
+    }
+    format!("{}{}", toc_nav(&headings, cfg.toc.levels), anchored)
+}
@@ -0,0 +1,442 @@
+use std::io;
+
+use crate::value::{Table, Value};
+
+fn parse_err(line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("toml parse error at line {}: {}", line, msg),
+    )
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied();
+        if let Some(c) = c {
+            self.pos += 1;
+            if c == '\n' {
+                self.line += 1;
+            }
+        }
+        c
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ' ' || c == '\t' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while let Some(c) = self.peek() {
+                if c == '\n' {
+                    break;
+                }
+                self.bump();
+            }
+        }
+    }
+
+    fn skip_whitespace_newlines_comments(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn expect_line_end(&mut self) -> io::Result<()> {
+        self.skip_whitespace();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.peek_at(1) == Some('\n') => Ok(()),
+            Some(c) => Err(parse_err(self.line, &format!("unexpected '{}'", c))),
+        }
+    }
+
+    fn key(&mut self) -> io::Result<Vec<String>> {
+        let mut parts = Vec::with_capacity(2);
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let mut bare = String::new();
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                            bare.push(c);
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    if bare.is_empty() {
+                        return Err(parse_err(self.line, "expected key"));
+                    }
+                    bare
+                }
+            };
+            parts.push(part);
+            self.skip_whitespace();
+            if self.peek() == Some('.') {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Ok(parts)
+    }
+
+    fn basic_string(&mut self) -> io::Result<String> {
+        if self.starts_with("\"\"\"") {
+            return self.multiline_basic_string();
+        }
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some('\n') | None => return Err(parse_err(self.line, "unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn multiline_basic_string(&mut self) -> io::Result<String> {
+        self.pos += 3;
+        if self.peek() == Some('\n') {
+            self.bump();
+        } else if self.starts_with("\r\n") {
+            self.bump();
+            self.bump();
+        }
+        let mut s = String::new();
+        loop {
+            if self.starts_with("\"\"\"") {
+                self.pos += 3;
+                return Ok(s);
+            }
+            match self.bump() {
+                Some('\\') => {
+                    if matches!(self.peek(), Some('\n') | Some('\r') | Some(' ')) {
+                        while matches!(
+                            self.peek(),
+                            Some('\n') | Some('\r') | Some(' ') | Some('\t')
+                        ) {
+                            self.bump();
+                        }
+                    } else {
+                        s.push(self.escape()?);
+                    }
+                }
+                Some(c) => s.push(c),
+                None => return Err(parse_err(self.line, "unterminated string")),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> io::Result<String> {
+        if self.starts_with("'''") {
+            self.pos += 3;
+            if self.peek() == Some('\n') {
+                self.bump();
+            }
+            let mut s = String::new();
+            loop {
+                if self.starts_with("'''") {
+                    self.pos += 3;
+                    return Ok(s);
+                }
+                match self.bump() {
+                    Some(c) => s.push(c),
+                    None => return Err(parse_err(self.line, "unterminated string")),
+                }
+            }
+        }
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(s),
+                Some('\n') | None => return Err(parse_err(self.line, "unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> io::Result<char> {
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => self.unicode_escape(4)?,
+            Some('U') => self.unicode_escape(8)?,
+            _ => return Err(parse_err(self.line, "invalid escape sequence")),
+        };
+        Ok(c)
+    }
+
+    fn unicode_escape(&mut self, len: usize) -> io::Result<char> {
+        let mut hex = String::with_capacity(len);
+        for _ in 0..len {
+            match self.bump() {
+                Some(c) => hex.push(c),
+                None => return Err(parse_err(self.line, "invalid unicode escape")),
+            }
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| parse_err(self.line, "invalid unicode escape"))
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err(parse_err(self.line, "expected value")),
+        }
+    }
+
+    fn array(&mut self) -> io::Result<Value> {
+        self.bump();
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace_newlines_comments();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_whitespace_newlines_comments();
+            match self.bump() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err(parse_err(self.line, "expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> io::Result<Value> {
+        self.bump();
+        let mut table = Table::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.bump();
+                return Ok(Value::Table(table));
+            }
+            let key = self.key()?;
+            self.skip_whitespace();
+            if self.bump() != Some('=') {
+                return Err(parse_err(self.line, "expected '=' in inline table"));
+            }
+            let value = self.value()?;
+            insert(&mut table, &key, value, self.line)?;
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return Err(parse_err(self.line, "expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    fn scalar(&mut self) -> io::Result<Value> {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            if c == ',' || c == ']' || c == '}' || c == '\n' || c == '\r' || c == '#' {
+                break;
+            }
+            token.push(c);
+            self.bump();
+        }
+        let token = token.trim_end();
+
+        match token {
+            "true" => return Ok(Value::Boolean(true)),
+            "false" => return Ok(Value::Boolean(false)),
+            _ => {}
+        }
+
+        let numeric = token.replace('_', "");
+        if let Ok(i) = numeric.parse::<i64>() {
+            return Ok(Value::Integer(i));
+        }
+        if numeric.contains(['.', 'e', 'E']) {
+            if let Ok(f) = numeric.parse::<f64>() {
+                return Ok(Value::Float(f));
+            }
+        }
+
+        // Dates and times are kept verbatim; callers parse them with chrono.
+        if token.starts_with(|c: char| c.is_ascii_digit()) && token.contains(['-', ':']) {
+            return Ok(Value::String(token.to_string()));
+        }
+
+        Err(parse_err(self.line, &format!("invalid value '{}'", token)))
+    }
+}
+
+fn table_at<'a>(root: &'a mut Table, path: &[String], line: usize) -> io::Result<&'a mut Table> {
+    let mut table = root;
+    for part in path {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match entry {
+            Value::Table(t) => t,
+            Value::Array(values) => match values.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return Err(parse_err(line, &format!("'{}' is not a table", part))),
+            },
+            _ => return Err(parse_err(line, &format!("'{}' is not a table", part))),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Table, key: &[String], value: Value, line: usize) -> io::Result<()> {
+    let (last, parents) = key.split_last().expect("key has at least one part");
+    let table = table_at(table, parents, line)?;
+    if table.insert(last.clone(), value).is_some() {
+        return Err(parse_err(
+            line,
+            &format!("duplicate key '{}'", key.join(".")),
+        ));
+    }
+    Ok(())
+}
+
+pub fn parse(input: &str) -> io::Result<Table> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = Table::new();
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        parser.skip_whitespace_newlines_comments();
+        match parser.peek() {
+            None => break,
+            Some('[') => {
+                let array_of_tables = parser.peek_at(1) == Some('[');
+                parser.bump();
+                if array_of_tables {
+                    parser.bump();
+                }
+                let path = parser.key()?;
+                let close = if array_of_tables { "]]" } else { "]" };
+                if !parser.starts_with(close) {
+                    return Err(parse_err(parser.line, "unterminated table header"));
+                }
+                parser.pos += close.len();
+                parser.expect_line_end()?;
+
+                if array_of_tables {
+                    let (last, parents) = path.split_last().expect("key has at least one part");
+                    let parent = table_at(&mut root, parents, parser.line)?;
+                    let entry = parent
+                        .entry(last.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    match entry {
+                        Value::Array(values) => values.push(Value::Table(Table::new())),
+                        _ => {
+                            return Err(parse_err(
+                                parser.line,
+                                &format!("'{}' is not an array", last),
+                            ))
+                        }
+                    }
+                } else {
+                    table_at(&mut root, &path, parser.line)?;
+                }
+                current = path;
+            }
+            Some(_) => {
+                let key = parser.key()?;
+                parser.skip_whitespace();
+                if parser.bump() != Some('=') {
+                    return Err(parse_err(parser.line, "expected '=' after key"));
+                }
+                let value = parser.value()?;
+                parser.expect_line_end()?;
+                let line = parser.line;
+                let table = table_at(&mut root, &current, line)?;
+                insert(table, &key, value, line)?;
+            }
+        }
+    }
+
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tables_and_arrays() {
+        let table = parse("title = \"T\"\n[site]\nport = 8080\ntags = [\"a\", \"b\"]\n").unwrap();
+        assert_eq!(table["title"].as_str(), Some("T"));
+        let site = &table["site"];
+        assert_eq!(site.get("port"), Some(&Value::Integer(8080)));
+        assert_eq!(
+            site.get("tags"),
+            Some(&Value::Array(vec![
+                Value::String(String::from("a")),
+                Value::String(String::from("b"))
+            ]))
+        );
+    }
+
+    #[test]
+    fn escaped_quotes_and_hashes_stay_in_strings() {
+        let table = parse("q = \"say \\\"hi\\\" # not a comment\"\nl = 'C:\\path'\n").unwrap();
+        assert_eq!(table["q"].as_str(), Some("say \"hi\" # not a comment"));
+        assert_eq!(table["l"].as_str(), Some("C:\\path"));
+    }
+}
@@ -0,0 +1,23 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// True when target was written since source last changed, so deriving it again can be skipped.
+pub fn is_fresh(source: &Path, target: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+    match (modified(source), modified(target)) {
+        (Ok(source), Ok(target)) => target >= source,
+        _ => false,
+    }
+}
+
+// For an external program that did not start: what to install, or which config key names it.
+pub fn spawn_err(program: &str, install: &str, key: &str, err: io::Error) -> io::Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        return io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found; install {} or set {}.", program, install, key),
+        );
+    }
+    io::Error::other(format!("error running {}: {}", program, err))
+}
@@ -0,0 +1,114 @@
+use std::path::{Component, Path};
+
+fn is_path_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(
+            c,
+            b'-' | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+                | b'/'
+                | b'%'
+        )
+}
+
+// Output and URL paths always use '/', whatever separator the platform joined them with.
+pub fn from_path(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    parts.join("/")
+}
+
+pub fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if is_path_char(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+pub fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&path[i + 1..i + 3], 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn is_absolute(url: &str) -> bool {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+                && !rest.is_empty()
+                && !rest.starts_with('/')
+        }
+        None => false,
+    }
+}
+
+// Resolves a reference found on the page at base, as a browser would; dot segments are kept.
+pub fn resolve(base: &str, reference: &str) -> String {
+    if is_absolute(reference) {
+        return reference.to_string();
+    }
+    let base = &base[..base.find('#').unwrap_or(base.len())];
+    let (scheme, rest) = base.split_once("://").unwrap_or(("", base));
+    let origin_len = scheme.len() + 3 + rest.find('/').unwrap_or(rest.len());
+    let origin = &base[..origin_len.min(base.len())];
+    if reference.is_empty() {
+        base.to_string()
+    } else if reference.starts_with("//") {
+        format!("{}:{}", scheme, reference)
+    } else if reference.starts_with('/') {
+        format!("{}{}", origin, reference)
+    } else if reference.starts_with('?') || reference.starts_with('#') {
+        let path = &base[..base.find('?').unwrap_or(base.len())];
+        format!("{}{}", path, reference)
+    } else {
+        let path = &base[origin.len()..base.find('?').unwrap_or(base.len())];
+        let dir = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+        let dir = if dir.is_empty() { "/" } else { dir };
+        format!("{}{}{}", origin, dir, reference)
+    }
+}
+
+pub fn join(base_url: &str, path: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let path = encode_path(path.trim_start_matches('/'));
+    format!("{}/{}", base_url, path)
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::config::{content_root, Config, DiscoveryConfig, SlugCollisions};
+use crate::content::{content_file_metadata, ContentFormat, ContentMeta};
+use crate::discovery::{content_files, glob_match, ContentFile};
+use crate::error::WebWeaverError;
+use crate::ignore::IgnoreFiles;
+use crate::{git, notebook, parallel};
+
+pub struct Problem {
+    pub path: PathBuf,
+    pub check: &'static str,
+    pub message: String,
+}
+
+fn problem(path: &Path, check: &'static str, message: impl Into<String>) -> Problem {
+    Problem {
+        path: path.to_path_buf(),
+        check,
+        message: message.into(),
+    }
+}
+
+// Dot files are editor and tool state, not content.
+fn missing_extensions(
+    dir: &Path,
+    discovery: &DiscoveryConfig,
+    ignores: &mut IgnoreFiles,
+    problems: &mut Vec<Problem>,
+) -> io::Result<()> {
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    ignores.enter(dir)?;
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if discovery
+            .ignore
+            .iter()
+            .any(|pattern| glob_match(pattern, &name))
+        {
+            continue;
+        }
+        let path = entry.path();
+        // Symlinks are left to discovery, which knows when they loop.
+        let file_type = entry.file_type()?;
+        if ignores.is_ignored(&path, file_type.is_dir()) {
+            continue;
+        }
+        if file_type.is_dir() {
+            missing_extensions(&path, discovery, ignores, problems)?;
+        } else if !file_type.is_symlink() && !name.starts_with('.') && path.extension().is_none() {
+            problems.push(problem(
+                &path,
+                "extension",
+                "file has no extension; content files end in .adoc, .md or .org.",
+            ));
+        }
+    }
+    ignores.leave();
+
+    Ok(())
+}
+
+// A prefix shaped like a date that does not parse is a typo, not part of the title.
+fn file_name_date(path: &Path) -> Option<Problem> {
+    let stem = path.file_stem()?.to_str()?;
+    let (prefix, _) = stem.split_once('_')?;
+    let date_shaped = prefix.len() == 10
+        && prefix.chars().enumerate().all(|(i, c)| {
+            if i == 4 || i == 7 {
+                c == '-'
+            } else {
+                c.is_ascii_digit()
+            }
+        });
+    if !date_shaped {
+        return None;
+    }
+    match NaiveDate::parse_from_str(prefix, "%Y-%m-%d") {
+        Ok(_) => None,
+        Err(err) => Some(problem(
+            path,
+            "date",
+            format!("invalid date {} in file name: {}.", prefix, err),
+        )),
+    }
+}
+
+fn check_file(cfg: &Config, content_file: &ContentFile) -> (Vec<Problem>, Option<ContentMeta>) {
+    let path = &content_file.path;
+    // An attachment is not text; only its name and sidecar are checked.
+    if content_file.attachment {
+        return check_metadata(cfg, content_file, Vec::new());
+    }
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => return (vec![problem(path, "read", format!("{}.", err))], None),
+    };
+    let contents = match String::from_utf8(bytes) {
+        Ok(contents) => contents,
+        Err(err) => {
+            return (
+                vec![problem(
+                    path,
+                    "encoding",
+                    format!(
+                        "not valid UTF-8 at byte {}.",
+                        err.utf8_error().valid_up_to()
+                    ),
+                )],
+                None,
+            )
+        }
+    };
+
+    let contents = if notebook::is_notebook(path) {
+        match notebook::to_markdown(&contents) {
+            Ok(markdown) => markdown,
+            Err(err) => return (vec![problem(path, "notebook", format!("{}", err))], None),
+        }
+    } else {
+        contents
+    };
+
+    let mut problems = Vec::new();
+    if contents.trim().is_empty() {
+        problems.push(problem(path, "empty", "file is empty."));
+    }
+
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(ContentFormat::from_ext);
+    if let Some(Err(err)) = format.map(|format| format.split_front_matter(&contents)) {
+        problems.push(problem(path, "front-matter", format!("{}", err)));
+        return (problems, None);
+    }
+
+    check_metadata(cfg, content_file, problems)
+}
+
+fn check_metadata(
+    cfg: &Config,
+    content_file: &ContentFile,
+    mut problems: Vec<Problem>,
+) -> (Vec<Problem>, Option<ContentMeta>) {
+    let path = &content_file.path;
+    if let Some(date) = file_name_date(path) {
+        problems.push(date);
+        return (problems, None);
+    }
+
+    match content_file_metadata(cfg, content_file) {
+        Ok(meta) => (problems, Some(meta)),
+        Err(WebWeaverError::Metadata { message, .. }) => {
+            problems.push(problem(path, "metadata", message));
+            (problems, None)
+        }
+        Err(err) => {
+            problems.push(problem(path, "metadata", err.to_string()));
+            (problems, None)
+        }
+    }
+}
+
+// Checks the whole .content tree, not only the input: a broken file anywhere breaks a `build --all`.
+pub fn validate(cfg: &Config) -> io::Result<(usize, Vec<Problem>)> {
+    let root = content_root(&cfg.input_content_root_path)
+        .unwrap_or_else(|| cfg.input_content_root_path.clone());
+    let discovery = DiscoveryConfig {
+        max_depth: None,
+        ignore: cfg.discovery.ignore.clone(),
+        symlinks: cfg.discovery.symlinks,
+        attachments: cfg.discovery.attachments,
+    };
+
+    let mut problems = Vec::new();
+    missing_extensions(
+        &root,
+        &discovery,
+        &mut IgnoreFiles::default(),
+        &mut problems,
+    )?;
+
+    let mut files = content_files(&root, Path::new(""), &discovery)?;
+    if cfg.build.git_dates {
+        git::set_git_dates(cfg, &root, &mut files)?;
+    }
+    let checked = parallel::map(cfg.build.jobs, &files, |content_file| {
+        check_file(cfg, content_file)
+    });
+
+    let mut outputs: BTreeMap<String, &Path> = BTreeMap::new();
+    for (content_file, (file_problems, meta)) in files.iter().zip(checked) {
+        problems.extend(file_problems);
+        if let Some(meta) = &meta {
+            if meta.protected && meta.password.is_none() && cfg.protected.password.is_empty() {
+                problems.push(problem(
+                    &content_file.path,
+                    "protected",
+                    "protected but has no password; set [protected] password or a front matter password.",
+                ));
+            }
+        }
+        // With slug_collisions = "suffix" the build renames the later post itself,
+        // unless it has a permalink.
+        let (output_path, permalink) = match meta {
+            Some(meta) if meta.permalink.is_some() => (meta.output_path(), true),
+            Some(meta) if cfg.build.slug_collisions == SlugCollisions::Error => {
+                (meta.output_path(), false)
+            }
+            _ => continue,
+        };
+        match outputs.get(&output_path) {
+            Some(first) => problems.push(problem(
+                &content_file.path,
+                "slug",
+                match permalink {
+                    true => format!("permalink writes {} like {}.", output_path, first.display()),
+                    false => format!(
+                        "writes {} like {}; rename one or set [build] slug_collisions = \"suffix\".",
+                        output_path,
+                        first.display()
+                    ),
+                },
+            )),
+            None => {
+                outputs.insert(output_path, &content_file.path);
+            }
+        }
+    }
+
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((files.len(), problems))
+}
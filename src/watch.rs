@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+fn snapshot_into(dir: &Path, snapshot: &mut Snapshot) -> io::Result<()> {
+    for dir_entry in fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            // A file removed between read_dir and stat shows up as a removal next poll.
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            snapshot_into(&path, snapshot)?;
+        } else {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            snapshot.insert(path, (modified, metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+fn snapshot(root: &Path) -> io::Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    snapshot_into(root, &mut snapshot)?;
+    Ok(snapshot)
+}
+
+fn changes(before: &Snapshot, after: &Snapshot) -> Vec<(&'static str, PathBuf)> {
+    let mut changes = Vec::new();
+    for (path, stat) in after {
+        match before.get(path) {
+            None => changes.push(("added", path.clone())),
+            Some(previous) if previous != stat => changes.push(("modified", path.clone())),
+            Some(_) => {}
+        }
+    }
+    for path in before.keys() {
+        if !after.contains_key(path) {
+            changes.push(("removed", path.clone()));
+        }
+    }
+    changes
+}
+
+pub fn watch<F>(root: &Path, interval: Duration, mut rebuild: F) -> io::Result<()>
+where
+    F: FnMut() -> Result<(), Box<dyn Error>>,
+{
+    eprintln!("watching {} for changes (ctrl-c to stop).", root.display());
+    let mut current = snapshot(root)?;
+
+    loop {
+        thread::sleep(interval);
+        let mut next = snapshot(root)?;
+        if changes(&current, &next).is_empty() {
+            continue;
+        }
+
+        // Editors often write a file in several steps; wait for the tree to settle.
+        loop {
+            thread::sleep(interval);
+            let settled = snapshot(root)?;
+            if settled == next {
+                break;
+            }
+            next = settled;
+        }
+
+        for (kind, path) in changes(&current, &next) {
+            eprintln!("{}: {}", kind, path.display());
+        }
+        current = next;
+
+        match rebuild() {
+            Ok(()) => eprintln!("rebuilt."),
+            Err(err) => eprintln!("rebuild failed: {}", err),
+        }
+    }
+}
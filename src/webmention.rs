@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::feed::canonical_url;
+use crate::json;
+use crate::links::{line_urls, RateLimiter};
+use crate::markdown::escape_html;
+use crate::parallel;
+use crate::tools::spawn_err;
+use crate::url;
+use crate::value::{Table, Value};
+use crate::ContentMeta;
+
+const WEBMENTION_STATE_FILE: &str = ".webweaver-webmentions.json";
+const WEBMENTION_STATE_VERSION: i64 = 1;
+
+pub struct Mention {
+    pub source: String,
+    pub target: String,
+}
+
+pub enum MentionStatus {
+    Sent(u16),
+    NoEndpoint,
+    Failed(String),
+}
+
+// The <link> tags that tell other sites where to send webmentions and pingbacks for our pages.
+pub fn endpoint_links(cfg: &Config) -> String {
+    [
+        ("webmention", &cfg.webmention.endpoint),
+        ("pingback", &cfg.webmention.pingback),
+    ]
+    .into_iter()
+    .filter_map(|(rel, href)| {
+        href.as_ref()
+            .map(|href| format!("<link rel=\"{}\" href=\"{}\">\n", rel, escape_html(href)))
+    })
+    .collect()
+}
+
+fn is_own(cfg: &Config, target: &str) -> bool {
+    [&cfg.site.base_url, &cfg.site.canonical_base_url]
+        .into_iter()
+        .filter(|base_url| url::is_absolute(base_url))
+        .any(|base_url| target.starts_with(base_url.trim_end_matches('/')))
+}
+
+// Every external link in each post, from its canonical page; links back to the site are left out,
+// and so are protected posts, whose links are for their readers only, and attachments, which have no text to read.
+pub fn outgoing(cfg: &Config, metas: &BTreeMap<PathBuf, ContentMeta>) -> io::Result<Vec<Mention>> {
+    let mut mentions = Vec::new();
+    for (path, meta) in metas
+        .iter()
+        .filter(|(_, meta)| !meta.protected && meta.attachment.is_none())
+    {
+        let contents = fs::read_to_string(path)?;
+        let source = canonical_url(cfg, meta);
+        let targets: BTreeSet<&str> = contents
+            .lines()
+            .flat_map(line_urls)
+            .filter(|target| !is_own(cfg, target))
+            .collect();
+        mentions.extend(targets.into_iter().map(|target| Mention {
+            source: source.clone(),
+            target: target.to_string(),
+        }));
+    }
+    Ok(mentions)
+}
+
+// The targets each source already notified; only the mentions not in it are sent.
+fn read_state(cfg: &Config) -> BTreeMap<String, BTreeSet<String>> {
+    let contents = match fs::read_to_string(cfg.output_root_path.join(WEBMENTION_STATE_FILE)) {
+        Ok(contents) => contents,
+        Err(_) => return BTreeMap::new(),
+    };
+    let state = match json::parse(&contents) {
+        Ok(state) => state,
+        Err(_) => return BTreeMap::new(),
+    };
+    if state.get("version").and_then(Value::as_integer) != Some(WEBMENTION_STATE_VERSION) {
+        return BTreeMap::new();
+    }
+    match state.get("sent") {
+        Some(Value::Table(sent)) => sent
+            .iter()
+            .map(|(source, targets)| {
+                let targets = targets
+                    .as_array()
+                    .map(|targets| {
+                        targets
+                            .iter()
+                            .filter_map(|target| Some(target.as_str()?.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (source.clone(), targets)
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn save_state(cfg: &Config, sent: &BTreeMap<String, BTreeSet<String>>) -> io::Result<()> {
+    let mut state = Table::new();
+    state.insert(
+        String::from("version"),
+        Value::Integer(WEBMENTION_STATE_VERSION),
+    );
+    state.insert(
+        String::from("sent"),
+        Value::Table(
+            sent.iter()
+                .map(|(source, targets)| {
+                    (
+                        source.clone(),
+                        Value::Array(targets.iter().cloned().map(Value::String).collect()),
+                    )
+                })
+                .collect(),
+        ),
+    );
+    fs::create_dir_all(&cfg.output_root_path)?;
+    fs::write(
+        cfg.output_root_path.join(WEBMENTION_STATE_FILE),
+        json::to_string(&Value::Table(state)),
+    )
+}
+
+fn curl(cfg: &Config) -> Command {
+    let mut command = Command::new(&cfg.links.curl);
+    command
+        .arg("--silent")
+        .arg("--max-time")
+        .arg(cfg.links.timeout_secs.to_string())
+        .arg("--user-agent")
+        .arg("WebWeaver webmention sender");
+    command
+}
+
+// The status and final URL after redirects, the last response's headers and its body.
+fn fetch(cfg: &Config, target: &str) -> io::Result<(u16, String, String, String)> {
+    let output = curl(cfg)
+        .arg("--location")
+        .arg("--dump-header")
+        .arg("-")
+        .arg("--write-out")
+        .arg("\n%{http_code} %{url_effective}")
+        .arg(target)
+        .output()
+        .map_err(|err| spawn_err(&cfg.links.curl, "curl", "[links] curl", err))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (mut response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let (code, effective) = status.split_once(' ').unwrap_or((status, target));
+    let mut headers = "";
+    // Every redirect adds a header block before the final one.
+    while response.starts_with("HTTP/") {
+        let (block, rest) = response
+            .split_once("\r\n\r\n")
+            .or_else(|| response.split_once("\n\n"))
+            .unwrap_or((response, ""));
+        headers = block;
+        response = rest;
+    }
+    Ok((
+        code.trim().parse().unwrap_or(0),
+        effective.trim().to_string(),
+        headers.to_string(),
+        response.to_string(),
+    ))
+}
+
+fn has_rel(rel: &str) -> bool {
+    rel.split_whitespace()
+        .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+}
+
+fn header_endpoint(headers: &str) -> Option<String> {
+    for line in headers.lines() {
+        let (name, value) = match line.split_once(':') {
+            Some(header) => header,
+            None => continue,
+        };
+        if !name.trim().eq_ignore_ascii_case("link") {
+            continue;
+        }
+        for link in value.split(',') {
+            let (href, params) = match link
+                .trim()
+                .strip_prefix('<')
+                .and_then(|link| link.split_once('>'))
+            {
+                Some(link) => link,
+                None => continue,
+            };
+            let webmention = params
+                .split(';')
+                .any(|param| match param.trim().split_once('=') {
+                    Some((name, rel)) if name.trim().eq_ignore_ascii_case("rel") => {
+                        has_rel(rel.trim().trim_matches('"'))
+                    }
+                    _ => false,
+                });
+            if webmention {
+                return Some(href.to_string());
+            }
+        }
+    }
+    None
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let pattern = format!(" {}=", name);
+    let at = lower.find(&pattern)? + pattern.len();
+    let rest = &tag[at..];
+    match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let rest = &rest[1..];
+            Some(&rest[..rest.find(quote)?])
+        }
+        _ => Some(
+            &rest[..rest
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .unwrap_or(rest.len())],
+        ),
+    }
+}
+
+// The first <link> or <a> with rel="webmention", in document order.
+fn html_endpoint(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let mut at = 0;
+    while let Some(start) = lower[at..].find('<').map(|start| at + start) {
+        at = start + 1;
+        let tag = &lower[start + 1..];
+        if !(tag.starts_with("link ") || tag.starts_with("a ")) {
+            continue;
+        }
+        let end = start + lower[start..].find('>').unwrap_or(lower.len() - start);
+        let tag = &body[start..end];
+        if attribute(tag, "rel").is_some_and(has_rel) {
+            if let Some(href) = attribute(tag, "href") {
+                return Some(href.replace("&amp;", "&"));
+            }
+        }
+    }
+    None
+}
+
+fn send(cfg: &Config, limiter: &RateLimiter, mention: &Mention) -> io::Result<MentionStatus> {
+    limiter.wait();
+    let (code, effective, headers, body) = fetch(cfg, &mention.target)?;
+    if code == 0 {
+        return Ok(MentionStatus::Failed(String::from(
+            "no response from target",
+        )));
+    }
+    let endpoint = match header_endpoint(&headers).or_else(|| html_endpoint(&body)) {
+        Some(endpoint) => url::resolve(&effective, &endpoint),
+        None => return Ok(MentionStatus::NoEndpoint),
+    };
+
+    limiter.wait();
+    let output = curl(cfg)
+        .arg("--output")
+        .arg(if cfg!(windows) { "NUL" } else { "/dev/null" })
+        .arg("--write-out")
+        .arg("%{http_code}")
+        .arg("--data-urlencode")
+        .arg(format!("source={}", mention.source))
+        .arg("--data-urlencode")
+        .arg(format!("target={}", mention.target))
+        .arg(&endpoint)
+        .output()
+        .map_err(|err| spawn_err(&cfg.links.curl, "curl", "[links] curl", err))?;
+    let code: u16 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0);
+    Ok(match code {
+        200..=299 => MentionStatus::Sent(code),
+        0 => MentionStatus::Failed(format!("no response from {}", endpoint)),
+        code => MentionStatus::Failed(format!("{} from {}", code, endpoint)),
+    })
+}
+
+// The mentions no earlier run sent; the first run finds every link in every post.
+pub fn unsent(cfg: &Config, mentions: Vec<Mention>) -> Vec<Mention> {
+    let sent = read_state(cfg);
+    mentions
+        .into_iter()
+        .filter(|mention| {
+            !sent
+                .get(&mention.source)
+                .is_some_and(|targets| targets.contains(&mention.target))
+        })
+        .collect()
+}
+
+// Remembers the mentions that went through or found no endpoint, so the next run skips them.
+pub fn send_webmentions(cfg: &Config, mentions: &[Mention]) -> io::Result<Vec<MentionStatus>> {
+    let limiter = RateLimiter::new(cfg.links.rate);
+    let statuses = parallel::map(cfg.build.jobs, mentions, |mention| {
+        send(cfg, &limiter, mention)
+    })
+    .into_iter()
+    .collect::<io::Result<Vec<MentionStatus>>>()?;
+
+    let mut sent = read_state(cfg);
+    for (mention, status) in mentions.iter().zip(&statuses) {
+        if !matches!(status, MentionStatus::Failed(_)) {
+            sent.entry(mention.source.clone())
+                .or_default()
+                .insert(mention.target.clone());
+        }
+    }
+    save_state(cfg, &sent)?;
+    Ok(statuses)
+}
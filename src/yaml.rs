@@ -0,0 +1,333 @@
+use std::io;
+
+use crate::value::{Table, Value};
+
+fn parse_err(line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("yaml parse error at line {}: {}", line, msg),
+    )
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '#' if !in_single && !in_double && (prev == ' ' || prev == '\t' || i == 0) => {
+                return &line[..i];
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn unquote(s: &str, line: usize) -> io::Result<String> {
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        let inner = &s[1..s.len() - 1];
+        let mut out = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some(other) => {
+                    out.push('\\');
+                    out.push(other);
+                }
+                None => return Err(parse_err(line, "dangling escape")),
+            }
+        }
+        return Ok(out);
+    }
+    if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
+        return Ok(s[1..s.len() - 1].replace("''", "'"));
+    }
+    Ok(s.to_string())
+}
+
+fn split_flow_items(inner: &str, line: usize) -> io::Result<Vec<&str>> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut start = 0;
+    // A quote opens only where a value starts, so don't in a plain item is no quote.
+    let mut value_start = true;
+    let mut escaped = false;
+    for (i, c) in inner.char_indices() {
+        let at_value_start = value_start;
+        if !c.is_whitespace() {
+            value_start = matches!(c, '[' | '{' | ',' | ':') && !in_single && !in_double;
+        }
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_double => escaped = true,
+            '\'' if in_single || (!in_double && at_value_start) => in_single = !in_single,
+            '"' if in_double || (!in_single && at_value_start) => in_double = !in_double,
+            '[' | '{' if !in_single && !in_double => depth += 1,
+            ']' | '}' if !in_single && !in_double => {
+                if depth == 0 {
+                    return Err(parse_err(line, &format!("unmatched '{}'", c)));
+                }
+                depth -= 1
+            }
+            ',' if depth == 0 && !in_single && !in_double => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if in_single || in_double {
+        return Err(parse_err(line, "unclosed quote"));
+    }
+    if depth > 0 {
+        return Err(parse_err(line, "unclosed '[' or '{'"));
+    }
+    let last = inner[start..].trim();
+    if !last.is_empty() {
+        items.push(last);
+    }
+    Ok(items)
+}
+
+fn scalar(s: &str, line: usize) -> io::Result<Value> {
+    let s = s.trim();
+
+    for (open, close) in [('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')] {
+        if s.starts_with(open) && (s.len() < 2 || !s.ends_with(close)) {
+            return Err(parse_err(line, &format!("unclosed '{}'", open)));
+        }
+    }
+
+    if s.starts_with('[') && s.ends_with(']') {
+        let values = split_flow_items(&s[1..s.len() - 1], line)?
+            .into_iter()
+            .map(|item| scalar(item, line))
+            .collect::<io::Result<Vec<Value>>>()?;
+        return Ok(Value::Array(values));
+    }
+
+    if s.starts_with('{') && s.ends_with('}') {
+        let mut table = Table::new();
+        for item in split_flow_items(&s[1..s.len() - 1], line)? {
+            let (key, value) = match item.split_once(':') {
+                Some(pair) => pair,
+                None => return Err(parse_err(line, "expected 'key: value' in flow mapping")),
+            };
+            table.insert(unquote(key.trim(), line)?, scalar(value, line)?);
+        }
+        return Ok(Value::Table(table));
+    }
+
+    match s {
+        "~" | "null" | "Null" => return Ok(Value::Null),
+        "true" | "True" | "yes" => return Ok(Value::Boolean(true)),
+        "false" | "False" | "no" => return Ok(Value::Boolean(false)),
+        _ => {}
+    }
+
+    if let Ok(i) = s.parse::<i64>() {
+        return Ok(Value::Integer(i));
+    }
+    if s.contains('.') && !s.contains(|c: char| c.is_alphabetic() && c != 'e' && c != 'E') {
+        if let Ok(f) = s.parse::<f64>() {
+            return Ok(Value::Float(f));
+        }
+    }
+
+    Ok(Value::String(unquote(s, line)?))
+}
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn block_scalar(lines: &[Line], pos: &mut usize, parent_indent: usize, folded: bool) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    while let Some(line) = lines.get(*pos) {
+        if line.indent <= parent_indent {
+            break;
+        }
+        parts.push(line.text);
+        *pos += 1;
+    }
+    let separator = if folded { " " } else { "\n" };
+    let mut out = parts.join(separator).trim_end().to_string();
+    if !folded {
+        out.push('\n');
+    }
+    out
+}
+
+fn block(lines: &[Line], pos: &mut usize, indent: usize) -> io::Result<Value> {
+    let first = match lines.get(*pos) {
+        Some(first) => first,
+        None => return Ok(Value::String(String::new())),
+    };
+
+    if first.text.starts_with("- ") || first.text == "-" {
+        let mut values = Vec::new();
+        while let Some(line) = lines.get(*pos) {
+            if line.indent != indent || !(line.text.starts_with("- ") || line.text == "-") {
+                break;
+            }
+            *pos += 1;
+            let item = line.text[1..].trim();
+            if item.is_empty() {
+                let child_indent = lines.get(*pos).map_or(indent + 2, |l| l.indent);
+                values.push(block(lines, pos, child_indent)?);
+            } else {
+                values.push(scalar(item, line.number)?);
+            }
+        }
+        return Ok(Value::Array(values));
+    }
+
+    let mut table = Table::new();
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent {
+            return Err(parse_err(line.number, "unexpected indentation"));
+        }
+
+        let (key, rest) = match line.text.split_once(':') {
+            Some((key, rest)) if rest.is_empty() || rest.starts_with(' ') => (key, rest.trim()),
+            _ => return Err(parse_err(line.number, "expected 'key: value'")),
+        };
+        let key = unquote(key.trim(), line.number)?;
+        *pos += 1;
+
+        let value = match rest {
+            "" => match lines.get(*pos) {
+                Some(next) if next.indent > indent => block(lines, pos, next.indent)?,
+                Some(next)
+                    if next.indent == indent
+                        && (next.text.starts_with("- ") || next.text == "-") =>
+                {
+                    block(lines, pos, indent)?
+                }
+                _ => Value::String(String::new()),
+            },
+            "|" | "|-" | ">" | ">-" => {
+                let mut s = block_scalar(lines, pos, indent, rest.starts_with('>'));
+                if rest.ends_with('-') {
+                    s.truncate(s.trim_end().len());
+                }
+                Value::String(s)
+            }
+            _ => scalar(rest, line.number)?,
+        };
+
+        if table.insert(key.clone(), value).is_some() {
+            return Err(parse_err(line.number, &format!("duplicate key '{}'", key)));
+        }
+    }
+
+    Ok(Value::Table(table))
+}
+
+pub fn parse(input: &str) -> io::Result<Table> {
+    let lines: Vec<Line> = input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let text = strip_comment(raw).trim_end();
+            if text.trim().is_empty() || text.trim() == "---" {
+                return None;
+            }
+            Some(Line {
+                number: i + 1,
+                indent: indent_of(text),
+                text: text.trim_start(),
+            })
+        })
+        .collect();
+
+    let mut pos = 0;
+    let indent = lines.first().map_or(0, |l| l.indent);
+    match block(&lines, &mut pos, indent)? {
+        Value::Table(table) => {
+            if let Some(line) = lines.get(pos) {
+                return Err(parse_err(line.number, "unexpected content"));
+            }
+            Ok(table)
+        }
+        _ => Err(parse_err(1, "expected a mapping at the top level")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scalars_and_block_lists() {
+        let table = parse("title: Hello\ntags:\n  - a\n  - b\ndraft: false\n").unwrap();
+        assert_eq!(table["title"].as_str(), Some("Hello"));
+        assert_eq!(table["draft"], Value::Boolean(false));
+        let tags: Vec<&str> = table["tags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn escaped_quotes_are_unescaped() {
+        let table = parse("a: \"say \\\"hi\\\"\"\nb: 'it''s'\n").unwrap();
+        assert_eq!(table["a"].as_str(), Some("say \"hi\""));
+        assert_eq!(table["b"].as_str(), Some("it's"));
+    }
+
+    #[test]
+    fn unclosed_flow_collections_and_quotes_are_errors() {
+        for source in [
+            "title: [unclosed",
+            "title: {a: b",
+            "title: \"unclosed",
+            "title: 'unclosed",
+            "tags: [a, [b]",
+            "tags: [a, 'b]",
+        ] {
+            assert!(parse(source).is_err(), "{} parsed", source);
+        }
+    }
+
+    #[test]
+    fn quotes_inside_plain_flow_items_are_text() {
+        let table = parse("tags: [don't, \"a, b\", 'it''s']").unwrap();
+        let tags: Vec<&str> = table["tags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(tags, ["don't", "a, b", "it's"]);
+    }
+}